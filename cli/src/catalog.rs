@@ -0,0 +1,44 @@
+//! `catalog` subcommand: exports every log in an elf, for documentation and review.
+
+use std::io::Write;
+
+use anyhow::Context;
+use cdefmt_parser::{Parser, catalog};
+
+use crate::{CatalogArgs, load_elf};
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum Format {
+    /// Comma separated values, with a header row.
+    Csv,
+    /// A JSON array of logs.
+    Json,
+    /// A Markdown table.
+    Markdown,
+}
+
+pub fn run(args: CatalogArgs) -> anyhow::Result<()> {
+    let elf = load_elf(&args.elf.elf)?;
+    let mut parser = Parser::new(&*elf)?;
+    parser.set_path_map(args.elf.path_map());
+
+    let entries = catalog::collect(&parser)?;
+
+    let mut writer: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(std::io::BufWriter::new(
+            std::fs::File::create(path).with_context(|| format!("Creating {}", path.display()))?,
+        )),
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    match args.format {
+        Format::Csv => catalog::write_csv(&entries, &mut writer)?,
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut writer, &entries)?;
+            writeln!(writer)?;
+        }
+        Format::Markdown => catalog::write_markdown(&entries, &mut writer)?,
+    }
+
+    Ok(writer.flush()?)
+}
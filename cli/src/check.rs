@@ -0,0 +1,28 @@
+//! `check` subcommand: validates that all of the logs in an elf can be decoded.
+
+use cdefmt_parser::Parser;
+
+use crate::{ElfArgs, load_elf};
+
+pub fn run(args: ElfArgs) -> anyhow::Result<()> {
+    let elf = load_elf(&args.elf)?;
+    let mut parser = Parser::new(&*elf)?;
+    parser.set_path_map(args.path_map());
+
+    let start = std::time::Instant::now();
+    let count = parser.log_count();
+    let mut diagnostics = parser.check();
+    let duration = start.elapsed();
+
+    diagnostics.sort_by(|a, b| a.location.cmp(&b.location).then(a.id.cmp(&b.id)));
+    for diagnostic in &diagnostics {
+        println!("{diagnostic}");
+    }
+
+    if diagnostics.is_empty() {
+        println!("{count} logs OK ({}[ms])", duration.as_millis());
+        Ok(())
+    } else {
+        anyhow::bail!("{} problems found in {count} logs", diagnostics.len())
+    }
+}
@@ -0,0 +1,138 @@
+//! `decode` subcommand: decodes frames from the inputs and prints them.
+
+use std::sync::mpsc::Receiver;
+
+use cdefmt_decoder::{
+    store::{Store, StoreDecoder},
+    template::Template,
+};
+
+use crate::{
+    DecodeArgs, ElfArgs, OutputArgs,
+    input::{self, Frame},
+    load_store,
+    output::{Broadcast, Output, Record},
+};
+
+pub fn run(args: DecodeArgs) -> anyhow::Result<()> {
+    let (store, build_id) = load_store(&args.source.elf, &args.source.elf_dir)?;
+    let decoder = decoder(&store, &build_id, &args.source.elf, &args.output)?;
+
+    let multiple_inputs = args.source.input.len() > 1;
+    // Unwrap safety: `decoder` selected an elf.
+    let endianness = decoder.decoder().unwrap().get_endianness();
+    let rx = input::spawn_all(args.source, endianness);
+
+    print(decoder, &store, build_id, rx, args.output, multiple_inputs)
+}
+
+/// Creates a decoder, starting with the elf `build_id`.
+pub fn decoder<'store>(
+    store: &'store Store,
+    build_id: &[u8],
+    elf: &ElfArgs,
+    args: &OutputArgs,
+) -> anyhow::Result<StoreDecoder<'store>> {
+    let mut decoder = StoreDecoder::new(store);
+    decoder.set_path_map(elf.path_map());
+    decoder.set_filter(args.filter.to_filter()?);
+    decoder.set_lenient(args.lenient);
+    decoder.select(build_id)?.precache_log_metadata()?;
+
+    Ok(decoder)
+}
+
+/// Decodes the frames received from `rx` and writes them to the outputs, until the channel is
+/// closed.
+///
+/// `build_id` is the elf initially selected by `decoder`, `tag_sources` prefixes the text format
+/// with each frame's source.
+/// Frames are also tagged once they were received from more than one source, which is how
+/// captures are handled since their sources are only known as they're read.
+pub fn print(
+    mut decoder: StoreDecoder,
+    store: &Store,
+    mut build_id: Vec<u8>,
+    rx: Receiver<anyhow::Result<Frame>>,
+    args: OutputArgs,
+    mut tag_sources: bool,
+) -> anyhow::Result<()> {
+    let stdout = std::io::stdout();
+    let mut template = args.text.to_template(&stdout, &build_id)?;
+    // Remote clients are rarely terminals that understand the escape codes.
+    let mut plain_template = template.clone().with_color(false);
+
+    let mut output = Output::new(args.format, stdout.lock())?;
+    let broadcast = args
+        .serve
+        .as_deref()
+        .map(|address| Broadcast::bind(address, args.serve_format))
+        .transpose()?;
+    if let Some(broadcast) = &broadcast {
+        eprintln!("Serving logs on {}", broadcast.local_addr());
+    }
+
+    let mut first_source = None;
+
+    for frame in rx {
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                eprintln!("Error: {e:?}");
+                continue;
+            }
+        };
+
+        if !tag_sources {
+            tag_sources = *first_source.get_or_insert_with(|| frame.source.clone()) != frame.source;
+        }
+
+        let mut log = match decoder.decode_log_filtered(&frame.data) {
+            Ok(Some(log)) => log,
+            Ok(None) => continue,
+            Err(e) => {
+                eprintln!("Error: {}: {e}", frame.source);
+                continue;
+            }
+        };
+
+        log.set_timestamp(frame.timestamp);
+
+        if let Some(current) = decoder.decoder().map(|d| d.parser().build_id())
+            && current != build_id
+        {
+            // Unwrap safety: the decoder's elf was loaded from the store.
+            eprintln!("Switched to {}", store.path(current).unwrap().display());
+            build_id = current.to_vec();
+            template = match args.text.to_template(&stdout, &build_id) {
+                Ok(template) => template,
+                Err(e) => {
+                    // Linking to the previous elf's commit would be wrong.
+                    eprintln!("Error: {e:#}, source locations aren't linked");
+                    template.with_link(None)
+                }
+            };
+            plain_template = template.clone().with_color(false);
+        }
+
+        let record = Record {
+            source: &frame.source,
+            log: &log,
+        };
+
+        let text = |template: &Template| {
+            if tag_sources {
+                format!("[{}] {}", frame.source, template.render(&log))
+            } else {
+                template.render(&log)
+            }
+        };
+
+        if let Some(broadcast) = &broadcast {
+            broadcast.write(&record, || text(&plain_template));
+        }
+        output.write(&record, || text(&template))?;
+    }
+
+    output.finish()
+}
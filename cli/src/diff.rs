@@ -0,0 +1,79 @@
+//! `diff` subcommand: compares the logs of two elfs.
+
+use cdefmt_parser::{
+    Parser,
+    catalog::{self, Entry},
+    diff::{Change, Field},
+};
+
+use crate::{DiffArgs, load_elf, output::Output, path_map};
+
+pub fn run(args: DiffArgs) -> anyhow::Result<()> {
+    let old_elf = load_elf(&args.old)?;
+    let new_elf = load_elf(&args.new)?;
+
+    let mut old_parser = Parser::new(&*old_elf)?;
+    let mut new_parser = Parser::new(&*new_elf)?;
+    old_parser.set_path_map(path_map(&args.path_map));
+    new_parser.set_path_map(path_map(&args.path_map));
+
+    let old = catalog::collect(&old_parser)?;
+    let new = catalog::collect(&new_parser)?;
+    let changes = cdefmt_parser::diff::diff(&old, &new, args.max_distance);
+
+    let mut output = Output::new(args.format, std::io::stdout().lock())?;
+    for change in &changes {
+        output.write(change, || text(change))?;
+    }
+    output.finish()?;
+
+    if args.format == crate::Format::Text {
+        let count = |f: fn(&Change) -> bool| changes.iter().filter(|c| f(c)).count();
+        println!(
+            "{} added, {} removed, {} changed",
+            count(|c| matches!(c, Change::Added { .. })),
+            count(|c| matches!(c, Change::Removed { .. })),
+            count(|c| matches!(c, Change::Changed { .. })),
+        );
+    }
+
+    Ok(())
+}
+
+fn text(change: &Change) -> String {
+    let describe = |log: &Entry| {
+        format!(
+            "{}:{} {:<7} \"{}\" ({})",
+            log.file,
+            log.line,
+            log.level,
+            log.fmt,
+            log.parameters()
+        )
+    };
+
+    match change {
+        Change::Added { log } => format!("+ {}", describe(log)),
+        Change::Removed { log } => format!("- {}", describe(log)),
+        Change::Changed { old, new, fields } => {
+            let mut text = format!("~ {}:{}", new.file, new.line);
+            if old.line != new.line {
+                text += &format!(" (was line {})", old.line);
+            }
+
+            for field in fields {
+                text += &match field {
+                    Field::Fmt => format!("\n    fmt: \"{}\" -> \"{}\"", old.fmt, new.fmt),
+                    Field::Level => format!("\n    level: {} -> {}", old.level, new.level),
+                    Field::Arguments => format!(
+                        "\n    arguments: ({}) -> ({})",
+                        old.parameters(),
+                        new.parameters()
+                    ),
+                };
+            }
+
+            text
+        }
+    }
+}
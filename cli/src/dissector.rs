@@ -0,0 +1,25 @@
+//! `dissector` subcommand: generates a Wireshark dissector of the logs in an elf.
+
+use std::io::Write;
+
+use anyhow::Context;
+use cdefmt_parser::{Parser, dissector};
+
+use crate::{DissectorArgs, load_elf};
+
+pub fn run(args: DissectorArgs) -> anyhow::Result<()> {
+    let elf = load_elf(&args.elf.elf)?;
+    let mut parser = Parser::new(&*elf)?;
+    parser.set_path_map(args.elf.path_map());
+
+    let mut writer: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(std::io::BufWriter::new(
+            std::fs::File::create(path).with_context(|| format!("Creating {}", path.display()))?,
+        )),
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    dissector::write_lua(&parser, args.link_type, &mut writer)?;
+
+    Ok(writer.flush()?)
+}
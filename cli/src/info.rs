@@ -0,0 +1,25 @@
+//! `info` subcommand: prints information about an elf.
+
+use cdefmt_parser::{Parser, metadata::SCHEMA_VERSION};
+
+use crate::{ElfArgs, load_elf};
+
+pub fn run(args: ElfArgs) -> anyhow::Result<()> {
+    let elf = load_elf(&args.elf)?;
+    let parser = Parser::new(&*elf)?;
+
+    let build_id = parser
+        .build_id()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+
+    println!("elf:          {}", args.elf.display());
+    println!("build id:     {build_id}");
+    println!("endianness:   {:?}", parser.endian());
+    println!("pointer size: {}", parser.address_size().bytes());
+    println!("schema:       {SCHEMA_VERSION}");
+    println!("logs:         {}", parser.iter_logs().count());
+
+    Ok(())
+}
@@ -0,0 +1,232 @@
+//! Input sources that produce raw log frames.
+//!
+//! Every input is read on its own thread, which splits the stream into frames and sends them to
+//! the decoding loop over a channel.
+//! Inputs that accept multiple connections read each connection on a separate thread, and tag
+//! its frames with the peer's address.
+
+use std::{
+    fmt,
+    io::Read,
+    path::PathBuf,
+    str::FromStr,
+    sync::mpsc::{self, Receiver, Sender},
+    thread::JoinHandle,
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use cdefmt_decoder::frame::{FrameDecoder, Framing};
+
+#[cfg(target_os = "linux")]
+use crate::serial;
+pub use crate::serial::SerialConfig;
+use crate::{InputArgs, network};
+
+/// A raw frame, tagged with where and when it was received.
+#[derive(Debug)]
+pub struct Frame {
+    pub source: String,
+    pub timestamp: SystemTime,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Input {
+    Stdin,
+    File(PathBuf),
+    Serial(PathBuf),
+    /// Connects to a TCP server.
+    Tcp(String),
+    /// Accepts TCP connections, each connection is a separate stream.
+    TcpListen(String),
+    /// Receives UDP datagrams, each datagram is a single frame.
+    Udp(String),
+}
+
+impl FromStr for Input {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(if s == "-" {
+            Input::Stdin
+        } else if let Some(path) = s.strip_prefix("file:") {
+            Input::File(path.into())
+        } else if let Some(path) = s.strip_prefix("serial:") {
+            Input::Serial(path.into())
+        } else if let Some(address) = s.strip_prefix("tcp:") {
+            Input::Tcp(address.to_string())
+        } else if let Some(address) = s.strip_prefix("tcp-listen:") {
+            Input::TcpListen(address.to_string())
+        } else if let Some(address) = s.strip_prefix("udp:") {
+            Input::Udp(address.to_string())
+        } else {
+            Input::File(s.into())
+        })
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::Stdin => write!(f, "stdin"),
+            Input::File(path) => write!(f, "{}", path.display()),
+            Input::Serial(path) => write!(f, "serial:{}", path.display()),
+            Input::Tcp(address) => write!(f, "tcp:{address}"),
+            Input::TcpListen(address) => write!(f, "tcp-listen:{address}"),
+            Input::Udp(address) => write!(f, "udp:{address}"),
+        }
+    }
+}
+
+/// Settings shared by all of the inputs.
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub framing: Framing,
+    /// The target's endianness.
+    pub endian: gimli::RunTimeEndian,
+    pub serial: SerialConfig,
+}
+
+impl Input {
+    fn open(&self) -> anyhow::Result<Box<dyn Read + Send>> {
+        Ok(match self {
+            Input::Stdin => Box::new(std::io::stdin()),
+            Input::File(path) => Box::new(
+                std::fs::File::open(path).with_context(|| format!("Opening {}", path.display()))?,
+            ),
+            // Only used on platforms where the line can't be configured.
+            Input::Serial(path) => Box::new(
+                std::fs::File::open(path).with_context(|| format!("Opening {}", path.display()))?,
+            ),
+            Input::Tcp(address) => Box::new(
+                std::net::TcpStream::connect(address)
+                    .with_context(|| format!("Connecting to {address}"))?,
+            ),
+            Input::TcpListen(_) | Input::Udp(_) => anyhow::bail!("{self} isn't a single stream"),
+        })
+    }
+
+    /// Starts reading frames from the input on a new thread.
+    ///
+    /// Frames, and the error that stopped the input (if any), are sent to `tx`.
+    /// The thread exits once the input reaches end of file, serial inputs are reopened instead.
+    pub fn spawn(self, options: Options, tx: Sender<anyhow::Result<Frame>>) -> JoinHandle<()> {
+        std::thread::spawn(move || {
+            let source = self.to_string();
+            let new_frame_decoder = || FrameDecoder::new(options.framing, options.endian);
+
+            let result = match &self {
+                #[cfg(target_os = "linux")]
+                Input::Serial(path) => serial::read_with_reconnect(path, &options.serial, |port| {
+                    // Any partial frame is lost along with the connection, start from scratch.
+                    read_frames(port, new_frame_decoder(), &source, &tx)
+                        .map(|stop| matches!(stop, Stop::Eof))
+                }),
+                Input::TcpListen(address) => network::accept_streams(address, &options, &tx),
+                Input::Udp(address) => network::receive_datagrams(address, &tx),
+                _ => self
+                    .open()
+                    .and_then(|reader| read_frames(reader, new_frame_decoder(), &source, &tx))
+                    .map(|_| ()),
+            };
+
+            if let Err(e) = result {
+                let _ = tx.send(Err(e.context(source)));
+            }
+        })
+    }
+}
+
+/// Starts reading all of the inputs in `args`, `endian` is the target's endianness.
+///
+/// The returned channel is closed once all of the inputs stopped.
+pub fn spawn_all(args: InputArgs, endian: gimli::RunTimeEndian) -> Receiver<anyhow::Result<Frame>> {
+    let (tx, rx) = mpsc::channel();
+    let options = Options {
+        framing: args.framing,
+        endian,
+        serial: args.serial,
+    };
+
+    for input in args.input {
+        input.spawn(options.clone(), tx.clone());
+    }
+
+    rx
+}
+
+/// Why [`read_frames`] stopped.
+pub enum Stop {
+    /// The reader reached end of file.
+    Eof,
+    /// The receiving end of the channel is gone, nobody is interested in more frames.
+    Hangup,
+}
+
+/// Splits the data read from `reader` into frames and sends them to `tx`, tagged with `source`.
+///
+/// Frames the [`FrameDecoder`] rejects are sent as errors, reading goes on with the next frame.
+pub fn read_frames(
+    mut reader: impl Read,
+    mut frames: FrameDecoder,
+    source: &str,
+    tx: &Sender<anyhow::Result<Frame>>,
+) -> anyhow::Result<Stop> {
+    let mut buffer = [0; 4096];
+
+    loop {
+        let len = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(Stop::Eof),
+            Ok(len) => len,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        frames.push(&buffer[..len]);
+        loop {
+            let frame = match frames.next_frame() {
+                Ok(Some(data)) => Ok(Frame {
+                    source: source.to_string(),
+                    timestamp: SystemTime::now(),
+                    data,
+                }),
+                Ok(None) => break,
+                // The bad frame is dropped and the decoder resyncs, keep reading.
+                Err(e) => Err(anyhow::Error::from(e).context(source.to_string())),
+            };
+
+            if tx.send(frame).is_err() {
+                return Ok(Stop::Hangup);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_frames_are_skipped() {
+        // A COBS frame whose code points past its end, between two valid frames.
+        let data: &[u8] = &[0x02, 0x11, 0x00, 0x05, 0x22, 0x00, 0x02, 0x33, 0x00];
+        let frames = FrameDecoder::new(Framing::Cobs, gimli::RunTimeEndian::Little);
+        let (tx, rx) = mpsc::channel();
+
+        assert!(matches!(
+            read_frames(data, frames, "test", &tx),
+            Ok(Stop::Eof)
+        ));
+        drop(tx);
+
+        let received = rx
+            .iter()
+            .map(|frame| frame.map(|frame| frame.data).map_err(|e| e.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            received,
+            [Ok(vec![0x11]), Err("test".to_string()), Ok(vec![0x33])]
+        );
+    }
+}
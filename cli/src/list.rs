@@ -0,0 +1,29 @@
+//! `list` subcommand: dumps all of the logs contained in an elf.
+
+use cdefmt_parser::Parser;
+
+use crate::{ListArgs, load_elf, output::Output};
+
+pub fn run(args: ListArgs) -> anyhow::Result<()> {
+    let elf = load_elf(&args.elf.elf)?;
+    let mut parser = Parser::new(&*elf)?;
+    parser.set_path_map(args.elf.path_map());
+
+    let mut logs = parser
+        .iter_logs()
+        .map(|l| l.map(|(metadata, _)| metadata))
+        .collect::<Result<Vec<_>, _>>()?;
+    logs.sort_by_key(|m| m.id);
+
+    let mut output = Output::new(args.format, std::io::stdout().lock())?;
+    for metadata in &logs {
+        output.write(metadata, || {
+            format!(
+                "{:#010x} {:<7} {}:{} \"{}\"",
+                metadata.id, metadata.level, metadata.file, metadata.line, metadata.fmt
+            )
+        })?;
+    }
+
+    output.finish()
+}
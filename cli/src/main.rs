@@ -0,0 +1,474 @@
+use std::{
+    io::IsTerminal,
+    path::{Path, PathBuf},
+};
+
+use cdefmt_decoder::{
+    Filter,
+    frame::Framing,
+    store::Store,
+    template::{self, ColorChoice, Link, Paths, Template},
+};
+use cdefmt_parser::{metadata::Level, path::PathMap};
+use clap::Parser;
+
+mod catalog;
+mod check;
+mod decode;
+mod diff;
+mod dissector;
+mod info;
+mod input;
+mod list;
+mod network;
+mod output;
+mod record;
+mod replay;
+mod serial;
+mod tui;
+
+use input::Input;
+use output::Format;
+
+/// Decodes and inspects cdefmt logs.
+#[derive(clap::Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Decodes raw log frames and prints them.
+    Decode(Box<DecodeArgs>),
+    /// Records raw log frames to a capture file, without decoding them.
+    Record(RecordArgs),
+    /// Decodes the frames of a capture file.
+    Replay(Box<ReplayArgs>),
+    /// Interactively views decoded logs as they arrive.
+    Tui(TuiArgs),
+    /// Lists all of the logs contained in an elf.
+    List(ListArgs),
+    /// Exports a catalog of all of the logs in an elf, including their argument types.
+    Catalog(CatalogArgs),
+    /// Lists the logs added, removed or changed between two elfs.
+    Diff(DiffArgs),
+    /// Generates a Wireshark Lua dissector of the logs in an elf, for captures recorded as pcapng.
+    Dissector(DissectorArgs),
+    /// Prints information about an elf.
+    Info(ElfArgs),
+    /// Lints all of the logs in an elf, exiting with a non-zero status if any is broken.
+    Check(ElfArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct ElfArgs {
+    /// Path to the original, unstripped, elf that generated the logs.
+    #[arg(short, long)]
+    elf: PathBuf,
+
+    /// Rewrites the prefix of source file paths, may be given multiple times.
+    ///
+    /// Similar to gcc's `-fdebug-prefix-map`, the rewritten paths are also used to find the logs'
+    /// debugging information.
+    #[arg(long = "path-map", value_name = "FROM=TO", value_parser = parse_path_mapping)]
+    path_map: Vec<(String, String)>,
+}
+
+impl ElfArgs {
+    fn path_map(&self) -> PathMap {
+        path_map(&self.path_map)
+    }
+}
+
+fn path_map(mappings: &[(String, String)]) -> PathMap {
+    let mut path_map = PathMap::new();
+    for (from, to) in mappings {
+        path_map.add(from, to);
+    }
+    path_map
+}
+
+fn parse_path_mapping(s: &str) -> Result<(String, String), &'static str> {
+    s.split_once('=')
+        .map(|(from, to)| (from.to_string(), to.to_string()))
+        .ok_or("expected FROM=TO")
+}
+
+/// Where, and how, to read the raw frames from.
+#[derive(clap::Args, Debug)]
+struct InputArgs {
+    #[command(flatten)]
+    elf: ElfArgs,
+
+    /// Where to read the frames from, may be given multiple times.
+    ///
+    /// One of: `-` (stdin), `PATH` or `file:PATH`, `serial:DEVICE`, `tcp:HOST:PORT` (connect),
+    /// `tcp-listen:ADDRESS:PORT` (accept connections) or `udp:ADDRESS:PORT` (one frame per
+    /// datagram).
+    #[arg(short, long, default_value = "-")]
+    input: Vec<Input>,
+
+    /// How frames are delimited in the input: len8, len16, len32, len64 or cobs.
+    ///
+    /// `lenN` frames are preceded by an N bit length in the target's endianness.
+    #[arg(long, default_value = "len64")]
+    framing: Framing,
+
+    #[command(flatten)]
+    serial: input::SerialConfig,
+
+    /// Directory of elfs from other firmware versions, searched recursively, may be given multiple
+    /// times.
+    ///
+    /// Whenever an init log from one of these elfs arrives, the following logs are decoded using
+    /// it, for example when a device is updated in the middle of a capture.
+    #[arg(long, value_name = "DIR")]
+    elf_dir: Vec<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct DecodeArgs {
+    #[command(flatten)]
+    source: InputArgs,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct RecordArgs {
+    #[command(flatten)]
+    source: InputArgs,
+
+    /// Path of the capture file to write.
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Format of the capture file.
+    #[arg(short, long, value_enum, default_value_t = record::Format::Capture)]
+    format: record::Format,
+
+    /// Link type of the frames in pcapng captures, one of the private use link types 147-162.
+    #[arg(long, default_value_t = cdefmt_decoder::pcapng::LINKTYPE_USER0)]
+    link_type: u16,
+}
+
+#[derive(clap::Args, Debug)]
+struct ReplayArgs {
+    /// Path of the capture file to decode.
+    capture: PathBuf,
+
+    #[command(flatten)]
+    elf: ElfArgs,
+
+    /// Directory of elfs from other firmware versions, see `decode --elf-dir`.
+    #[arg(long, value_name = "DIR")]
+    elf_dir: Vec<PathBuf>,
+
+    /// Replays the frames at the pace they were recorded, instead of as fast as possible.
+    #[arg(long)]
+    realtime: bool,
+
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+/// How, and where, decoded logs are written.
+#[derive(clap::Args, Debug)]
+struct OutputArgs {
+    /// Output format of the decoded logs.
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    #[command(flatten)]
+    text: TextArgs,
+
+    #[command(flatten)]
+    filter: FilterArgs,
+
+    /// Decode truncated frames as far as possible, marking the missing arguments `<truncated>`,
+    /// instead of dropping them.
+    #[arg(long)]
+    lenient: bool,
+
+    /// Also republish the decoded logs to TCP clients connecting to this address.
+    #[arg(long, value_name = "ADDRESS:PORT")]
+    serve: Option<String>,
+
+    /// Output format of the logs republished using `--serve`.
+    #[arg(long, value_enum, default_value_t = Format::Ndjson)]
+    serve_format: Format,
+}
+
+/// How logs are rendered in the text format.
+#[derive(clap::Args, Debug)]
+struct TextArgs {
+    /// Template of the text format, for example `{time} {level:>7} {file}:{line} {message}`.
+    ///
+    /// Available fields: timestamp, time, level, file, line, id, fmt and message.
+    #[arg(long, default_value = template::DEFAULT_TEMPLATE)]
+    template: String,
+
+    /// When to colour the text format: auto, always or never.
+    #[arg(long, default_value = "auto")]
+    color: ColorChoice,
+
+    /// Display source file paths relative to this directory.
+    #[arg(long, value_name = "DIR", conflicts_with = "relative")]
+    strip_prefix: Option<PathBuf>,
+
+    /// Display source file paths relative to the current directory.
+    #[arg(long)]
+    relative: bool,
+
+    /// Turn source locations into hyperlinks to this URL, for example
+    /// `https://github.com/org/repo/blob/{commit}/{path}#L{line}`.
+    ///
+    /// Available fields: file, path (as displayed), line, commit and build_id.
+    /// Links are only emitted when colours are, see `--color`.
+    #[arg(long, value_name = "URL", num_args = 0..=1, default_missing_value = "file://{file}")]
+    link: Option<String>,
+
+    /// The commit the elf was built from, used by `--link`.
+    #[arg(long, conflicts_with = "commit_map")]
+    commit: Option<String>,
+
+    /// File mapping build IDs to commits, used by `--link`.
+    ///
+    /// Every line contains a build ID in hex and a commit, separated by whitespace.
+    #[arg(long, value_name = "FILE")]
+    commit_map: Option<PathBuf>,
+}
+
+impl TextArgs {
+    /// Builds the template, colours are enabled according to whether `stream` is a terminal.
+    ///
+    /// `build_id` is the elf's build ID, used to look up the commit for links, failing if it
+    /// isn't in the commit map.
+    fn to_template(&self, stream: &impl IsTerminal, build_id: &[u8]) -> anyhow::Result<Template> {
+        let paths = if let Some(prefix) = &self.strip_prefix {
+            Paths::RelativeTo(prefix.clone())
+        } else if self.relative {
+            Paths::RelativeTo(std::env::current_dir()?)
+        } else {
+            Paths::AsIs
+        };
+
+        let escapes = self.color.enabled(stream);
+        let link = match &self.link {
+            Some(url) if escapes => {
+                let link = Link::new(url)?.with_build_id(build_id);
+                Some(match self.commit(build_id)? {
+                    Some(commit) => link.with_commit(commit),
+                    None => link,
+                })
+            }
+            _ => None,
+        };
+
+        Ok(Template::new(&self.template)?
+            .with_color(escapes)
+            .with_paths(paths)
+            .with_link(link))
+    }
+
+    /// Returns the commit the elf was built from, `None` if neither `--commit` nor `--commit-map`
+    /// was given.
+    fn commit(&self, build_id: &[u8]) -> anyhow::Result<Option<String>> {
+        use anyhow::Context;
+
+        let Some(path) = &self.commit_map else {
+            return Ok(self.commit.clone());
+        };
+
+        let build_id = build_id
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>();
+        let map =
+            std::fs::read_to_string(path).with_context(|| format!("Reading {}", path.display()))?;
+
+        let commit = map
+            .lines()
+            .find_map(|line| {
+                let mut words = line.split_whitespace();
+                let id = words.next()?;
+                id.eq_ignore_ascii_case(&build_id)
+                    .then(|| words.next())
+                    .flatten()
+            })
+            .with_context(|| format!("Build ID {build_id} not found in {}", path.display()))?;
+
+        Ok(Some(commit.to_string()))
+    }
+}
+
+/// Logs that don't match are dropped before their arguments are decoded.
+#[derive(clap::Args, Debug)]
+struct FilterArgs {
+    /// Only print logs at this level or more severe.
+    #[arg(short, long, default_value = "verbose")]
+    level: Level,
+
+    /// Only print logs from files matching this glob, may be given multiple times.
+    #[arg(long = "file", value_name = "GLOB")]
+    files: Vec<String>,
+
+    /// Only print logs whose format string matches this regular expression.
+    #[arg(long, value_name = "REGEX")]
+    fmt: Option<String>,
+
+    /// Only print the log with this id, may be given multiple times.
+    #[arg(long = "id", value_name = "ID", value_parser = parse_id)]
+    ids: Vec<usize>,
+
+    /// Never print the log with this id, may be given multiple times.
+    #[arg(long = "exclude-id", value_name = "ID", value_parser = parse_id)]
+    excluded_ids: Vec<usize>,
+}
+
+impl FilterArgs {
+    fn to_filter(&self) -> anyhow::Result<Filter> {
+        let mut filter = Filter::new()
+            .with_level(self.level)
+            .with_denied_ids(self.excluded_ids.iter().copied());
+
+        if !self.files.is_empty() {
+            filter = filter.with_files(&self.files)?;
+        }
+        if let Some(fmt) = &self.fmt {
+            filter = filter.with_fmt(fmt)?;
+        }
+        if !self.ids.is_empty() {
+            filter = filter.with_allowed_ids(self.ids.iter().copied());
+        }
+
+        Ok(filter)
+    }
+}
+
+/// Parses a log id, in hex (`0x1a0`, as printed by `list`) or decimal.
+fn parse_id(s: &str) -> Result<usize, std::num::ParseIntError> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct TuiArgs {
+    #[command(flatten)]
+    source: InputArgs,
+
+    /// Maximum number of logs kept for scrolling back, older logs are discarded.
+    #[arg(long, default_value_t = 100_000)]
+    scrollback: usize,
+}
+
+#[derive(clap::Args, Debug)]
+struct ListArgs {
+    #[command(flatten)]
+    elf: ElfArgs,
+
+    /// Output format of the log list.
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+#[derive(clap::Args, Debug)]
+struct DiffArgs {
+    /// The elf of the previous version.
+    old: PathBuf,
+
+    /// The elf of the new version.
+    new: PathBuf,
+
+    /// Rewrites the prefix of source file paths in both elfs, may be given multiple times.
+    ///
+    /// Logs are matched by their source file, so both elfs' paths have to be mapped to the same
+    /// prefix if they were built in different directories.
+    #[arg(long = "path-map", value_name = "FROM=TO", value_parser = parse_path_mapping)]
+    path_map: Vec<(String, String)>,
+
+    /// Maximum distance, in lines, between logs whose format string changed, to still be
+    /// considered the same log.
+    #[arg(long, default_value_t = cdefmt_parser::diff::DEFAULT_MAX_DISTANCE)]
+    max_distance: usize,
+
+    /// Output format of the changes.
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+#[derive(clap::Args, Debug)]
+struct CatalogArgs {
+    #[command(flatten)]
+    elf: ElfArgs,
+
+    /// Output format of the catalog.
+    #[arg(short, long, value_enum, default_value_t = catalog::Format::Csv)]
+    format: catalog::Format,
+
+    /// Writes the catalog to a file instead of stdout.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct DissectorArgs {
+    #[command(flatten)]
+    elf: ElfArgs,
+
+    /// Link type the dissector is registered for, see `record --link-type`.
+    #[arg(long, default_value_t = cdefmt_parser::dissector::LINKTYPE_USER0)]
+    link_type: u16,
+
+    /// Writes the dissector to a file instead of stdout.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+fn main() {
+    if let Err(e) = main_impl() {
+        eprintln!("Error: {e:?}");
+        std::process::exit(1);
+    }
+}
+
+fn main_impl() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Decode(args) => decode::run(*args),
+        Command::Record(args) => record::run(args),
+        Command::Replay(args) => replay::run(*args),
+        Command::Tui(args) => tui::run(args),
+        Command::List(args) => list::run(args),
+        Command::Catalog(args) => catalog::run(args),
+        Command::Diff(args) => diff::run(args),
+        Command::Dissector(args) => dissector::run(args),
+        Command::Info(args) => info::run(args),
+        Command::Check(args) => check::run(args),
+    }
+}
+
+/// Loads the elf and the elf directories into a store, returning the elf's build ID.
+fn load_store(elf: &ElfArgs, elf_dirs: &[PathBuf]) -> anyhow::Result<(Store, Vec<u8>)> {
+    let mut store = Store::new();
+    for dir in elf_dirs {
+        store.add_dir(dir)?;
+    }
+    let build_id = store.add_file(&elf.elf)?;
+
+    Ok((store, build_id))
+}
+
+/// Memory maps the elf at `path`.
+fn load_elf(path: &Path) -> anyhow::Result<memmap2::Mmap> {
+    use anyhow::Context;
+
+    let file = std::fs::File::open(path).with_context(|| format!("Opening {}", path.display()))?;
+    Ok(unsafe { memmap2::Mmap::map(&file) }?)
+}
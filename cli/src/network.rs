@@ -0,0 +1,76 @@
+//! Network inputs that accept frames from multiple peers.
+
+use std::{
+    net::{TcpListener, UdpSocket},
+    sync::mpsc::Sender,
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use cdefmt_decoder::frame::FrameDecoder;
+
+use crate::input::{self, Frame, Options, Stop};
+
+/// Receives UDP datagrams on `address`, each datagram is a single frame.
+///
+/// Frames are tagged with the address of the peer that sent them.
+pub fn receive_datagrams(address: &str, tx: &Sender<anyhow::Result<Frame>>) -> anyhow::Result<()> {
+    let socket = UdpSocket::bind(address).with_context(|| format!("Binding to {address}"))?;
+
+    // Large enough for any UDP datagram.
+    let mut buffer = vec![0; u16::MAX as usize];
+
+    loop {
+        let (len, peer) = socket.recv_from(&mut buffer)?;
+
+        let frame = Frame {
+            source: format!("udp:{peer}"),
+            timestamp: SystemTime::now(),
+            data: buffer[..len].to_vec(),
+        };
+
+        if tx.send(Ok(frame)).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+/// Accepts TCP connections on `address`, every connection is read on its own thread as a
+/// separate stream.
+///
+/// Frames are tagged with the address of the peer that sent them.
+pub fn accept_streams(
+    address: &str,
+    options: &Options,
+    tx: &Sender<anyhow::Result<Frame>>,
+) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(address).with_context(|| format!("Binding to {address}"))?;
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("tcp-listen:{address}: {e}");
+                continue;
+            }
+        };
+
+        let source = match stream.peer_addr() {
+            Ok(peer) => format!("tcp:{peer}"),
+            Err(_) => "tcp:<unknown>".to_string(),
+        };
+        eprintln!("{source}: connected");
+
+        let frames = FrameDecoder::new(options.framing, options.endian);
+        let tx = tx.clone();
+        std::thread::spawn(
+            move || match input::read_frames(stream, frames, &source, &tx) {
+                Ok(Stop::Eof) => eprintln!("{source}: disconnected"),
+                Ok(Stop::Hangup) => {}
+                Err(e) => eprintln!("{source}: disconnected ({e:#})"),
+            },
+        );
+    }
+
+    Ok(())
+}
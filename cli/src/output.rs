@@ -0,0 +1,246 @@
+//! Writing decoded logs in the different output formats.
+
+use std::{
+    io::Write,
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, SyncSender},
+    },
+    time::Duration,
+};
+
+use anyhow::Context;
+
+use cdefmt_decoder::log::Log;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Human readable text.
+    Text,
+    /// A single JSON array, written incrementally.
+    Json,
+    /// One JSON object per line.
+    Ndjson,
+}
+
+/// Writes a stream of serializable records in the requested format.
+pub struct Output<W: Write> {
+    format: Format,
+    writer: W,
+    empty: bool,
+}
+
+impl<W: Write> Output<W> {
+    pub fn new(format: Format, mut writer: W) -> anyhow::Result<Self> {
+        if format == Format::Json {
+            writeln!(writer, "[")?;
+        }
+
+        Ok(Self {
+            format,
+            writer,
+            empty: true,
+        })
+    }
+
+    /// Writes a single record, `text` renders it for the text format.
+    pub fn write<T: serde::Serialize>(
+        &mut self,
+        record: &T,
+        text: impl FnOnce() -> String,
+    ) -> anyhow::Result<()> {
+        match self.format {
+            Format::Text => writeln!(self.writer, "{}", text())?,
+            Format::Json => {
+                if !self.empty {
+                    writeln!(self.writer, ",")?;
+                }
+                serde_json::to_writer_pretty(&mut self.writer, record)?;
+            }
+            Format::Ndjson => {
+                serde_json::to_writer(&mut self.writer, record)?;
+                writeln!(self.writer)?;
+            }
+        }
+
+        self.empty = false;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Terminates the output, must be called once all of the records were written.
+    pub fn finish(mut self) -> anyhow::Result<()> {
+        if self.format == Format::Json {
+            if !self.empty {
+                writeln!(self.writer)?;
+            }
+            writeln!(self.writer, "]")?;
+        }
+
+        Ok(self.writer.flush()?)
+    }
+}
+
+/// How many records may be queued for a client before it's considered stalled and disconnected.
+const CLIENT_QUEUE_SIZE: usize = 1024;
+
+/// How long writing to a client may block before it's disconnected.
+const CLIENT_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Writes records to all of the clients connected to a TCP server.
+///
+/// Each client is written to by its own thread, from a bounded queue, so a slow client can't hold
+/// back the decoding or the other clients. Clients whose queue fills up are disconnected.
+pub struct Broadcast {
+    format: Format,
+    address: SocketAddr,
+    clients: Arc<Mutex<Vec<SyncSender<Message>>>>,
+}
+
+/// A record, rendered once for all of the clients.
+#[derive(Clone)]
+enum Message {
+    Text(Arc<str>),
+    Json(Arc<serde_json::Value>),
+}
+
+impl Broadcast {
+    /// Starts accepting clients on `address`, every client receives the records written after it
+    /// connected.
+    pub fn bind(address: &str, format: Format) -> anyhow::Result<Self> {
+        let listener =
+            TcpListener::bind(address).with_context(|| format!("Binding to {address}"))?;
+        let address = listener.local_addr()?;
+        let clients = Arc::new(Mutex::new(Vec::new()));
+
+        let accepted = clients.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // Disable Nagle's algorithm, logs are small and should arrive as soon as possible.
+                let _ = stream.set_nodelay(true);
+                let _ = stream.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT));
+                if let Ok(output) = Output::new(format, stream) {
+                    let (tx, rx) = mpsc::sync_channel(CLIENT_QUEUE_SIZE);
+                    std::thread::spawn(move || write_client(output, rx));
+                    accepted.lock().unwrap().push(tx);
+                }
+            }
+        });
+
+        Ok(Self {
+            format,
+            address,
+            clients,
+        })
+    }
+
+    /// The address clients connect to, useful when binding to port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Queues a single record to all of the clients, clients that failed or stalled are
+    /// disconnected.
+    pub fn write<T: serde::Serialize>(&self, record: &T, text: impl FnOnce() -> String) {
+        let mut clients = self.clients.lock().unwrap();
+        if clients.is_empty() {
+            return;
+        }
+
+        let message = match self.format {
+            Format::Text => Message::Text(text().into()),
+            Format::Json | Format::Ndjson => match serde_json::to_value(record) {
+                Ok(value) => Message::Json(Arc::new(value)),
+                Err(e) => {
+                    eprintln!("Error: serializing the record: {e}");
+                    return;
+                }
+            },
+        };
+
+        // A full queue means the client isn't keeping up, dropping its sender disconnects it.
+        clients.retain(|client| client.try_send(message.clone()).is_ok());
+    }
+}
+
+/// Writes the records queued for a client, until it fails or is disconnected.
+fn write_client(mut output: Output<TcpStream>, rx: Receiver<Message>) {
+    for message in rx {
+        let result = match &message {
+            Message::Text(text) => output.write(&(), || text.to_string()),
+            Message::Json(value) => output.write(value.as_ref(), String::new),
+        };
+        if result.is_err() {
+            return;
+        }
+    }
+
+    let _ = output.finish();
+}
+
+/// A decoded log along with the input it was received from.
+#[derive(serde::Serialize)]
+pub struct Record<'a, 'elf> {
+    pub source: &'a str,
+    #[serde(flatten)]
+    pub log: &'a Log<'elf>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Instant,
+    };
+
+    use super::*;
+
+    fn connected(broadcast: &Broadcast) -> usize {
+        broadcast.clients.lock().unwrap().len()
+    }
+
+    #[test]
+    fn stalled_client_is_disconnected() {
+        let broadcast = Broadcast::bind("127.0.0.1:0", Format::Text).unwrap();
+        let stalled = TcpStream::connect(broadcast.local_addr()).unwrap();
+        let reader = TcpStream::connect(broadcast.local_addr()).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while connected(&broadcast) < 2 {
+            assert!(Instant::now() < deadline, "The clients weren't accepted");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let records = 20_000;
+        let received = Arc::new(AtomicUsize::new(0));
+        let counter = received.clone();
+        let reading = std::thread::spawn(move || {
+            for _ in BufReader::new(reader).lines().take(records) {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        // The stalled client never reads, once the socket's buffers and its queue are full it's
+        // disconnected, without ever blocking the writer.
+        let line = "x".repeat(1024);
+        for i in 0..records {
+            let start = Instant::now();
+            broadcast.write(&(), || line.clone());
+            assert!(start.elapsed() < Duration::from_secs(1));
+
+            // Keep the reading client's queue from filling up.
+            while i.saturating_sub(received.load(Ordering::Relaxed)) > CLIENT_QUEUE_SIZE / 2 {
+                assert!(Instant::now() < deadline + Duration::from_secs(30));
+                std::thread::yield_now();
+            }
+        }
+
+        assert_eq!(connected(&broadcast), 1);
+        reading.join().unwrap();
+        assert_eq!(received.load(Ordering::Relaxed), records);
+        drop(stalled);
+    }
+}
@@ -0,0 +1,80 @@
+//! `record` subcommand: saves the raw frames from the inputs to a capture file.
+
+use std::{fs::File, io::BufWriter, time::SystemTime};
+
+use anyhow::Context;
+use cdefmt_decoder::{
+    capture::{self, Header},
+    pcapng,
+};
+use cdefmt_parser::Parser;
+
+use crate::{RecordArgs, input, load_elf};
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum Format {
+    /// cdefmt's own capture format, which can be decoded using `replay`.
+    Capture,
+    /// pcapng, for inspection in Wireshark using the dissector generated by `dissector`.
+    Pcapng,
+}
+
+enum Writer {
+    Capture(capture::Writer<BufWriter<File>>),
+    Pcapng(pcapng::Writer<BufWriter<File>>),
+}
+
+impl Writer {
+    fn write(&mut self, timestamp: SystemTime, source: &str, data: &[u8]) -> anyhow::Result<()> {
+        match self {
+            Writer::Capture(writer) => Ok(writer.write(timestamp, source, data)?),
+            Writer::Pcapng(writer) => Ok(writer.write(timestamp, source, data)?),
+        }
+    }
+
+    fn flush(&mut self) -> anyhow::Result<()> {
+        match self {
+            Writer::Capture(writer) => Ok(writer.flush()?),
+            Writer::Pcapng(writer) => Ok(writer.flush()?),
+        }
+    }
+}
+
+pub fn run(args: RecordArgs) -> anyhow::Result<()> {
+    let elf = load_elf(&args.source.elf.elf)?;
+    let parser = Parser::new(&*elf)?;
+
+    let file = File::create(&args.output)
+        .with_context(|| format!("Creating {}", args.output.display()))?;
+    let file = BufWriter::new(file);
+    let mut writer = match args.format {
+        Format::Capture => {
+            let header = Header {
+                build_ids: vec![parser.build_id().to_vec()],
+            };
+            Writer::Capture(capture::Writer::new(file, &header)?)
+        }
+        Format::Pcapng => Writer::Pcapng(pcapng::Writer::new(file, args.link_type)?),
+    };
+
+    let rx = input::spawn_all(args.source, parser.endian());
+    let mut count = 0;
+
+    while let Ok(frame) = rx.recv() {
+        // Write whatever else already arrived before flushing, so bursts don't cost a write each.
+        for frame in std::iter::once(frame).chain(rx.try_iter()) {
+            match frame {
+                Ok(frame) => {
+                    writer.write(frame.timestamp, &frame.source, &frame.data)?;
+                    count += 1;
+                }
+                Err(e) => eprintln!("Error: {e:?}"),
+            }
+        }
+        writer.flush()?;
+    }
+
+    eprintln!("Recorded {count} frames to {}", args.output.display());
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+//! `replay` subcommand: decodes the frames of a capture file.
+
+use std::{sync::mpsc, time::Instant};
+
+use anyhow::Context;
+use cdefmt_decoder::capture::{self, Reader};
+
+use crate::{ReplayArgs, decode, input::Frame, load_store};
+
+pub fn run(args: ReplayArgs) -> anyhow::Result<()> {
+    let file = std::fs::File::open(&args.capture)
+        .with_context(|| format!("Opening {}", args.capture.display()))?;
+    let reader = Reader::new(std::io::BufReader::new(file))
+        .with_context(|| format!("Reading {}", args.capture.display()))?;
+    let (store, build_id) = load_store(&args.elf, &args.elf_dir)?;
+
+    let recorded = &reader.header().build_ids;
+    if !recorded.is_empty() && !recorded.iter().any(|id| store.get(id).is_some()) {
+        eprintln!("Warning: none of the elfs the capture was recorded with are available");
+    }
+
+    let decoder = decode::decoder(&store, &build_id, &args.elf, &args.output)?;
+    let (tx, rx) = mpsc::channel();
+    let realtime = args.realtime;
+
+    std::thread::spawn(move || {
+        let mut start = None;
+
+        for frame in reader {
+            let frame = frame
+                .map_err(anyhow::Error::from)
+                .map(|frame: capture::Frame| {
+                    if realtime {
+                        let (instant, timestamp) =
+                            *start.get_or_insert((Instant::now(), frame.timestamp));
+                        if let Ok(offset) = frame.timestamp.duration_since(timestamp) {
+                            std::thread::sleep(
+                                (instant + offset).saturating_duration_since(Instant::now()),
+                            );
+                        }
+                    }
+
+                    Frame {
+                        source: frame.source,
+                        timestamp: frame.timestamp,
+                        data: frame.data,
+                    }
+                });
+
+            let stop = frame.is_err();
+            if tx.send(frame).is_err() || stop {
+                break;
+            }
+        }
+    });
+
+    // The logs are tagged once frames from another source show up.
+    decode::print(decoder, &store, build_id, rx, args.output, false)
+}
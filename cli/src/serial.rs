@@ -0,0 +1,284 @@
+//! Serial port input, configured using termios.
+//!
+//! Configuring the line is only supported on Linux, on other platforms serial devices are read
+//! as is and have to be configured beforehand, for example using `stty`.
+
+#[cfg(target_os = "linux")]
+use std::{fs::File, io::Read, os::unix::fs::OpenOptionsExt, path::Path};
+
+#[cfg(target_os = "linux")]
+use anyhow::Context;
+#[cfg(target_os = "linux")]
+use nix::{
+    fcntl::OFlag,
+    sys::termios::{self, BaudRate, ControlFlags, InputFlags, SetArg, SpecialCharacterIndices},
+};
+
+/// Line settings of serial inputs.
+#[derive(clap::Args, Clone, Debug)]
+pub struct SerialConfig {
+    /// Baud rate of serial inputs.
+    #[arg(long, default_value_t = 115200)]
+    pub baud: u32,
+
+    /// Parity of serial inputs.
+    #[arg(long, value_enum, default_value_t = Parity::None)]
+    pub parity: Parity,
+
+    /// Flow control of serial inputs.
+    #[arg(long, value_enum, default_value_t = FlowControl::None)]
+    pub flow_control: FlowControl,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum Parity {
+    None,
+    Even,
+    Odd,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum FlowControl {
+    None,
+    /// RTS/CTS.
+    Hardware,
+    /// XON/XOFF.
+    Software,
+}
+
+/// An open serial port in raw mode.
+#[cfg(target_os = "linux")]
+pub struct SerialPort {
+    file: File,
+}
+
+#[cfg(target_os = "linux")]
+impl SerialPort {
+    /// Opens the serial device at `path` and configures it according to `config`.
+    pub fn open(path: &Path, config: &SerialConfig) -> anyhow::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(OFlag::O_NOCTTY.bits())
+            .open(path)
+            .with_context(|| format!("Opening {}", path.display()))?;
+
+        let mut tty = termios::tcgetattr(&file)?;
+        termios::cfmakeraw(&mut tty);
+        termios::cfsetspeed(&mut tty, baud_rate(config.baud)?)?;
+
+        // 8 data bits, ignore modem control lines.
+        tty.control_flags |= ControlFlags::CS8 | ControlFlags::CREAD | ControlFlags::CLOCAL;
+        tty.control_flags &= !ControlFlags::CSTOPB;
+
+        match config.parity {
+            Parity::None => tty.control_flags &= !(ControlFlags::PARENB | ControlFlags::PARODD),
+            Parity::Even => {
+                tty.control_flags |= ControlFlags::PARENB;
+                tty.control_flags &= !ControlFlags::PARODD;
+            }
+            Parity::Odd => tty.control_flags |= ControlFlags::PARENB | ControlFlags::PARODD,
+        }
+
+        tty.control_flags &= !ControlFlags::CRTSCTS;
+        tty.input_flags &= !(InputFlags::IXON | InputFlags::IXOFF | InputFlags::IXANY);
+        match config.flow_control {
+            FlowControl::None => {}
+            FlowControl::Hardware => tty.control_flags |= ControlFlags::CRTSCTS,
+            FlowControl::Software => tty.input_flags |= InputFlags::IXON | InputFlags::IXOFF,
+        }
+
+        // Block until at least a single byte is available.
+        tty.control_chars[SpecialCharacterIndices::VMIN as usize] = 1;
+        tty.control_chars[SpecialCharacterIndices::VTIME as usize] = 0;
+
+        termios::tcsetattr(&file, SetArg::TCSANOW, &tty)
+            .with_context(|| format!("Configuring {}", path.display()))?;
+
+        Ok(Self { file })
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Read for SerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+/// Reads from the serial device at `path`, reopening it whenever it disappears, for example when
+/// a USB device re-enumerates.
+///
+/// `read` is called with every successfully opened port, and should return once the port stops
+/// producing data, or with the error that stopped it.
+/// If it returns `Ok(false)`, no more data is wanted and the device isn't reopened.
+///
+/// Only returns an error if the configuration itself is invalid.
+#[cfg(target_os = "linux")]
+pub fn read_with_reconnect(
+    path: &Path,
+    config: &SerialConfig,
+    mut read: impl FnMut(SerialPort) -> anyhow::Result<bool>,
+) -> anyhow::Result<()> {
+    const RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+    // Catch configuration errors early, rather than retrying forever.
+    baud_rate(config.baud)?;
+
+    // Whether the device is gone and we're waiting for it to come back.
+    let mut waiting = false;
+
+    loop {
+        match SerialPort::open(path, config) {
+            Ok(port) => {
+                if waiting {
+                    eprintln!("{}: reconnected", path.display());
+                }
+
+                match read(port) {
+                    Ok(false) => return Ok(()),
+                    Ok(true) => {
+                        eprintln!("{}: disconnected, waiting for device...", path.display())
+                    }
+                    // Unplugging the device usually results in an I/O error.
+                    Err(e) => eprintln!(
+                        "{}: disconnected ({e:#}), waiting for device...",
+                        path.display()
+                    ),
+                }
+                waiting = true;
+            }
+            Err(e) => {
+                if !waiting {
+                    eprintln!("{}: {e:#}, waiting for device...", path.display());
+                    waiting = true;
+                }
+            }
+        }
+
+        std::thread::sleep(RETRY_INTERVAL);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn baud_rate(baud: u32) -> anyhow::Result<BaudRate> {
+    Ok(match baud {
+        1200 => BaudRate::B1200,
+        2400 => BaudRate::B2400,
+        4800 => BaudRate::B4800,
+        9600 => BaudRate::B9600,
+        19200 => BaudRate::B19200,
+        38400 => BaudRate::B38400,
+        57600 => BaudRate::B57600,
+        115200 => BaudRate::B115200,
+        230400 => BaudRate::B230400,
+        460800 => BaudRate::B460800,
+        500000 => BaudRate::B500000,
+        576000 => BaudRate::B576000,
+        921600 => BaudRate::B921600,
+        1000000 => BaudRate::B1000000,
+        1152000 => BaudRate::B1152000,
+        1500000 => BaudRate::B1500000,
+        2000000 => BaudRate::B2000000,
+        _ => anyhow::bail!("Unsupported baud rate: {baud}"),
+    })
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::{fs, io::Write, os::unix::fs::symlink, path::PathBuf, sync::mpsc, time::Duration};
+
+    use nix::{
+        pty::{OpenptyResult, openpty},
+        sys::termios::{LocalFlags, cfgetispeed},
+        unistd::ttyname,
+    };
+
+    use super::*;
+
+    const CONFIG: SerialConfig = SerialConfig {
+        baud: 57600,
+        parity: Parity::Even,
+        flow_control: FlowControl::None,
+    };
+
+    /// Opens a pty pair, pointing `link` at its slave side, like the `/dev/serial/by-id` links.
+    fn pty(link: &Path) -> File {
+        let OpenptyResult { master, slave } = openpty(None, None).unwrap();
+        let _ = fs::remove_file(link);
+        symlink(ttyname(&slave).unwrap(), link).unwrap();
+        File::from(master)
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("cdefmt-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn configures_the_line() {
+        let link = temp_path("serial-config");
+        let mut master = pty(&link);
+
+        let mut port = SerialPort::open(&link, &CONFIG).unwrap();
+        let tty = termios::tcgetattr(&port.file).unwrap();
+        assert_eq!(cfgetispeed(&tty), BaudRate::B57600);
+        // Ptys always use 8 bits without parity, so the parity can't be checked here.
+        assert!(tty.control_flags.contains(ControlFlags::CS8));
+        assert!(
+            !tty.local_flags
+                .intersects(LocalFlags::ICANON | LocalFlags::ECHO)
+        );
+
+        // Raw mode, so bytes such as newlines and ^C go through untouched.
+        let frame = [0x02, 0x0a, 0x03, 0x0d, 0x00];
+        master.write_all(&frame).unwrap();
+        let mut received = [0; 5];
+        port.read_exact(&mut received).unwrap();
+        assert_eq!(received, frame);
+
+        fs::remove_file(link).unwrap();
+    }
+
+    #[test]
+    fn reconnects() {
+        let link = temp_path("serial-reconnect");
+        let master = pty(&link);
+
+        let (tx, rx) = mpsc::channel();
+        let reader = {
+            let link = link.clone();
+            std::thread::spawn(move || {
+                let mut connections = 0;
+                read_with_reconnect(&link, &CONFIG, |mut port| {
+                    connections += 1;
+                    let mut buffer = [0; 64];
+                    loop {
+                        match port.read(&mut buffer) {
+                            Ok(0) => return Ok(true),
+                            Ok(len) => tx.send(buffer[..len].to_vec()).unwrap(),
+                            Err(e) => return Err(e.into()),
+                        }
+                        // Stop once the second device sent its data.
+                        if connections == 2 {
+                            return Ok(false);
+                        }
+                    }
+                })
+            })
+        };
+
+        let write = |mut master: &File, data: &[u8]| {
+            master.write_all(data).unwrap();
+            rx.recv_timeout(Duration::from_secs(5)).unwrap()
+        };
+        assert_eq!(write(&master, b"first"), b"first");
+
+        // Unplug the device, and plug another one in at the same path.
+        drop(master);
+        let master = pty(&link);
+        assert_eq!(write(&master, b"second"), b"second");
+
+        reader.join().unwrap().unwrap();
+        fs::remove_file(link).unwrap();
+    }
+}
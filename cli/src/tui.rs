@@ -0,0 +1,90 @@
+//! `tui` subcommand: an interactive viewer of the decoded logs.
+
+mod app;
+mod ui;
+
+use std::{
+    sync::mpsc::{Receiver, TryRecvError},
+    time::{Duration, SystemTime},
+};
+
+use cdefmt_decoder::store::StoreDecoder;
+use ratatui::{
+    DefaultTerminal,
+    crossterm::event::{self, Event, KeyEventKind},
+};
+
+use crate::{TuiArgs, input, load_store};
+use app::App;
+
+/// How long to wait for key presses before checking for new frames.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Maximum number of frames decoded between redraws, keeps the viewer responsive under load.
+const FRAMES_PER_REDRAW: usize = 10_000;
+
+pub fn run(args: TuiArgs) -> anyhow::Result<()> {
+    let (store, build_id) = load_store(&args.source.elf, &args.source.elf_dir)?;
+    let mut decoder = StoreDecoder::new(&store);
+    decoder.set_path_map(args.source.elf.path_map());
+    let endianness = {
+        let decoder = decoder.select(&build_id)?;
+        decoder.precache_log_metadata()?;
+        decoder.get_endianness()
+    };
+
+    let rx = input::spawn_all(args.source, endianness);
+    let mut app = App::new(args.scrollback);
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, &mut app, &mut decoder, &rx);
+    ratatui::restore();
+
+    result
+}
+
+fn event_loop<'elf>(
+    terminal: &mut DefaultTerminal,
+    app: &mut App<'elf>,
+    decoder: &mut StoreDecoder<'elf>,
+    rx: &Receiver<anyhow::Result<input::Frame>>,
+) -> anyhow::Result<()> {
+    let start = SystemTime::now();
+    let mut page = 0;
+
+    while !app.quit {
+        terminal.draw(|frame| page = ui::draw(frame, app, start))?;
+
+        if event::poll(POLL_INTERVAL)?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            app.on_key(key, page);
+        }
+
+        for _ in 0..FRAMES_PER_REDRAW {
+            let frame = match rx.try_recv() {
+                Ok(Ok(frame)) => frame,
+                Ok(Err(e)) => {
+                    app.push_error(format!("{e:#}"));
+                    continue;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    app.inputs_closed = true;
+                    break;
+                }
+            };
+
+            match decoder.decode_log(&frame.data) {
+                Ok(mut log) => {
+                    log.set_timestamp(frame.timestamp);
+                    app.push_log(frame.source, log);
+                }
+                Err(e) => app.push_error(format!("{}: {e:#}", frame.source)),
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,741 @@
+//! State of the log viewer, independent of how it's drawn.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    time::SystemTime,
+};
+
+use cdefmt_decoder::log::Log;
+use cdefmt_parser::metadata::Level;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// A decoded log, along with everything needed to display it.
+pub struct Entry<'elf> {
+    pub source: String,
+    pub log: Log<'elf>,
+    /// The formatted log message, rendered once when the log is received.
+    pub message: String,
+}
+
+/// Restricts which logs are shown.
+///
+/// All of the text filters are case sensitive substring matches, empty filters match everything.
+pub struct Filter {
+    /// Only show logs at this level or more severe.
+    pub level: Level,
+    pub file: String,
+    /// Matched against the log's format string, not the formatted message.
+    pub fmt: String,
+    /// Either `NAME=VALUE`, matching logs whose argument `NAME` contains `VALUE` when formatted,
+    /// or just `VALUE`, matching any argument.
+    ///
+    /// `NAME` may continue into the argument's members, for example `config.ports[1]=80`.
+    pub arg: String,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            level: Level::Verbose,
+            file: String::new(),
+            fmt: String::new(),
+            arg: String::new(),
+        }
+    }
+}
+
+impl Filter {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let log = &entry.log;
+
+        log.get_level() <= self.level
+            && log.get_file().contains(&self.file)
+            && log.get_fmt().contains(&self.fmt)
+            && self.matches_arg(log)
+    }
+
+    fn matches_arg(&self, log: &Log) -> bool {
+        if self.arg.is_empty() {
+            return true;
+        }
+
+        let Some((path, value)) = self.arg.split_once('=') else {
+            return log
+                .get_args()
+                .iter()
+                .any(|arg| arg.to_string().contains(&self.arg));
+        };
+
+        // Argument names are the expressions passed to the log, and may contain `.` or `[`
+        // themselves, so any argument whose name is a prefix of the path is a candidate.
+        log.get_named_args().any(|(name, arg)| {
+            let Some(rest) = path.strip_prefix(name) else {
+                return false;
+            };
+            if !(rest.is_empty() || rest.starts_with('.') || rest.starts_with('[')) {
+                return false;
+            }
+
+            arg.get(rest.strip_prefix('.').unwrap_or(rest))
+                .is_some_and(|var| var.to_string().contains(value))
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.level == Level::Verbose
+            && self.file.is_empty()
+            && self.fmt.is_empty()
+            && self.arg.is_empty()
+    }
+}
+
+/// How many times a single log was received.
+pub struct Talker {
+    pub id: usize,
+    pub count: u64,
+    pub level: Level,
+    pub location: String,
+    pub fmt: String,
+}
+
+/// Text field currently being edited.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    File,
+    Fmt,
+    Arg,
+    Search,
+}
+
+impl Field {
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::File => "file",
+            Field::Fmt => "fmt",
+            Field::Arg => "arg",
+            Field::Search => "search",
+        }
+    }
+}
+
+pub struct App<'elf> {
+    /// Received logs, oldest first.
+    entries: VecDeque<Entry<'elf>>,
+    /// Sequence number of the first entry in `entries`, increases as old logs are discarded.
+    first: u64,
+    scrollback: usize,
+
+    /// Sequence numbers of the entries that pass the filter, in ascending order.
+    pub visible: VecDeque<u64>,
+    pub filter: Filter,
+    pub search: String,
+
+    /// Index into `visible` of the selected log.
+    pub selected: Option<usize>,
+    /// Whether the selection follows newly received logs, false while paused.
+    pub following: bool,
+    /// Logs that passed the filter since the view was paused.
+    pub unseen: usize,
+
+    talkers: HashMap<usize, Talker>,
+    pub received: u64,
+    pub errors: u64,
+    pub last_error: Option<String>,
+
+    /// The field being edited, and its contents before editing started.
+    pub editing: Option<(Field, String)>,
+    pub inputs_closed: bool,
+    pub quit: bool,
+}
+
+impl<'elf> App<'elf> {
+    pub fn new(scrollback: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            first: 0,
+            scrollback: scrollback.max(1),
+            visible: VecDeque::new(),
+            filter: Filter::default(),
+            search: String::new(),
+            selected: None,
+            following: true,
+            unseen: 0,
+            talkers: HashMap::new(),
+            received: 0,
+            errors: 0,
+            last_error: None,
+            editing: None,
+            inputs_closed: false,
+            quit: false,
+        }
+    }
+
+    pub fn entry(&self, seq: u64) -> Option<&Entry<'elf>> {
+        self.entries.get(seq.checked_sub(self.first)? as usize)
+    }
+
+    pub fn selected_entry(&self) -> Option<&Entry<'elf>> {
+        self.entry(*self.visible.get(self.selected?)?)
+    }
+
+    pub fn total(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn push_log(&mut self, source: String, log: Log<'elf>) {
+        let message = match log.to_string() {
+            Ok(message) => message,
+            Err(e) => format!("Error: {e:#}"),
+        };
+
+        self.received += 1;
+        self.talkers
+            .entry(log.get_id())
+            .or_insert_with(|| Talker {
+                id: log.get_id(),
+                count: 0,
+                level: log.get_level(),
+                location: format!("{}:{}", log.get_file(), log.get_line()),
+                fmt: log.get_fmt().to_string(),
+            })
+            .count += 1;
+
+        let entry = Entry {
+            source,
+            log,
+            message,
+        };
+        let seq = self.first + self.entries.len() as u64;
+        let visible = self.filter.matches(&entry);
+        self.entries.push_back(entry);
+
+        if self.entries.len() > self.scrollback {
+            self.entries.pop_front();
+            self.first += 1;
+            if self.visible.front() == Some(&(self.first - 1)) {
+                self.visible.pop_front();
+                self.selected = self.selected.map(|s| s.saturating_sub(1));
+            }
+        }
+
+        if visible {
+            self.visible.push_back(seq);
+            if self.following {
+                self.selected = Some(self.visible.len() - 1);
+            } else {
+                self.unseen += 1;
+            }
+        }
+    }
+
+    pub fn push_error(&mut self, error: String) {
+        self.errors += 1;
+        self.last_error = Some(error);
+    }
+
+    /// Logs sorted by the number of times they were received, most frequent first.
+    pub fn top_talkers(&self) -> Vec<&Talker> {
+        let mut talkers = self.talkers.values().collect::<Vec<_>>();
+        talkers.sort_by(|a, b| b.count.cmp(&a.count).then(a.id.cmp(&b.id)));
+        talkers
+    }
+
+    pub fn filter_description(&self) -> Option<String> {
+        (!self.filter.is_empty()).then(|| {
+            let mut parts = vec![format!("level {}", self.filter.level)];
+            for (name, value) in [
+                ("file", &self.filter.file),
+                ("fmt", &self.filter.fmt),
+                ("arg", &self.filter.arg),
+            ] {
+                if !value.is_empty() {
+                    parts.push(format!("{name}~{value:?}"));
+                }
+            }
+            parts.join(" ")
+        })
+    }
+
+    /// Recomputes the visible logs after the filter changed, keeping the selection on the same
+    /// log if it's still visible.
+    fn refilter(&mut self) {
+        let selected = self.selected.and_then(|s| self.visible.get(s).copied());
+
+        self.visible = self
+            .entries
+            .iter()
+            .zip(self.first..)
+            .filter(|(entry, _)| self.filter.matches(entry))
+            .map(|(_, seq)| seq)
+            .collect();
+
+        self.selected = if self.following {
+            self.visible.len().checked_sub(1)
+        } else {
+            selected
+                .map(|seq| self.visible.partition_point(|s| *s < seq))
+                .map(|s| s.min(self.visible.len().saturating_sub(1)))
+                .filter(|_| !self.visible.is_empty())
+        };
+        self.unseen = 0;
+    }
+
+    fn select(&mut self, selected: usize) {
+        if self.visible.is_empty() {
+            return;
+        }
+
+        let last = self.visible.len() - 1;
+        self.selected = Some(selected.min(last));
+        self.following = selected >= last;
+        if self.following {
+            self.unseen = 0;
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let current = self.selected.unwrap_or(0);
+        self.select(current.saturating_add_signed(delta));
+    }
+
+    /// Selects the next (or previous) visible log whose message contains the search text.
+    fn find(&mut self, forward: bool) {
+        if self.search.is_empty() || self.visible.is_empty() {
+            return;
+        }
+
+        let current = self.selected.unwrap_or(0);
+        let matches = |index: &usize| {
+            self.entry(self.visible[*index])
+                .is_some_and(|entry| entry.message.contains(&self.search))
+        };
+
+        let found = if forward {
+            (current + 1..self.visible.len()).find(matches)
+        } else {
+            (0..current).rev().find(matches)
+        };
+
+        if let Some(found) = found {
+            self.select(found);
+            // Jumping to the last match shouldn't resume following.
+            self.following = false;
+        }
+    }
+
+    pub fn field(&self, field: Field) -> &str {
+        match field {
+            Field::File => &self.filter.file,
+            Field::Fmt => &self.filter.fmt,
+            Field::Arg => &self.filter.arg,
+            Field::Search => &self.search,
+        }
+    }
+
+    fn field_mut(&mut self, field: Field) -> &mut String {
+        match field {
+            Field::File => &mut self.filter.file,
+            Field::Fmt => &mut self.filter.fmt,
+            Field::Arg => &mut self.filter.arg,
+            Field::Search => &mut self.search,
+        }
+    }
+
+    /// Handles a key press, `page` is the number of logs that fit on the screen.
+    pub fn on_key(&mut self, key: KeyEvent, page: usize) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+
+        if let Some((field, original)) = self.editing.take() {
+            self.on_edit_key(field, original, key);
+            return;
+        }
+
+        let page = page.max(1) as isize;
+
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char(' ') => {
+                if self.following {
+                    self.following = false;
+                } else if let Some(last) = self.visible.len().checked_sub(1) {
+                    self.select(last);
+                } else {
+                    self.following = true;
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-page),
+            KeyCode::PageDown => self.move_selection(page),
+            KeyCode::Home | KeyCode::Char('g') => {
+                self.select(0);
+                self.following = false;
+            }
+            KeyCode::End | KeyCode::Char('G') => self.select(usize::MAX),
+            KeyCode::Char('l') => {
+                self.filter.level = match self.filter.level {
+                    Level::Verbose => Level::Debug,
+                    Level::Debug => Level::Info,
+                    Level::Info => Level::Warning,
+                    Level::Warning => Level::Error,
+                    Level::Error => Level::Verbose,
+                };
+                self.refilter();
+            }
+            KeyCode::Char('f') => self.start_editing(Field::File),
+            KeyCode::Char('m') => self.start_editing(Field::Fmt),
+            KeyCode::Char('a') => self.start_editing(Field::Arg),
+            KeyCode::Char('/') => self.start_editing(Field::Search),
+            KeyCode::Char('n') => self.find(true),
+            KeyCode::Char('N') => self.find(false),
+            KeyCode::Char('c') => {
+                self.filter = Filter::default();
+                self.search.clear();
+                self.refilter();
+            }
+            _ => {}
+        }
+    }
+
+    fn start_editing(&mut self, field: Field) {
+        let original = self.field_mut(field).clone();
+        self.editing = Some((field, original));
+    }
+
+    fn on_edit_key(&mut self, field: Field, original: String, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => {
+                if field == Field::Search {
+                    self.find(true);
+                }
+                return;
+            }
+            KeyCode::Esc => *self.field_mut(field) = original.clone(),
+            KeyCode::Backspace => {
+                self.field_mut(field).pop();
+            }
+            KeyCode::Char(c) => self.field_mut(field).push(c),
+            _ => {}
+        }
+
+        if key.code != KeyCode::Esc {
+            self.editing = Some((field, original));
+        }
+
+        // Filters apply as they're typed.
+        if field != Field::Search {
+            self.refilter();
+        }
+    }
+}
+
+/// Seconds since `start`, used to timestamp the logs relative to when the viewer started.
+pub fn elapsed(start: SystemTime, log: &Log) -> f64 {
+    log.get_timestamp()
+        .and_then(|t| t.duration_since(start).ok())
+        .map(|d| d.as_secs_f64())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use cdefmt_decoder::Decoder;
+    use cdefmt_encoder::{
+        Encoding,
+        elf::{self, ArgumentType},
+        encode_to_vec,
+    };
+    use cdefmt_parser::r#type::{StructureMember, Type};
+
+    use super::*;
+
+    /// An elf with logs at several levels and files, returning it along with the ids of the
+    /// `uart`, `config` and `tick` logs.
+    fn elf() -> (Vec<u8>, [u64; 3]) {
+        let config = Type::Structure {
+            members: vec![StructureMember {
+                offset: 0,
+                name: "ports".to_string(),
+                ty: Type::Array {
+                    ty: Box::new(Type::U16),
+                    lengths: vec![2],
+                },
+            }],
+            size: 4,
+        };
+
+        let count = [("count", ArgumentType::Value(Type::U32))];
+        let config = [("config", ArgumentType::Value(config))];
+        let (elf, ids) = elf::build(
+            Encoding::NATIVE,
+            &[
+                (Level::Error, "drivers/uart.c", 1, "uart overrun {}", &count),
+                (Level::Info, "app/main.c", 1, "config {}", &config),
+                (Level::Debug, "app/main.c", 1, "tick {}", &count),
+            ],
+        )
+        .unwrap();
+
+        (elf, ids.try_into().unwrap())
+    }
+
+    /// Receives a log, `args` are the encoded arguments.
+    fn push<'elf>(app: &mut App<'elf>, decoder: &mut Decoder<'elf>, id: u64, args: &[u8]) {
+        let mut frame = encode_to_vec(Encoding::NATIVE, id, &[]).unwrap();
+        frame.extend_from_slice(args);
+        app.push_log("test".to_string(), decoder.decode_log(&frame).unwrap());
+    }
+
+    fn tick<'elf>(app: &mut App<'elf>, decoder: &mut Decoder<'elf>, ids: [u64; 3], count: u32) {
+        push(app, decoder, ids[2], &count.to_ne_bytes());
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.on_key(KeyEvent::new(code, KeyModifiers::NONE), 10);
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c));
+        }
+    }
+
+    /// The messages of the visible logs.
+    fn visible(app: &App) -> Vec<String> {
+        app.visible
+            .iter()
+            .map(|seq| app.entry(*seq).unwrap().message.clone())
+            .collect()
+    }
+
+    fn selected(app: &App) -> Option<String> {
+        app.selected_entry().map(|entry| entry.message.clone())
+    }
+
+    /// Receives one of each log: `uart overrun 3`, `config` with ports 1 and 80, and `tick 7`.
+    fn receive_each<'elf>(app: &mut App<'elf>, decoder: &mut Decoder<'elf>, ids: [u64; 3]) {
+        push(app, decoder, ids[0], &3u32.to_ne_bytes());
+        let ports = [1u16.to_ne_bytes(), 80u16.to_ne_bytes()].concat();
+        push(app, decoder, ids[1], &ports);
+        tick(app, decoder, ids, 7);
+    }
+
+    #[test]
+    fn filter_by_level() {
+        let (elf, ids) = elf();
+        let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+        let mut app = App::new(100);
+        receive_each(&mut app, &mut decoder, ids);
+        assert_eq!(app.visible.len(), 3);
+        assert_eq!(app.filter_description(), None);
+
+        // Verbose, Debug, Info.
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.visible.len(), 3);
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.visible.len(), 2);
+        assert_eq!(app.filter_description().unwrap(), "level Info");
+
+        // Logs received while filtered are filtered too.
+        tick(&mut app, &mut decoder, ids, 8);
+        assert_eq!(app.visible.len(), 2);
+        assert_eq!(app.total(), 4);
+
+        // Warning, Error, and back to Verbose.
+        press(&mut app, KeyCode::Char('l'));
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(visible(&app), ["uart overrun 3"]);
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.visible.len(), 4);
+    }
+
+    #[test]
+    fn filter_by_file_and_fmt() {
+        let (elf, ids) = elf();
+        let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+        let mut app = App::new(100);
+        receive_each(&mut app, &mut decoder, ids);
+
+        // Applied as it's typed.
+        press(&mut app, KeyCode::Char('f'));
+        type_text(&mut app, "app/");
+        assert_eq!(app.visible.len(), 2);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.editing.is_none());
+
+        press(&mut app, KeyCode::Char('m'));
+        type_text(&mut app, "tock");
+        assert_eq!(app.visible.len(), 0);
+        for _ in 0..3 {
+            press(&mut app, KeyCode::Backspace);
+        }
+        type_text(&mut app, "ick");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(visible(&app), ["tick 7"]);
+        assert_eq!(
+            app.filter_description().unwrap(),
+            "level Verbose file~\"app/\" fmt~\"tick\""
+        );
+
+        // Escape restores the filter from before editing started.
+        press(&mut app, KeyCode::Char('f'));
+        type_text(&mut app, "x");
+        assert_eq!(app.visible.len(), 0);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.filter.file, "app/");
+        assert_eq!(visible(&app), ["tick 7"]);
+
+        press(&mut app, KeyCode::Char('c'));
+        assert_eq!(app.visible.len(), 3);
+    }
+
+    #[test]
+    fn filter_by_arg() {
+        let (elf, ids) = elf();
+        let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+        let mut app = App::new(100);
+        receive_each(&mut app, &mut decoder, ids);
+
+        let mut filtered = |arg: &str| {
+            app.filter.arg = arg.to_string();
+            app.refilter();
+            app.visible.len()
+        };
+
+        assert_eq!(filtered("config.ports[1]=80"), 1);
+        assert_eq!(filtered("config.ports[0]=80"), 0);
+        assert_eq!(filtered("config.ports[2]=80"), 0);
+        assert_eq!(filtered("config=80"), 1);
+        // Both logs have a `count` argument.
+        assert_eq!(filtered("count=7"), 1);
+        assert_eq!(filtered("count="), 2);
+        // Not an argument, only a prefix of one.
+        assert_eq!(filtered("con=80"), 0);
+        // Any argument.
+        assert_eq!(filtered("3"), 1);
+        assert_eq!(filtered(""), 3);
+    }
+
+    #[test]
+    fn search() {
+        let (elf, ids) = elf();
+        let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+        let mut app = App::new(100);
+        for count in 0..6 {
+            tick(&mut app, &mut decoder, ids, count * 10);
+        }
+        press(&mut app, KeyCode::Home);
+        assert_eq!(selected(&app).unwrap(), "tick 0");
+
+        // Starts from the log after the selected one.
+        press(&mut app, KeyCode::Char('/'));
+        type_text(&mut app, "0");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(selected(&app).unwrap(), "tick 10");
+
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(selected(&app).unwrap(), "tick 20");
+        press(&mut app, KeyCode::Char('N'));
+        press(&mut app, KeyCode::Char('N'));
+        assert_eq!(selected(&app).unwrap(), "tick 0");
+        // No earlier match, the selection stays.
+        press(&mut app, KeyCode::Char('N'));
+        assert_eq!(selected(&app).unwrap(), "tick 0");
+
+        // Reaching the last log doesn't resume following.
+        app.search = "50".to_string();
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(selected(&app).unwrap(), "tick 50");
+        assert!(!app.following);
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(selected(&app).unwrap(), "tick 50");
+    }
+
+    #[test]
+    fn pause() {
+        let (elf, ids) = elf();
+        let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+        let mut app = App::new(100);
+        tick(&mut app, &mut decoder, ids, 1);
+        tick(&mut app, &mut decoder, ids, 2);
+        assert!(app.following);
+        assert_eq!(selected(&app).unwrap(), "tick 2");
+
+        press(&mut app, KeyCode::Char(' '));
+        assert!(!app.following);
+        tick(&mut app, &mut decoder, ids, 3);
+        tick(&mut app, &mut decoder, ids, 4);
+        assert_eq!(selected(&app).unwrap(), "tick 2");
+        assert_eq!(app.unseen, 2);
+
+        // Moving up keeps it paused, reaching the end resumes.
+        press(&mut app, KeyCode::Up);
+        assert_eq!(selected(&app).unwrap(), "tick 1");
+        press(&mut app, KeyCode::PageDown);
+        assert!(app.following);
+        assert_eq!(app.unseen, 0);
+        assert_eq!(selected(&app).unwrap(), "tick 4");
+
+        press(&mut app, KeyCode::Char(' '));
+        tick(&mut app, &mut decoder, ids, 5);
+        press(&mut app, KeyCode::Char(' '));
+        assert!(app.following);
+        assert_eq!(selected(&app).unwrap(), "tick 5");
+    }
+
+    #[test]
+    fn scrollback() {
+        let (elf, ids) = elf();
+        let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+        let mut app = App::new(3);
+
+        for count in 0..3 {
+            tick(&mut app, &mut decoder, ids, count);
+        }
+        press(&mut app, KeyCode::Home);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(selected(&app).unwrap(), "tick 1");
+
+        // The oldest logs are dropped, the selection stays on the same log.
+        tick(&mut app, &mut decoder, ids, 3);
+        assert_eq!(app.total(), 3);
+        assert!(app.entry(0).is_none());
+        assert_eq!(visible(&app), ["tick 1", "tick 2", "tick 3"]);
+        assert_eq!(selected(&app).unwrap(), "tick 1");
+
+        // Until the selected log itself is dropped.
+        tick(&mut app, &mut decoder, ids, 4);
+        tick(&mut app, &mut decoder, ids, 5);
+        assert_eq!(visible(&app), ["tick 3", "tick 4", "tick 5"]);
+        assert_eq!(selected(&app).unwrap(), "tick 3");
+        assert_eq!(app.received, 6);
+    }
+
+    #[test]
+    fn top_talkers() {
+        let (elf, ids) = elf();
+        let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+        let mut app = App::new(2);
+        receive_each(&mut app, &mut decoder, ids);
+        for count in 0..3 {
+            tick(&mut app, &mut decoder, ids, count);
+        }
+        push(&mut app, &mut decoder, ids[0], &4u32.to_ne_bytes());
+
+        // Counted even after the logs left the scrollback, ties are ordered by id.
+        let talkers = app
+            .top_talkers()
+            .iter()
+            .map(|t| (t.fmt.as_str(), t.count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            talkers,
+            [("tick {}", 4), ("uart overrun {}", 2), ("config {}", 1)]
+        );
+        assert_eq!(app.top_talkers()[1].location, "drivers/uart.c:1");
+        assert_eq!(app.top_talkers()[1].level, Level::Error);
+    }
+}
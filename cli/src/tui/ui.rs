@@ -0,0 +1,245 @@
+//! Drawing the log viewer.
+
+use std::time::SystemTime;
+
+use cdefmt_decoder::var::Var;
+use cdefmt_parser::metadata::Level;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Wrap},
+};
+
+use super::app::{App, Entry, elapsed};
+
+const HELP: &str = "q quit  space pause  ↑↓/PgUp/PgDn scroll  l level  f file  m fmt  a arg  / search  n/N next/prev  c clear";
+
+pub fn level_style(level: Level) -> Style {
+    match level {
+        Level::Error => Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+        Level::Warning => Style::new().fg(Color::Yellow),
+        Level::Info => Style::new().fg(Color::Green),
+        Level::Debug => Style::new().fg(Color::Blue),
+        Level::Verbose => Style::new().fg(Color::DarkGray),
+    }
+}
+
+/// Draws the whole viewer, returns the number of logs that fit in the log list.
+pub fn draw(frame: &mut Frame, app: &App, start: SystemTime) -> usize {
+    let [main, status, help] = Layout::vertical([
+        Constraint::Min(3),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [logs, side] =
+        Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)]).areas(main);
+    let [details, talkers] =
+        Layout::vertical([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(side);
+
+    let page = draw_logs(frame, app, start, logs);
+    draw_details(frame, app, details);
+    draw_talkers(frame, app, talkers);
+    draw_status(frame, app, status);
+    frame.render_widget(Paragraph::new(HELP).dark_gray(), help);
+
+    page
+}
+
+fn draw_logs(frame: &mut Frame, app: &App, start: SystemTime, area: Rect) -> usize {
+    let mut title = format!(" Logs {}/{} ", app.visible.len(), app.total());
+    if !app.following {
+        title += &format!("[PAUSED, {} new] ", app.unseen);
+    }
+    let block = Block::bordered().title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let height = inner.height as usize;
+    let selected = app.selected.unwrap_or(0);
+    // Keep the selection on screen, at the bottom when following.
+    let offset = (selected + 1).saturating_sub(height);
+
+    let lines = app
+        .visible
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .filter_map(|(index, seq)| {
+            let entry = app.entry(*seq)?;
+            let mut line = log_line(app, entry, start);
+            if app.selected == Some(index) {
+                line = line.patch_style(Style::new().add_modifier(Modifier::REVERSED));
+            }
+            Some(line)
+        })
+        .collect::<Vec<_>>();
+
+    frame.render_widget(Paragraph::new(lines), inner);
+
+    height
+}
+
+fn log_line<'a>(app: &App, entry: &'a Entry, start: SystemTime) -> Line<'a> {
+    let log = &entry.log;
+    let mut spans = vec![
+        Span::raw(format!("{:>10.3} ", elapsed(start, log))).dark_gray(),
+        Span::styled(
+            format!("{:<7} ", log.get_level()),
+            level_style(log.get_level()),
+        ),
+        Span::raw(format!("{}:{} ", log.get_file(), log.get_line())).dark_gray(),
+    ];
+    spans.extend(highlight(&entry.message, &app.search));
+    Line::from(spans)
+}
+
+/// Splits `text` into spans, highlighting every occurrence of `search`.
+fn highlight<'a>(text: &'a str, search: &str) -> Vec<Span<'a>> {
+    if search.is_empty() {
+        return vec![Span::raw(text)];
+    }
+
+    let mut spans = Vec::new();
+    let mut rest = text;
+    while let Some(position) = rest.find(search) {
+        let (before, after) = rest.split_at(position);
+        let (found, after) = after.split_at(search.len());
+        spans.push(Span::raw(before));
+        spans.push(Span::raw(found).black().on_yellow());
+        rest = after;
+    }
+    spans.push(Span::raw(rest));
+    spans
+}
+
+fn draw_details(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::bordered().title(" Details ");
+
+    let Some(entry) = app.selected_entry() else {
+        frame.render_widget(block, area);
+        return;
+    };
+
+    let log = &entry.log;
+    let mut lines = vec![
+        Line::from(vec![
+            Span::raw("id     ").dark_gray(),
+            Span::raw(format!("{:#x}", log.get_id())),
+        ]),
+        Line::from(vec![
+            Span::raw("level  ").dark_gray(),
+            Span::styled(log.get_level().to_string(), level_style(log.get_level())),
+        ]),
+        Line::from(vec![
+            Span::raw("file   ").dark_gray(),
+            Span::raw(format!("{}:{}", log.get_file(), log.get_line())),
+        ]),
+        Line::from(vec![
+            Span::raw("source ").dark_gray(),
+            Span::raw(entry.source.as_str()),
+        ]),
+        Line::from(vec![
+            Span::raw("fmt    ").dark_gray(),
+            Span::raw(format!("{:?}", log.get_fmt())),
+        ]),
+        Line::default(),
+    ];
+
+    for (name, arg) in log.get_named_args() {
+        var_tree(name, arg, 0, &mut lines);
+    }
+
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
+
+/// Renders `var` and all of its children, one per line, indented by depth.
+fn var_tree(name: &str, var: &Var, depth: usize, lines: &mut Vec<Line>) {
+    let indent = "  ".repeat(depth);
+    let name = Span::raw(format!("{indent}{name}")).cyan();
+
+    match var {
+        Var::Structure { members } => {
+            lines.push(Line::from(vec![name, Span::raw(" {}").dark_gray()]));
+            for member in members {
+                var_tree(&member.name, &member.value, depth + 1, lines);
+            }
+        }
+        Var::Array(elements) => {
+            lines.push(Line::from(vec![
+                name,
+                Span::raw(format!(" [{}]", elements.len())).dark_gray(),
+            ]));
+            // Strings are much easier to read as a whole.
+            if let Some(string) = var
+                .as_str()
+                .filter(|s| !s.is_empty() && !s.contains(char::is_control))
+            {
+                lines.push(Line::from(format!("{indent}  {string:?}")));
+            } else {
+                for (index, element) in elements.iter().enumerate() {
+                    var_tree(&format!("[{index}]"), element, depth + 1, lines);
+                }
+            }
+        }
+        Var::Pointer(_) => lines.push(Line::from(vec![name, Span::raw(format!(" = {var:#x}"))])),
+        _ => lines.push(Line::from(vec![name, Span::raw(format!(" = {var}"))])),
+    }
+}
+
+fn draw_talkers(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::bordered().title(format!(" Top talkers ({} logs) ", app.received));
+    let height = block.inner(area).height as usize;
+
+    let lines = app
+        .top_talkers()
+        .into_iter()
+        .take(height)
+        .map(|talker| {
+            Line::from(vec![
+                Span::raw(format!("{:>7} ", talker.count)),
+                Span::styled(format!("{:#010x} ", talker.id), level_style(talker.level)),
+                Span::raw(format!("{} ", talker.location)).dark_gray(),
+                Span::raw(format!("{:?}", talker.fmt)),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+    let line = if let Some((field, _)) = &app.editing {
+        Line::from(vec![
+            Span::raw(format!("{}: ", field.name())).bold(),
+            Span::raw(app.field(*field)),
+            Span::raw("█").slow_blink(),
+            Span::raw("  (enter accept, esc cancel)").dark_gray(),
+        ])
+    } else {
+        let mut spans = vec![Span::raw(
+            app.filter_description()
+                .unwrap_or_else(|| "no filter".to_string()),
+        )];
+        if !app.search.is_empty() {
+            spans.push(Span::raw(format!("  search {:?}", app.search)));
+        }
+        if app.inputs_closed {
+            spans.push(Span::raw("  [inputs closed]").dark_gray());
+        }
+        if let Some(error) = &app.last_error {
+            spans.push(Span::raw(format!("  {} errors, last: {error}", app.errors)).red());
+        }
+        Line::from(spans)
+    };
+
+    frame.render_widget(Paragraph::new(line), area);
+}
@@ -0,0 +1,242 @@
+//! A file format for storing raw log frames, so they can be decoded later.
+//!
+//! Unlike decoded text, a capture keeps the exact bytes sent by the target, along with when and
+//! from where each frame was received.
+//!
+//! All integers are little endian.
+//! A capture starts with a header:
+//! * The magic `CDEFMTCP` (8 bytes).
+//! * The format version (u16), currently [`VERSION`].
+//! * The number of build IDs (u16), followed by each build ID's length (u8) and bytes. These are
+//!   the build IDs of the elfs the capture was recorded with.
+//!
+//! The header is followed by records, each made of a kind (u8), the payload's length (u32) and
+//! the payload.
+//! Readers skip records of unknown kinds, so new kinds can be added without breaking them.
+//! * Source (1): an index (u16) followed by the source's name in UTF-8, for example
+//!   `serial:/dev/ttyUSB0`. Defined before the first frame from that source.
+//! * Frame (2): the source's index (u16), the host time at which the frame was received in
+//!   nanoseconds since the unix epoch (u64), followed by the frame's raw bytes.
+
+use std::{
+    collections::HashMap,
+    io::{ErrorKind, Read, Write},
+    time::{Duration, SystemTime},
+};
+
+use crate::{Error, Result};
+
+pub const MAGIC: [u8; 8] = *b"CDEFMTCP";
+pub const VERSION: u16 = 1;
+
+const SOURCE: u8 = 1;
+const FRAME: u8 = 2;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Header {
+    /// Build IDs of the elfs that generated the captured logs.
+    pub build_ids: Vec<Vec<u8>>,
+}
+
+/// A raw frame read from a capture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub timestamp: SystemTime,
+    /// Where the frame was received from.
+    pub source: String,
+    pub data: Vec<u8>,
+}
+
+/// Writes frames to a capture.
+pub struct Writer<W: Write> {
+    writer: W,
+    sources: HashMap<String, u16>,
+}
+
+impl<W: Write> Writer<W> {
+    /// Writes the capture's header, frames can then be written using [`Self::write`].
+    pub fn new(mut writer: W, header: &Header) -> Result<Self> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+
+        let count = u16::try_from(header.build_ids.len())
+            .map_err(|_| Error::TooLarge("The number of build IDs"))?;
+        writer.write_all(&count.to_le_bytes())?;
+        for build_id in &header.build_ids {
+            let len = u8::try_from(build_id.len()).map_err(|_| Error::TooLarge("The build ID"))?;
+            writer.write_all(&[len])?;
+            writer.write_all(build_id)?;
+        }
+
+        Ok(Self {
+            writer,
+            sources: Default::default(),
+        })
+    }
+
+    /// Writes a frame received from `source` at `timestamp`.
+    pub fn write(&mut self, timestamp: SystemTime, source: &str, data: &[u8]) -> Result<()> {
+        let index = match self.sources.get(source) {
+            Some(index) => *index,
+            None => {
+                let index = u16::try_from(self.sources.len())
+                    .map_err(|_| Error::TooLarge("The number of sources"))?;
+                self.write_record(SOURCE, &[&index.to_le_bytes(), source.as_bytes()])?;
+                self.sources.insert(source.to_string(), index);
+                index
+            }
+        };
+
+        let timestamp = timestamp
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+
+        self.write_record(
+            FRAME,
+            &[&index.to_le_bytes(), &timestamp.to_le_bytes(), data],
+        )
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_record(&mut self, kind: u8, parts: &[&[u8]]) -> Result<()> {
+        let len = parts.iter().map(|p| p.len()).sum::<usize>();
+        let len = u32::try_from(len).map_err(|_| Error::TooLarge("The record"))?;
+
+        self.writer.write_all(&[kind])?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        for part in parts {
+            self.writer.write_all(part)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads frames from a capture.
+pub struct Reader<R: Read> {
+    reader: R,
+    header: Header,
+    sources: HashMap<u16, String>,
+}
+
+impl<R: Read> Reader<R> {
+    /// Reads and validates the capture's header.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(Error::NotACapture);
+        }
+
+        let version = u16::from_le_bytes(read_array(&mut reader)?);
+        if version != VERSION {
+            return Err(Error::UnsupportedCaptureVersion(version));
+        }
+
+        let count = u16::from_le_bytes(read_array(&mut reader)?);
+        let build_ids = (0..count)
+            .map(|_| {
+                let [len] = read_array(&mut reader)?;
+                let mut build_id = vec![0; len as usize];
+                reader.read_exact(&mut build_id)?;
+                Ok(build_id)
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            reader,
+            header: Header { build_ids },
+            sources: Default::default(),
+        })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Reads the next frame, returns `None` at the end of the capture.
+    pub fn next_frame(&mut self) -> Result<Option<Frame>> {
+        loop {
+            let mut kind = [0];
+            match self.reader.read_exact(&mut kind) {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+
+            let len = match read_array(&mut self.reader) {
+                Ok(len) => u32::from_le_bytes(len) as usize,
+                Err(Error::Io(e)) if e.kind() == ErrorKind::UnexpectedEof => {
+                    return Err(Error::TruncatedRecord);
+                }
+                Err(e) => return Err(e),
+            };
+            let mut payload = Vec::new();
+            self.reader
+                .by_ref()
+                .take(len as u64)
+                .read_to_end(&mut payload)?;
+            if payload.len() != len {
+                return Err(Error::TruncatedRecord);
+            }
+
+            match kind[0] {
+                SOURCE => {
+                    let (index, name) = payload
+                        .split_first_chunk::<2>()
+                        .ok_or(Error::MalformedRecord("source"))?;
+                    let name = String::from_utf8_lossy(name).into_owned();
+                    self.sources.insert(u16::from_le_bytes(*index), name);
+                }
+                FRAME => {
+                    let (index, rest) = payload
+                        .split_first_chunk::<2>()
+                        .ok_or(Error::MalformedRecord("frame"))?;
+                    let (timestamp, data) = rest
+                        .split_first_chunk::<8>()
+                        .ok_or(Error::MalformedRecord("frame"))?;
+
+                    let index = u16::from_le_bytes(*index);
+                    let source = self
+                        .sources
+                        .get(&index)
+                        .ok_or(Error::UndefinedSource(index))?
+                        .clone();
+                    let timestamp = SystemTime::UNIX_EPOCH
+                        + Duration::from_nanos(u64::from_le_bytes(*timestamp));
+
+                    return Ok(Some(Frame {
+                        timestamp,
+                        source,
+                        data: data.to_vec(),
+                    }));
+                }
+                // Written by a newer version, skip it.
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_frame().transpose()
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+    let mut array = [0; N];
+    reader.read_exact(&mut array)?;
+    Ok(array)
+}
@@ -5,17 +5,34 @@ use std::collections::HashMap;
 use cdefmt_parser::{
     Parser,
     metadata::Metadata,
+    path::PathMap,
     r#type::{self, Type},
 };
 use gimli::Reader;
 use object::ReadRef;
 
-use crate::{Error, Result, log::Log, var::Var};
+use crate::{Error, MalformedType, Result, filter::Filter, limits::Limits, log::Log, var::Var};
+
+/// The known log whose id is closest to an unknown one, see [`Error::UnknownLogId`].
+///
+/// An id that's only a few bytes off hints at a corrupted frame, while a far one, or a nearest log
+/// that makes no sense where the frame came from, hints at decoding with the wrong elf.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NearestLog {
+    pub id: usize,
+    pub file: String,
+    pub line: usize,
+}
 
 /// Responsible for parsing logs from the elf.
 pub struct Decoder<'elf> {
     parser: Parser<'elf>,
     log_cache: HashMap<usize, (Metadata<'elf>, Option<Type>)>,
+    filter: Filter,
+    /// Whether the filter accepts each log id, so it's only evaluated once per log.
+    filter_cache: HashMap<usize, bool>,
+    limits: Limits,
+    lenient: bool,
 }
 
 impl<'elf> Decoder<'elf> {
@@ -24,31 +41,184 @@ impl<'elf> Decoder<'elf> {
         Ok(Decoder {
             parser: Parser::new(data)?,
             log_cache: Default::default(),
+            filter: Default::default(),
+            filter_cache: Default::default(),
+            limits: Default::default(),
+            lenient: false,
         })
     }
 
     /// Decodes a raw log
     pub fn decode_log(&mut self, data: &[u8]) -> Result<Log<'elf>> {
+        let (id, body) = self.read_id(data)?;
+        self.decode_log_body(id, body)
+            .map_err(|e| self.unknown_log(e, data))
+    }
+
+    /// Decodes a raw log, unless it's rejected by the filter set using [`Self::set_filter`].
+    ///
+    /// Rejected logs are dropped right after reading their id, their arguments are never parsed.
+    /// The init log is the only exception, it's always decoded so the build ID is validated.
+    pub fn decode_log_filtered(&mut self, data: &[u8]) -> Result<Option<Log<'elf>>> {
+        self.decode_log_filtered_inner(data)
+            .map_err(|e| self.unknown_log(e, data))
+    }
+
+    fn decode_log_filtered_inner(&mut self, data: &[u8]) -> Result<Option<Log<'elf>>> {
+        let (id, data) = self.read_id(data)?;
+
+        if id == 0 {
+            let log = self.decode_log_body(id, data)?;
+            return Ok(self.accepts(id)?.then_some(log));
+        }
+
+        if !self.accepts(id)? {
+            return Ok(None);
+        }
+
+        self.decode_log_body(id, data).map(Some)
+    }
+
+    /// Attaches the raw `frame` and the nearest known log to an [`Error::UnknownLogId`].
+    fn unknown_log(&self, error: Error, frame: &[u8]) -> Error {
+        match error {
+            Error::UnknownLogId { id, .. } => Error::UnknownLogId {
+                id,
+                frame: frame.to_vec(),
+                nearest: self.nearest_log(id),
+            },
+            e => e,
+        }
+    }
+
+    /// Returns the known log whose id is closest to `id`.
+    pub fn nearest_log(&self, id: usize) -> Option<NearestLog> {
+        let id = self.parser.nearest_log_id(id)?;
+        let metadata = self.parser.get_log_metadata(id).ok()?;
+        Some(NearestLog {
+            id,
+            file: metadata.file.to_string(),
+            line: metadata.line,
+        })
+    }
+
+    /// Sets the filter used by [`Self::decode_log_filtered`].
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.filter = filter;
+        self.filter_cache.clear();
+    }
+
+    pub fn filter(&self) -> &Filter {
+        &self.filter
+    }
+
+    /// Sets the limits on decoded frames, frames exceeding them fail to decode with
+    /// [`Error::LimitExceeded`].
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Sets whether truncated and mismatched frames are decoded as far as possible instead of
+    /// failing.
+    ///
+    /// In lenient mode the arguments missing from a truncated frame are [`Var::Truncated`], and
+    /// the bytes that couldn't be decoded are kept as the log's trailing bytes, see
+    /// [`Log::get_trailing`].
+    /// If the log's arguments type can't be found, all of its arguments are truncated and the whole
+    /// frame is trailing.
+    pub fn set_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    pub fn lenient(&self) -> bool {
+        self.lenient
+    }
+
+    fn read_id<'data>(
+        &self,
+        data: &'data [u8],
+    ) -> Result<(usize, gimli::EndianSlice<'data, gimli::RunTimeEndian>)> {
+        self.limits.check_frame_size(data.len())?;
+
         let mut data = gimli::EndianSlice::new(data, self.parser.endian());
-        let id = data.read_address(self.parser.address_size().bytes())? as usize;
+        let id = data
+            .read_address(self.parser.address_size().bytes())
+            .map_err(|e| truncated(e.into(), None))? as usize;
+        Ok((id, data))
+    }
+
+    /// Parses the log's metadata.
+    fn log_metadata(&self, id: usize) -> Result<Metadata<'elf>> {
+        self.parser.get_log_metadata(id).map_err(|e| match e {
+            cdefmt_parser::Error::UnknownLogId(_) | cdefmt_parser::Error::OutOfBounds(..) => {
+                Error::UnknownLogId {
+                    id,
+                    frame: Vec::new(),
+                    nearest: None,
+                }
+            }
+            e => e.into(),
+        })
+    }
 
-        if let std::collections::hash_map::Entry::Vacant(e) = self.log_cache.entry(id) {
-            // Parse log metadata and type if we don't have it cached.
-            let metadata = self.parser.get_log_metadata(id)?;
+    /// Parses the log's metadata and type if we don't have it cached.
+    fn cache_log(&mut self, id: usize) -> Result<()> {
+        if !self.log_cache.contains_key(&id) {
+            let metadata = self.log_metadata(id)?;
             let ty = self.parser.get_log_args_type(&metadata)?;
-            e.insert((metadata, ty));
+            self.log_cache.insert(id, (metadata, ty));
+        }
+
+        Ok(())
+    }
+
+    /// Checks the log against the filter, only its metadata is parsed, the type of its arguments
+    /// is looked up once it's decoded.
+    fn accepts(&mut self, id: usize) -> Result<bool> {
+        if self.filter.is_empty() {
+            return Ok(true);
+        }
+
+        if let Some(accepted) = self.filter_cache.get(&id) {
+            return Ok(*accepted);
+        }
+
+        let accepted = match self.log_cache.get(&id) {
+            Some((metadata, _)) => self.filter.matches(metadata),
+            None => self.filter.matches(&self.log_metadata(id)?),
         };
+        self.filter_cache.insert(id, accepted);
+
+        Ok(accepted)
+    }
+
+    /// Decodes the arguments of the log `id`, `data` is the rest of the frame following the id.
+    fn decode_log_body<R: Reader>(&mut self, id: usize, mut data: R) -> Result<Log<'elf>> {
+        self.cache_log(id)?;
 
         // Unwrap safety: made sure that the entry exists right above here.
         let (metadata, ty) = self.log_cache.get(&id).unwrap();
 
-        let args = if let Some(ty) = ty {
-            Self::decode_log_args(ty, data)?
-        } else {
-            vec![]
+        let args = match ty {
+            Some(ty) => Self::decode_log_args(ty, &mut data, &self.limits, self.lenient)
+                .map_err(|e| truncated(e, Some(id)))?,
+            None if self.lenient => vec![Var::Truncated; metadata.names.len()],
+            None => {
+                return Err(Error::TypeNotFound {
+                    file: metadata.file.to_string(),
+                    counter: metadata.counter,
+                });
+            }
         };
 
-        let log = Log::new(metadata.clone(), args);
+        let mut log = Log::new(metadata.clone(), args);
+        if self.lenient {
+            log.set_trailing(data.to_slice()?.into_owned());
+        }
 
         if id == 0 {
             self.validate_init(&log)?
@@ -78,19 +248,40 @@ impl<'elf> Decoder<'elf> {
         self.parser.endian()
     }
 
+    /// Sets the path prefix remapping applied to the source file of every log, see
+    /// [`Parser::set_path_map`].
+    pub fn set_path_map(&mut self, path_map: PathMap) {
+        self.parser.set_path_map(path_map);
+        // Cached metadata holds the old paths, which may also have resolved to other types.
+        self.log_cache.clear();
+        self.filter_cache.clear();
+    }
+
+    /// Returns the underlying parser, giving access to the elf's information.
+    pub fn parser(&self) -> &Parser<'elf> {
+        &self.parser
+    }
+
     // Parses the log's arguments.
-    fn decode_log_args<R: Reader>(ty: &Type, mut data: R) -> Result<Vec<Var>> {
+    //
+    // When `lenient`, the arguments that run out of data are truncated, and `data` is left at the
+    // start of the first one.
+    fn decode_log_args<R: Reader>(
+        ty: &Type,
+        data: &mut R,
+        limits: &Limits,
+        lenient: bool,
+    ) -> Result<Vec<Var>> {
         let members = if let Type::Structure { members, .. } = ty {
             members
         } else {
-            return Err(Error::Custom("The log's args aren't a structure!").into());
+            return Err(MalformedType::ArgsNotStructure.into());
         };
 
         // We already read the log_id from the data, skip it.
-        let members = &members[1..];
-
-        // Parse the raw data into `Var` representation.
-        let mut decoded = members
+        let members = members
+            .get(1..)
+            .ok_or(MalformedType::MissingLogId)?
             .iter()
             // Filter out the 'dynamic_data' member for now - this contains variable-length data
             // that needs to be processed after parsing the fixed-size fields, since we need
@@ -98,19 +289,59 @@ impl<'elf> Decoder<'elf> {
             // The dynamic_data field is positioned at the end of the structure when present,
             // but not all logs have it, so we filter by name rather than skipping the last element.
             .filter(|m| !matches!(m.name.as_str(), "dynamic_data"))
-            .map(|m| Ok(Var::parse(&m.ty, &mut data)?.0))
-            .collect::<Result<Vec<_>>>()?;
+            .collect::<Vec<_>>();
+
+        // Parse the raw data into `Var` representation.
+        let mut decoded = Vec::with_capacity(members.len());
+        let mut complete = true;
+        for member in &members {
+            let start = data.clone();
+            match Var::parse_with_limits(&member.ty, data, limits) {
+                Ok((var, _)) => decoded.push(var),
+                Err(e) if lenient && is_eof(&e) => {
+                    *data = start;
+                    complete = false;
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
 
-        // Decode dynamic members
+        // Decode dynamic members, their data follows all of the fixed size members, so there's
+        // none if those are truncated.
+        let mut dynamic_data = 0;
         for (i, member) in members.iter().enumerate() {
             match member.name.as_str() {
+                _ if i >= decoded.len() => break,
                 n if n.contains("dynamic_array") => {
-                    decoded[i] = Self::decode_dynamic_array(member, &decoded[i], &mut data)?
+                    if !complete {
+                        decoded[i] = Var::Truncated;
+                        continue;
+                    }
+
+                    let start = data.clone();
+                    decoded[i] = match Self::decode_dynamic_array(
+                        member,
+                        &decoded[i],
+                        data,
+                        limits,
+                        &mut dynamic_data,
+                    ) {
+                        Ok(var) => var,
+                        Err(e) if lenient && is_eof(&e) => {
+                            *data = start;
+                            complete = false;
+                            Var::Truncated
+                        }
+                        Err(e) => return Err(e),
+                    };
                 }
                 _ => continue,
             }
         }
 
+        decoded.resize(members.len(), Var::Truncated);
+
         Ok(decoded)
     }
 
@@ -118,7 +349,7 @@ impl<'elf> Decoder<'elf> {
         let args = log.get_args();
 
         if args.is_empty() {
-            return Err(Error::Custom("No build ID argument information!").into());
+            return Err(Error::InvalidBuildId);
         }
 
         if let Var::Array(build_id) = args.first().unwrap() {
@@ -126,16 +357,19 @@ impl<'elf> Decoder<'elf> {
                 .iter()
                 .map(|b| match b {
                     Var::U8(b) => Ok(*b),
-                    _ => Err(Error::Custom("Build ID data contains non u8 element!").into()),
+                    _ => Err(Error::InvalidBuildId),
                 })
                 .collect::<Result<Vec<_>>>()?;
             if self.parser.build_id() != build_id {
-                Err(Error::Custom("Build ID mismatch!").into())
+                Err(Error::BuildIdMismatch {
+                    expected: self.parser.build_id().to_vec(),
+                    found: build_id,
+                })
             } else {
                 Ok(())
             }
         } else {
-            Err(Error::Custom("Build ID missing or not an array").into())
+            Err(Error::InvalidBuildId)
         }
     }
 
@@ -143,34 +377,60 @@ impl<'elf> Decoder<'elf> {
         metadata: &r#type::StructureMember,
         value: &Var,
         data: &mut R,
+        limits: &Limits,
+        dynamic_data: &mut u64,
     ) -> Result<Var> {
         // The dynamic_array is structured as:
         // [0] size
         // [1] type
 
         // Extract size from value that was previously decoded
-        let size = match value {
-            Var::Structure { members } => &members[0].value,
-            _ => return Err(Error::Custom("Dynamic array metadata is not a struct!").into()),
-        }
-        .as_u64();
+        let size = value
+            .member("size")
+            .ok_or(MalformedType::DynamicArrayWithoutSize)?
+            .try_as_u64()
+            .ok_or(MalformedType::DynamicArraySizeNotInteger)?;
+
+        // The total size of the frame's dynamic arrays so far.
+        *dynamic_data = dynamic_data.saturating_add(size);
+        limits.check_dynamic_data(*dynamic_data)?;
 
         // Extract type from metadata
         let arr_ty = match &metadata.ty {
-            Type::Structure { members, .. } => &members[1].ty,
-            _ => return Err(Error::Custom("Dynamic array metadata is not a struct!").into()),
+            Type::Structure { members, .. } => members
+                .get(1)
+                .map(|m| &m.ty)
+                .ok_or(MalformedType::DynamicArrayWithoutType)?,
+            _ => return Err(MalformedType::DynamicArrayNotStructure.into()),
         };
 
         let ty = match arr_ty {
             Type::Array { ty, .. } => ty,
-            _ => return Err(Error::Custom("Dynamic array type metadata is not an array!").into()),
+            _ => return Err(MalformedType::DynamicArrayTypeNotArray.into()),
         };
 
+        let element_size = ty.size() as u64;
+        if element_size == 0 {
+            return Err(MalformedType::ZeroSizeElements.into());
+        }
+
         let dyn_ty = Type::Array {
             ty: ty.clone(),
-            lengths: vec![size / ty.size() as u64],
+            lengths: vec![size / element_size],
         };
 
-        Ok(Var::parse(&dyn_ty, data)?.0)
+        Ok(Var::parse_with_limits(&dyn_ty, data, limits)?.0)
+    }
+}
+
+fn is_eof(error: &Error) -> bool {
+    matches!(error, Error::Gimli(gimli::Error::UnexpectedEof(_)))
+}
+
+/// Reports running out of data while reading the frame of log `id` as a truncated frame.
+fn truncated(error: Error, id: Option<usize>) -> Error {
+    match error {
+        Error::Gimli(gimli::Error::UnexpectedEof(_)) => Error::TruncatedFrame { id },
+        e => e,
     }
 }
@@ -0,0 +1,114 @@
+//! Selecting which logs get decoded.
+//!
+//! A [`Filter`] only looks at a log's metadata, so the decoder can reject a frame right after
+//! reading its id, without parsing the arguments or formatting the message.
+
+use std::collections::HashSet;
+
+use cdefmt_parser::metadata::{Level, Metadata};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::Regex;
+
+use crate::Result;
+
+/// Criteria a log has to meet in order to be decoded, all of them have to match.
+///
+/// The default filter accepts every log.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    level: Option<Level>,
+    files: Option<GlobSet>,
+    allowed_ids: Option<HashSet<usize>>,
+    denied_ids: HashSet<usize>,
+    fmt: Option<Regex>,
+}
+
+impl Filter {
+    /// Creates a filter that accepts every log.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Only accepts logs at `level` or more severe.
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Only accepts logs from files matching at least one of the glob `patterns`, for example
+    /// `**/drivers/*.c`.
+    ///
+    /// Patterns are matched against the file path after applying the decoder's
+    /// [`PathMap`](cdefmt_parser::path::PathMap), see [`Decoder::set_path_map`](crate::Decoder::set_path_map).
+    pub fn with_files<S: AsRef<str>>(
+        mut self,
+        patterns: impl IntoIterator<Item = S>,
+    ) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(Glob::new(pattern.as_ref())?);
+        }
+        self.files = Some(builder.build()?);
+        Ok(self)
+    }
+
+    /// Only accepts logs with one of the given ids.
+    pub fn with_allowed_ids(mut self, ids: impl IntoIterator<Item = usize>) -> Self {
+        self.allowed_ids.get_or_insert_default().extend(ids);
+        self
+    }
+
+    /// Rejects logs with any of the given ids, takes precedence over [`Self::with_allowed_ids`].
+    pub fn with_denied_ids(mut self, ids: impl IntoIterator<Item = usize>) -> Self {
+        self.denied_ids.extend(ids);
+        self
+    }
+
+    /// Only accepts logs whose format string matches the regular expression `pattern`.
+    pub fn with_fmt(mut self, pattern: &str) -> Result<Self> {
+        self.fmt = Some(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Whether the filter accepts every log.
+    pub fn is_empty(&self) -> bool {
+        self.level.is_none()
+            && self.files.is_none()
+            && self.allowed_ids.is_none()
+            && self.denied_ids.is_empty()
+            && self.fmt.is_none()
+    }
+
+    /// Checks whether the log described by `metadata` should be decoded.
+    pub fn matches(&self, metadata: &Metadata) -> bool {
+        if self.denied_ids.contains(&metadata.id) {
+            return false;
+        }
+
+        if let Some(allowed_ids) = &self.allowed_ids
+            && !allowed_ids.contains(&metadata.id)
+        {
+            return false;
+        }
+
+        if let Some(level) = self.level
+            && metadata.level > level
+        {
+            return false;
+        }
+
+        if let Some(files) = &self.files
+            && !files.is_match(metadata.file.as_ref())
+        {
+            return false;
+        }
+
+        if let Some(fmt) = &self.fmt
+            && !fmt.is_match(metadata.fmt)
+        {
+            return false;
+        }
+
+        true
+    }
+}
@@ -0,0 +1,198 @@
+//! Splitting a byte stream into raw log frames.
+//!
+//! The target decides how frames are delimited on the wire, the [`FrameDecoder`] takes arbitrary
+//! chunks of the stream and yields complete frames, ready to be passed to
+//! [`Decoder::decode_log`](crate::Decoder::decode_log).
+
+use std::str::FromStr;
+
+use gimli::{EndianSlice, Reader, RunTimeEndian};
+
+use crate::{
+    Error, Result,
+    limits::{Limit, Limits},
+};
+
+/// The way frames are delimited in a byte stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// Each frame is preceded by its length, an unsigned integer of the given width (in bytes)
+    /// encoded using the target's endianness.
+    ///
+    /// The stdout example uses an 8 byte length.
+    LengthPrefixed(u8),
+    /// Each frame is COBS encoded and terminated by a zero byte.
+    Cobs,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Framing::LengthPrefixed(8)
+    }
+}
+
+/// The error returned when parsing an unknown [`Framing`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("Unknown framing '{0}', expected one of: len8, len16, len32, len64, cobs")]
+pub struct UnknownFraming(pub String);
+
+impl FromStr for Framing {
+    type Err = UnknownFraming;
+
+    /// Parses `len8`, `len16`, `len32`, `len64` or `cobs`.
+    /// The number after `len` is the width of the length prefix in bits.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "len8" => Ok(Framing::LengthPrefixed(1)),
+            "len16" => Ok(Framing::LengthPrefixed(2)),
+            "len32" => Ok(Framing::LengthPrefixed(4)),
+            "len64" => Ok(Framing::LengthPrefixed(8)),
+            "cobs" => Ok(Framing::Cobs),
+            _ => Err(UnknownFraming(s.to_string())),
+        }
+    }
+}
+
+/// Incrementally extracts frames from a byte stream.
+///
+/// Frames longer than the maximal frame size are dropped, so a corrupted length or a missing COBS
+/// terminator can't make the decoder buffer without bound.
+#[derive(Debug)]
+pub struct FrameDecoder {
+    framing: Framing,
+    endian: RunTimeEndian,
+    buffer: Vec<u8>,
+    max_frame_size: usize,
+    /// Whether the rest of an oversized COBS frame is being skipped, up to its terminator.
+    resyncing: bool,
+}
+
+impl FrameDecoder {
+    /// Creates a new frame decoder, `endian` should be the target's endianness.
+    ///
+    /// Frames are bounded by the default [`Limits`].
+    pub fn new(framing: Framing, endian: RunTimeEndian) -> Self {
+        Self {
+            framing,
+            endian,
+            buffer: Vec::new(),
+            max_frame_size: Limits::default().max_frame_size(),
+            resyncing: false,
+        }
+    }
+
+    /// Drops frames longer than the `limits`' maximal frame size, see
+    /// [`Limits::with_max_frame_size`].
+    pub fn with_limits(mut self, limits: &Limits) -> Self {
+        self.max_frame_size = limits.max_frame_size();
+        self
+    }
+
+    /// Appends a chunk of the stream.
+    pub fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Returns the next complete frame, or `None` if more data is needed.
+    ///
+    /// Oversized frames fail with [`Error::LimitExceeded`] and malformed COBS frames with
+    /// [`Error::MalformedCobsFrame`], both are dropped and the following frames can still be read.
+    pub fn next_frame(&mut self) -> Result<Option<Vec<u8>>> {
+        match self.framing {
+            Framing::LengthPrefixed(width) => self.next_length_prefixed(width as usize),
+            Framing::Cobs => self.next_cobs(),
+        }
+    }
+
+    /// Returns the amount of buffered bytes that aren't part of a complete frame yet.
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+
+    fn next_length_prefixed(&mut self, width: usize) -> Result<Option<Vec<u8>>> {
+        if self.buffer.len() < width {
+            return Ok(None);
+        }
+
+        let mut header = EndianSlice::new(&self.buffer[..width], self.endian);
+        let len = header.read_sized_offset(width as u8)?;
+
+        if len > self.max_frame_size {
+            // The length is most likely corrupted, so is the frame's end. Skip the prefix and
+            // look for the next frame right after it.
+            self.buffer.drain(..width);
+            return Err(self.frame_too_large(len));
+        }
+
+        if self.buffer.len() - width < len {
+            return Ok(None);
+        }
+
+        let frame = self.buffer[width..width + len].to_vec();
+        self.buffer.drain(..width + len);
+
+        Ok(Some(frame))
+    }
+
+    fn next_cobs(&mut self) -> Result<Option<Vec<u8>>> {
+        // COBS adds a byte for every 254 bytes, and one more at the start.
+        let max_encoded_size = self.max_frame_size + self.max_frame_size / 254 + 1;
+
+        let Some(end) = self.buffer.iter().position(|b| *b == 0) else {
+            if self.resyncing {
+                self.buffer.clear();
+            } else if self.buffer.len() > max_encoded_size {
+                let dropped = self.buffer.len();
+                self.buffer.clear();
+                self.resyncing = true;
+                return Err(self.frame_too_large(dropped));
+            }
+            return Ok(None);
+        };
+
+        let frame = self.buffer.drain(..=end).take(end).collect::<Vec<_>>();
+        if std::mem::take(&mut self.resyncing) {
+            // The end of the frame that was dropped, the next one starts right after it.
+            return self.next_cobs();
+        }
+        if frame.len() > max_encoded_size {
+            return Err(self.frame_too_large(frame.len()));
+        }
+
+        cobs_decode(&frame).map(Some)
+    }
+
+    fn frame_too_large(&self, size: usize) -> Error {
+        Error::LimitExceeded {
+            limit: Limit::FrameSize,
+            value: size as u64,
+            max: self.max_frame_size as u64,
+        }
+    }
+}
+
+/// Decodes a single COBS encoded frame, without its zero terminator.
+fn cobs_decode(data: &[u8]) -> Result<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(data.len());
+    let mut i = 0;
+
+    while i < data.len() {
+        let code = data[i] as usize;
+        let end = i + code;
+
+        if code == 0 || end > data.len() {
+            return Err(Error::MalformedCobsFrame);
+        }
+
+        decoded.extend_from_slice(&data[i + 1..end]);
+
+        // A code of 0xff means a run of 254 bytes that isn't followed by a zero.
+        if code != 0xff && end < data.len() {
+            decoded.push(0);
+        }
+
+        i = end;
+    }
+
+    Ok(decoded)
+}
@@ -1,10 +1,21 @@
+use std::path::PathBuf;
+
 use gimli::{DwAte, DwTag, SectionId};
 
+pub mod capture;
 pub mod decoder;
+pub mod filter;
+pub mod frame;
+pub mod limits;
 pub mod log;
+pub mod pcapng;
+pub mod store;
+pub mod template;
 pub mod var;
 
-pub use decoder::Decoder;
+pub use decoder::{Decoder, NearestLog};
+pub use filter::Filter;
+pub use limits::Limits;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -12,8 +23,43 @@ pub enum Error {
     Gimli(#[from] gimli::Error),
     #[error("{0}")]
     Parser(#[from] cdefmt_parser::Error),
-    #[error("rformat error: {0}")]
-    RFormat(#[from] rformat::error::FormatError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{}: {source}", path.display())]
+    File {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Loading {}: {source}", path.display())]
+    Elf { path: PathBuf, source: Box<Error> },
+    /// The frame's log id isn't in the elf, either the elf is of another build or the id is
+    /// corrupted, see [`NearestLog`].
+    #[error("<unknown log {id:#x}> [raw: {}]{}", hex_dump(frame), nearest_log(*id, nearest))]
+    UnknownLogId {
+        id: usize,
+        /// The whole frame, including the id.
+        frame: Vec<u8>,
+        nearest: Option<NearestLog>,
+    },
+    /// The frame ended before all of its arguments were read, `id` is `None` if it ended before
+    /// the log id.
+    #[error("The frame of log [{}] is truncated", id.map_or("?".to_string(), |id| format!("{id:#x}")))]
+    TruncatedFrame { id: Option<usize> },
+    #[error(
+        "Build ID mismatch, the elf's is {}, the device's is {}",
+        hex(expected),
+        hex(found)
+    )]
+    BuildIdMismatch { expected: Vec<u8>, found: Vec<u8> },
+    #[error("Unable to find the arguments type of log #{counter} in {file}")]
+    TypeNotFound { file: String, counter: u32 },
+    #[error("{file}:{line}: Could not format '{fmt}': {source}")]
+    FormatError {
+        file: String,
+        line: usize,
+        fmt: String,
+        source: rformat::error::FormatError,
+    },
     #[error("The provided elf is missing the '.cdefmt' section.")]
     MissingSection,
     #[error("DIE is missing attribute {0}")]
@@ -44,8 +90,102 @@ pub enum Error {
     NoDIE(u64),
     #[error("Unsupported schema version: {0}")]
     Schema(u32),
-    #[error("{0}")]
-    Custom(&'static str),
+    #[error("Invalid file glob: {0}")]
+    Glob(#[from] globset::Error),
+    #[error("Invalid format regex: {0}")]
+    Regex(#[from] regex::Error),
+    #[error("Invalid template: {0}")]
+    Template(#[from] template::TemplateError),
+    /// The debugging information of a log's arguments doesn't describe a frame that can be
+    /// decoded.
+    #[error("Malformed arguments type: {0}")]
+    MalformedType(MalformedType),
+    /// The build ID argument of the init log is missing, or isn't an array of bytes.
+    #[error("The init log has no valid build ID argument")]
+    InvalidBuildId,
+    #[error("Unable to find build ID in elf!")]
+    MissingBuildId,
+    #[error("The build ID {} isn't in the store", hex(.0))]
+    UnknownBuildId(Vec<u8>),
+    #[error("No elf selected, waiting for an init log!")]
+    NoElfSelected,
+    #[error("Malformed COBS frame")]
+    MalformedCobsFrame,
+    #[error("Not a cdefmt capture")]
+    NotACapture,
+    #[error("Unsupported capture version: {0}")]
+    UnsupportedCaptureVersion(u16),
+    #[error("The capture's last record is truncated")]
+    TruncatedRecord,
+    #[error("Malformed capture {0} record")]
+    MalformedRecord(&'static str),
+    #[error("Capture frame from the undefined source {0}")]
+    UndefinedSource(u16),
+    /// A capture or pcapng file can't hold the item, `what` describes it.
+    #[error("{0} doesn't fit in the file format")]
+    TooLarge(&'static str),
+    #[error("The frame exceeds the {limit} limit: {value} > {max}")]
+    LimitExceeded {
+        limit: limits::Limit,
+        value: u64,
+        max: u64,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Why a log's arguments type can't be used to decode its frames, see [`Error::MalformedType`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum MalformedType {
+    #[error("the log's arguments aren't a structure")]
+    ArgsNotStructure,
+    #[error("the log's arguments have no log id")]
+    MissingLogId,
+    #[error("structure members exceed the structure's size")]
+    StructureOverflow,
+    #[error("array has no dimensions")]
+    ArrayWithoutDimensions,
+    #[error("array elements have no size")]
+    ZeroSizeElements,
+    #[error("dynamic array isn't a structure")]
+    DynamicArrayNotStructure,
+    #[error("dynamic array has no size")]
+    DynamicArrayWithoutSize,
+    #[error("dynamic array size isn't an integer")]
+    DynamicArraySizeNotInteger,
+    #[error("dynamic array has no elements type")]
+    DynamicArrayWithoutType,
+    #[error("dynamic array elements type isn't an array")]
+    DynamicArrayTypeNotArray,
+}
+
+impl From<MalformedType> for Error {
+    fn from(malformed: MalformedType) -> Self {
+        Error::MalformedType(malformed)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
 }
 
-pub type Result<T> = std::result::Result<T, anyhow::Error>;
+fn nearest_log(id: usize, nearest: &Option<NearestLog>) -> String {
+    nearest.as_ref().map_or(String::new(), |nearest| {
+        format!(
+            ", nearest log {:#x} at {}:{} (off by {:+})",
+            nearest.id,
+            nearest.file,
+            nearest.line,
+            id as i128 - nearest.id as i128
+        )
+    })
+}
+
+/// Formats raw frame bytes as space separated hex, for example `de ad be ef`.
+pub(crate) fn hex_dump(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
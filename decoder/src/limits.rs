@@ -0,0 +1,128 @@
+//! Bounds on the resources spent decoding a single frame.
+//!
+//! Frames may come from untrusted devices, the [`Limits`] set on the [`Decoder`](crate::Decoder)
+//! make sure a malformed or malicious frame can't make it allocate or recurse without bound.
+
+use std::fmt;
+
+use crate::{Error, Result};
+
+/// The resource a [`Limits`] bound applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    /// The size of a frame, in bytes.
+    FrameSize,
+    /// The number of elements in an array, dynamic or not.
+    ArrayElements,
+    /// How deeply an argument's type is nested: structures, arrays, enumerations and pointers.
+    Depth,
+    /// The total size of a frame's dynamic arrays, in bytes.
+    DynamicData,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Limit::FrameSize => "frame size",
+            Limit::ArrayElements => "array elements",
+            Limit::Depth => "nesting depth",
+            Limit::DynamicData => "dynamic data",
+        })
+    }
+}
+
+/// Limits on the frames accepted by the decoder.
+///
+/// The defaults are far beyond what the C macros produce, while keeping a single frame's memory
+/// usage within a few megabytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    max_frame_size: usize,
+    max_array_elements: u64,
+    max_depth: usize,
+    max_dynamic_data: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_frame_size: 1024 * 1024,
+            max_array_elements: 64 * 1024,
+            max_depth: 32,
+            max_dynamic_data: 1024 * 1024,
+        }
+    }
+}
+
+impl Limits {
+    /// Creates the default limits.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Rejects frames longer than `max` bytes, including the log id.
+    pub fn with_max_frame_size(mut self, max: usize) -> Self {
+        self.max_frame_size = max;
+        self
+    }
+
+    /// Rejects arrays with more than `max` elements, multidimensional arrays are limited by the
+    /// product of their dimensions.
+    pub fn with_max_array_elements(mut self, max: u64) -> Self {
+        self.max_array_elements = max;
+        self
+    }
+
+    /// Rejects arguments whose types are nested more than `max` levels deep, a scalar argument
+    /// has a depth of 0.
+    pub fn with_max_depth(mut self, max: usize) -> Self {
+        self.max_depth = max;
+        self
+    }
+
+    /// Rejects frames whose dynamic arrays add up to more than `max` bytes.
+    pub fn with_max_dynamic_data(mut self, max: u64) -> Self {
+        self.max_dynamic_data = max;
+        self
+    }
+
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    pub fn max_array_elements(&self) -> u64 {
+        self.max_array_elements
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn max_dynamic_data(&self) -> u64 {
+        self.max_dynamic_data
+    }
+
+    pub(crate) fn check_frame_size(&self, size: usize) -> Result<()> {
+        check(Limit::FrameSize, size as u64, self.max_frame_size as u64)
+    }
+
+    pub(crate) fn check_array_elements(&self, elements: u64) -> Result<()> {
+        check(Limit::ArrayElements, elements, self.max_array_elements)
+    }
+
+    pub(crate) fn check_depth(&self, depth: usize) -> Result<()> {
+        check(Limit::Depth, depth as u64, self.max_depth as u64)
+    }
+
+    pub(crate) fn check_dynamic_data(&self, size: u64) -> Result<()> {
+        check(Limit::DynamicData, size, self.max_dynamic_data)
+    }
+}
+
+fn check(limit: Limit, value: u64, max: u64) -> Result<()> {
+    if value > max {
+        Err(Error::LimitExceeded { limit, value, max })
+    } else {
+        Ok(())
+    }
+}
@@ -3,21 +3,34 @@
 //! The logic contained within this file relates to using a log id to extract and parse the log's
 //! information from the elf.
 
-use anyhow::Context;
+use std::time::SystemTime;
+
 use cdefmt_parser::metadata::{Level, Metadata};
 use rformat::{fmt::format::format_string, prelude::*};
 
-use crate::{Result, var::Var};
+use crate::{Error, Result, var::Var};
 
 #[derive(Clone, Debug)]
 pub struct Log<'elf> {
     metadata: Metadata<'elf>,
     args: Vec<Var>,
+    timestamp: Option<SystemTime>,
+    trailing: Vec<u8>,
 }
 
 impl<'elf> Log<'elf> {
     pub(crate) fn new(metadata: Metadata<'elf>, args: Vec<Var>) -> Self {
-        Self { metadata, args }
+        Self {
+            metadata,
+            args,
+            timestamp: None,
+            trailing: Vec::new(),
+        }
+    }
+
+    /// Returns the log's id, the address of its metadata in the elf's `.cdefmt` section.
+    pub fn get_id(&self) -> usize {
+        self.metadata.id
     }
 
     pub fn get_level(&self) -> Level {
@@ -25,37 +38,135 @@ impl<'elf> Log<'elf> {
     }
 
     pub fn get_file(&self) -> &str {
-        self.metadata.file
+        &self.metadata.file
     }
 
     pub fn get_line(&self) -> usize {
         self.metadata.line
     }
 
+    /// Returns the log's format string.
+    pub fn get_fmt(&self) -> &str {
+        self.metadata.fmt
+    }
+
     pub fn get_args(&self) -> &[Var] {
         &self.args
     }
+
+    /// Returns the log's arguments paired with their names, in the order they were passed to the
+    /// log.
+    ///
+    /// The name of an argument is the expression that was passed to the log macro, for example
+    /// `some_struct.b`.
+    pub fn get_named_args(&self) -> impl Iterator<Item = (&str, &Var)> {
+        self.metadata.names.iter().copied().zip(self.args.iter())
+    }
+
+    /// Returns the argument called `name`.
+    pub fn get_arg(&self, name: &str) -> Option<&Var> {
+        self.get_named_args()
+            .find(|(n, _)| *n == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns whether some of the log's arguments are missing from its frame, see
+    /// [`Decoder::set_lenient`](crate::Decoder::set_lenient).
+    pub fn is_truncated(&self) -> bool {
+        self.args.iter().any(|arg| matches!(arg, Var::Truncated))
+    }
+
+    /// Returns the bytes at the end of the frame that weren't decoded as arguments.
+    ///
+    /// Only a lenient [`Decoder`](crate::Decoder) keeps them, they're the partial data of the
+    /// first truncated argument, or data the elf doesn't describe.
+    pub fn get_trailing(&self) -> &[u8] {
+        &self.trailing
+    }
+
+    pub(crate) fn set_trailing(&mut self, trailing: Vec<u8>) {
+        self.trailing = trailing;
+    }
+
+    /// Returns the host time at which the log was received, if one was attached.
+    pub fn get_timestamp(&self) -> Option<SystemTime> {
+        self.timestamp
+    }
+
+    /// Attaches the host time at which the log was received.
+    ///
+    /// The target doesn't send any timing information, so this is up to whoever reads the frames.
+    pub fn set_timestamp(&mut self, timestamp: SystemTime) {
+        self.timestamp = Some(timestamp);
+    }
 }
 
 impl Log<'_> {
     pub fn to_string(&self) -> Result<String> {
         let params = self
-            .args
-            .iter()
-            .zip(self.metadata.names.iter())
-            .map(|(a, n)| rformat::fmt::format::Parameter {
+            .get_named_args()
+            .map(|(n, a)| rformat::fmt::format::Parameter {
                 identifier: n,
                 formattable: rformat::formattable::into_formattable!((*a)),
             })
             .collect::<Vec<_>>();
 
-        format_string(self.metadata.fmt, &params).with_context(|| {
-            format!(
-                "{}:{}: Could not format '{}'",
-                self.get_file(),
-                self.get_line(),
-                self.metadata.fmt
-            )
+        format_string(self.metadata.fmt, &params).map_err(|source| Error::FormatError {
+            file: self.get_file().to_string(),
+            line: self.get_line(),
+            fmt: self.metadata.fmt.to_string(),
+            source,
         })
     }
 }
+
+/// Serializes the log's metadata, its named arguments as typed [`Var`] trees, the rendered
+/// message and the timestamp (seconds since the unix epoch).
+///
+/// If the message can't be rendered, `message` is `null` and `error` holds the reason.
+/// `trailing` holds the frame's undecoded bytes as hex, or `null` if there are none.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Log<'_> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        #[derive(serde::Serialize)]
+        struct Arg<'a> {
+            name: &'a str,
+            value: &'a Var,
+        }
+
+        let args = self
+            .get_named_args()
+            .map(|(name, value)| Arg { name, value })
+            .collect::<Vec<_>>();
+
+        let (message, error) = match self.to_string() {
+            Ok(message) => (Some(message), None),
+            Err(e) => (None, Some(format!("{e:#}"))),
+        };
+
+        let timestamp = self
+            .timestamp
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|t| t.as_secs_f64());
+
+        let trailing = (!self.trailing.is_empty()).then(|| crate::hex_dump(&self.trailing));
+
+        let mut log = serializer.serialize_struct("Log", 10)?;
+        log.serialize_field("timestamp", &timestamp)?;
+        log.serialize_field("id", &self.get_id())?;
+        log.serialize_field("level", &self.metadata.level)?;
+        log.serialize_field("file", &self.metadata.file)?;
+        log.serialize_field("line", &self.metadata.line)?;
+        log.serialize_field("fmt", self.get_fmt())?;
+        log.serialize_field("args", &args)?;
+        log.serialize_field("message", &message)?;
+        log.serialize_field("error", &error)?;
+        log.serialize_field("trailing", &trailing)?;
+        log.end()
+    }
+}
@@ -0,0 +1,140 @@
+//! Writing raw log frames as pcapng, so they can be inspected in Wireshark.
+//!
+//! Each source is written as its own interface, named after the source, and each frame as an
+//! enhanced packet block with the host time at which it was received, in nanoseconds.
+//! The frames use one of the link types reserved for private use, [`LINKTYPE_USER0`] by default,
+//! which Wireshark hands to the Lua dissector generated by
+//! [`cdefmt_parser::dissector::write_lua`].
+//!
+//! See <https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-02.html> for the format.
+
+use std::{collections::HashMap, io::Write, time::SystemTime};
+
+pub use cdefmt_parser::dissector::LINKTYPE_USER0;
+
+use crate::{Error, Result};
+
+const SECTION_HEADER: u32 = 0x0A0D_0D0A;
+const INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+const ENHANCED_PACKET: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+const OPT_END: u16 = 0;
+const SHB_USERAPPL: u16 = 4;
+const IF_NAME: u16 = 2;
+const IF_TSRESOL: u16 = 9;
+
+/// Writes frames to a pcapng file.
+pub struct Writer<W: Write> {
+    writer: W,
+    link_type: u16,
+    interfaces: HashMap<String, u32>,
+}
+
+impl<W: Write> Writer<W> {
+    /// Writes the section header, frames can then be written using [`Self::write`].
+    ///
+    /// `link_type` is the link type of every interface, usually [`LINKTYPE_USER0`].
+    pub fn new(mut writer: W, link_type: u16) -> Result<Self> {
+        let mut body = Vec::new();
+        body.extend(BYTE_ORDER_MAGIC.to_le_bytes());
+        // Version 1.0.
+        body.extend(1u16.to_le_bytes());
+        body.extend(0u16.to_le_bytes());
+        // Unknown section length.
+        body.extend((-1i64).to_le_bytes());
+        push_option(&mut body, SHB_USERAPPL, b"cdefmt");
+        push_option(&mut body, OPT_END, &[]);
+
+        write_block(&mut writer, SECTION_HEADER, &body)?;
+
+        Ok(Self {
+            writer,
+            link_type,
+            interfaces: Default::default(),
+        })
+    }
+
+    /// Writes a frame received from `source` at `timestamp`.
+    ///
+    /// An interface is described the first time a frame is written from each source.
+    pub fn write(&mut self, timestamp: SystemTime, source: &str, data: &[u8]) -> Result<()> {
+        let interface = match self.interfaces.get(source) {
+            Some(interface) => *interface,
+            None => self.add_interface(source)?,
+        };
+
+        let timestamp = timestamp
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        let len = u32::try_from(data.len()).map_err(|_| Error::TooLarge("The frame"))?;
+
+        let mut body = Vec::with_capacity(20 + data.len() + 3);
+        body.extend(interface.to_le_bytes());
+        body.extend(((timestamp >> 32) as u32).to_le_bytes());
+        body.extend((timestamp as u32).to_le_bytes());
+        // Captured and original lengths, frames are never truncated.
+        body.extend(len.to_le_bytes());
+        body.extend(len.to_le_bytes());
+        body.extend(data);
+        pad(&mut body);
+
+        write_block(&mut self.writer, ENHANCED_PACKET, &body)
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn add_interface(&mut self, source: &str) -> Result<u32> {
+        let interface = u32::try_from(self.interfaces.len())
+            .map_err(|_| Error::TooLarge("The number of sources"))?;
+
+        let mut body = Vec::new();
+        body.extend(self.link_type.to_le_bytes());
+        // Reserved.
+        body.extend(0u16.to_le_bytes());
+        // No snapshot length limit.
+        body.extend(0u32.to_le_bytes());
+        push_option(&mut body, IF_NAME, source.as_bytes());
+        // Timestamps are in units of 10^-9 seconds.
+        push_option(&mut body, IF_TSRESOL, &[9]);
+        push_option(&mut body, OPT_END, &[]);
+
+        write_block(&mut self.writer, INTERFACE_DESCRIPTION, &body)?;
+        self.interfaces.insert(source.to_string(), interface);
+
+        Ok(interface)
+    }
+}
+
+/// Writes a block whose `body` is already padded to 32 bits.
+fn write_block(writer: &mut impl Write, ty: u32, body: &[u8]) -> Result<()> {
+    // The block type and the two copies of the total length.
+    let len = u32::try_from(body.len() + 12).map_err(|_| Error::TooLarge("The block"))?;
+
+    writer.write_all(&ty.to_le_bytes())?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(body)?;
+    writer.write_all(&len.to_le_bytes())?;
+
+    Ok(())
+}
+
+fn push_option(body: &mut Vec<u8>, code: u16, value: &[u8]) {
+    body.extend(code.to_le_bytes());
+    body.extend((value.len() as u16).to_le_bytes());
+    body.extend(value);
+    pad(body);
+}
+
+/// Pads `body` with zeros to a multiple of 32 bits.
+fn pad(body: &mut Vec<u8>) {
+    body.resize(body.len().next_multiple_of(4), 0);
+}
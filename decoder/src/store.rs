@@ -0,0 +1,258 @@
+//! Decoding logs from several firmware versions.
+//!
+//! A [`Store`] indexes elfs by their GNU build ID, the [`StoreDecoder`] uses it to switch to the
+//! elf that generated the logs every time an init log arrives, for example when a device is
+//! updated in the middle of a capture.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use cdefmt_parser::path::PathMap;
+use object::Object;
+
+use crate::{Decoder, Error, Result, filter::Filter, limits::Limits, log::Log};
+
+/// A collection of elfs, indexed by their GNU build ID.
+#[derive(Default)]
+pub struct Store {
+    elfs: HashMap<Vec<u8>, Elf>,
+}
+
+struct Elf {
+    path: PathBuf,
+    data: memmap2::Mmap,
+}
+
+impl Store {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates a store containing all of the elfs in `dir`, see [`Self::add_dir`].
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let mut store = Self::new();
+        store.add_dir(dir)?;
+        Ok(store)
+    }
+
+    /// Adds all of the elfs in `dir` and its subdirectories, returning how many were added.
+    ///
+    /// Files that aren't elfs, or have no build ID, are skipped.
+    /// Symbolic links are followed, each directory is only searched once.
+    /// If several elfs have the same build ID, the last one found is kept.
+    pub fn add_dir(&mut self, dir: impl AsRef<Path>) -> Result<usize> {
+        self.add_dir_once(dir.as_ref(), &mut HashSet::new())
+    }
+
+    /// Adds the elfs in `dir` unless it's in `visited`, which holds canonical paths.
+    fn add_dir_once(&mut self, dir: &Path, visited: &mut HashSet<PathBuf>) -> Result<usize> {
+        let error = |source| Error::File {
+            path: dir.to_path_buf(),
+            source,
+        };
+        if !visited.insert(std::fs::canonicalize(dir).map_err(error)?) {
+            return Ok(0);
+        }
+
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir).map_err(error)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                count += self.add_dir_once(&path, visited)?;
+            } else if self.add_file(&path).is_ok() {
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Adds a single elf, returning its build ID.
+    pub fn add_file(&mut self, path: impl AsRef<Path>) -> Result<Vec<u8>> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path).map_err(|source| Error::File {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        // SAFETY: the mapping is only sound as long as the file isn't modified, which can't be
+        // enforced. Elfs are build outputs that aren't expected to change while they're decoded,
+        // and a file that's replaced rather than modified in place keeps the old contents mapped.
+        let data = unsafe { memmap2::Mmap::map(&file) }?;
+
+        let build_id = object::File::parse(&*data)?
+            .build_id()?
+            .ok_or(Error::MissingBuildId)?
+            .to_vec();
+
+        let elf = Elf {
+            path: path.to_path_buf(),
+            data,
+        };
+        self.elfs.insert(build_id.clone(), elf);
+
+        Ok(build_id)
+    }
+
+    /// Returns the contents of the elf with the given build ID.
+    pub fn get(&self, build_id: &[u8]) -> Option<&[u8]> {
+        self.elfs.get(build_id).map(|elf| &*elf.data)
+    }
+
+    /// Returns the path of the elf with the given build ID.
+    pub fn path(&self, build_id: &[u8]) -> Option<&Path> {
+        self.elfs.get(build_id).map(|elf| elf.path.as_path())
+    }
+
+    /// Iterates over the build IDs of all of the elfs in the store.
+    pub fn build_ids(&self) -> impl Iterator<Item = &[u8]> {
+        self.elfs.keys().map(Vec::as_slice)
+    }
+
+    pub fn len(&self) -> usize {
+        self.elfs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elfs.is_empty()
+    }
+}
+
+/// Decodes logs using the elfs of a [`Store`], switching to the elf whose build ID is sent in
+/// each init log.
+///
+/// A [`Decoder`] is created for each elf the first time it's used, and kept for the next time the
+/// device switches back to it.
+pub struct StoreDecoder<'store> {
+    store: &'store Store,
+    decoders: HashMap<&'store [u8], Decoder<'store>>,
+    current: Option<&'store [u8]>,
+    path_map: PathMap,
+    filter: Filter,
+    limits: Limits,
+    lenient: bool,
+}
+
+impl<'store> StoreDecoder<'store> {
+    /// Creates a decoder that has no elf selected, logs can only be decoded once an init log
+    /// arrives, or an elf is selected using [`Self::select`].
+    pub fn new(store: &'store Store) -> Self {
+        Self {
+            store,
+            decoders: Default::default(),
+            current: None,
+            path_map: Default::default(),
+            filter: Default::default(),
+            limits: Default::default(),
+            lenient: false,
+        }
+    }
+
+    /// Decodes the following logs using the elf with the given build ID.
+    pub fn select(&mut self, build_id: &[u8]) -> Result<&mut Decoder<'store>> {
+        let (build_id, elf) = self
+            .store
+            .elfs
+            .get_key_value(build_id)
+            .ok_or_else(|| Error::UnknownBuildId(build_id.to_vec()))?;
+
+        if !self.decoders.contains_key(build_id.as_slice()) {
+            let mut decoder = Decoder::new(&*elf.data).map_err(|source| Error::Elf {
+                path: elf.path.clone(),
+                source: Box::new(source),
+            })?;
+            decoder.set_path_map(self.path_map.clone());
+            decoder.set_filter(self.filter.clone());
+            decoder.set_limits(self.limits);
+            decoder.set_lenient(self.lenient);
+            self.decoders.insert(build_id, decoder);
+        }
+
+        self.current = Some(build_id);
+        // Unwrap safety: made sure that the entry exists right above here.
+        Ok(self.decoders.get_mut(build_id.as_slice()).unwrap())
+    }
+
+    /// Returns the decoder of the currently selected elf.
+    pub fn decoder(&self) -> Option<&Decoder<'store>> {
+        self.current.and_then(|id| self.decoders.get(id))
+    }
+
+    /// Decodes a raw log, see [`Decoder::decode_log`].
+    pub fn decode_log(&mut self, data: &[u8]) -> Result<Log<'store>> {
+        self.decoder_for(data)?.decode_log(data)
+    }
+
+    /// Decodes a raw log unless it's rejected by the filter, see
+    /// [`Decoder::decode_log_filtered`].
+    pub fn decode_log_filtered(&mut self, data: &[u8]) -> Result<Option<Log<'store>>> {
+        self.decoder_for(data)?.decode_log_filtered(data)
+    }
+
+    /// Sets the path prefix remapping of every elf, see [`Decoder::set_path_map`].
+    pub fn set_path_map(&mut self, path_map: PathMap) {
+        for decoder in self.decoders.values_mut() {
+            decoder.set_path_map(path_map.clone());
+        }
+        self.path_map = path_map;
+    }
+
+    /// Sets the filter of every elf, see [`Decoder::set_filter`].
+    pub fn set_filter(&mut self, filter: Filter) {
+        for decoder in self.decoders.values_mut() {
+            decoder.set_filter(filter.clone());
+        }
+        self.filter = filter;
+    }
+
+    /// Sets the limits of every elf, see [`Decoder::set_limits`].
+    pub fn set_limits(&mut self, limits: Limits) {
+        for decoder in self.decoders.values_mut() {
+            decoder.set_limits(limits);
+        }
+        self.limits = limits;
+    }
+
+    /// Sets whether every elf decodes truncated frames leniently, see [`Decoder::set_lenient`].
+    pub fn set_lenient(&mut self, lenient: bool) {
+        for decoder in self.decoders.values_mut() {
+            decoder.set_lenient(lenient);
+        }
+        self.lenient = lenient;
+    }
+
+    /// Returns the decoder that should decode `data`, switching elfs if it's an init log of
+    /// another elf in the store.
+    fn decoder_for(&mut self, data: &[u8]) -> Result<&mut Decoder<'store>> {
+        if let Some(build_id) = self.init_build_id(data)
+            && self.current != Some(build_id)
+        {
+            return self.select(build_id);
+        }
+
+        match self.current {
+            Some(build_id) => Ok(self.decoders.get_mut(build_id).unwrap()),
+            None => Err(Error::NoElfSelected),
+        }
+    }
+
+    /// If `data` is an init log, whose build ID is in the store, returns the build ID.
+    ///
+    /// The init log's arguments are the log id, which is 0, followed by the build ID.
+    /// The frame is checked for both 32 and 64 bit ids, since the frame's target isn't known yet.
+    fn init_build_id(&self, data: &[u8]) -> Option<&'store [u8]> {
+        [8, 4].into_iter().find_map(|id_size| {
+            let (id, build_id) = data.split_at_checked(id_size)?;
+            if id.iter().any(|b| *b != 0) {
+                return None;
+            }
+
+            self.store
+                .elfs
+                .get_key_value(build_id)
+                .map(|(build_id, _)| build_id.as_slice())
+        })
+    }
+}
@@ -0,0 +1,671 @@
+//! Rendering logs as human readable text using user defined templates.
+//!
+//! A template is a string containing fields in braces, for example
+//! `{timestamp} {level:>7} {file}:{line} {message}`.
+//! Fields may be padded using a `[[fill]align]width` spec, similar to rust's format strings,
+//! where `align` is one of `<`, `^` or `>`.
+//! Braces are escaped by doubling them: `{{` and `}}`.
+//!
+//! The available fields are:
+//! | field       | description                                                       |
+//! | ----------- | ----------------------------------------------------------------- |
+//! | `timestamp` | When the log was received, as an ISO 8601 UTC date and time.      |
+//! | `time`      | When the log was received, as an UTC time of day.                 |
+//! | `level`     | The log's level.                                                  |
+//! | `file`      | The source file containing the log, see [`Template::with_paths`]. |
+//! | `line`      | The line in the source file.                                      |
+//! | `id`        | The log's id, in hex.                                             |
+//! | `fmt`       | The log's unformatted format string.                              |
+//! | `message`   | The formatted log message, followed by any trailing frame bytes.  |
+//!
+//! The `file` and `line` fields can also be turned into terminal hyperlinks, see [`Link`].
+
+use std::{
+    ffi::OsStr,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use cdefmt_parser::metadata::Level;
+
+use crate::{Result, log::Log};
+
+/// The template used when none is specified.
+pub const DEFAULT_TEMPLATE: &str = "{level:<7} > {message}";
+
+/// Renders logs according to a template.
+#[derive(Clone, Debug)]
+pub struct Template {
+    parts: Vec<Part<Field>>,
+    color: bool,
+    paths: Paths,
+    link: Option<Link>,
+}
+
+/// How source file paths are displayed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Paths {
+    /// As they're recorded in the elf.
+    #[default]
+    AsIs,
+    /// Relative to the given directory, for example the project's root.
+    /// Paths outside of the directory are displayed as they're recorded in the elf.
+    RelativeTo(PathBuf),
+}
+
+/// When to use ANSI colours.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Only when writing to a terminal, and the `NO_COLOR` environment variable isn't set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Resolves whether colours should be used when writing to `stream`.
+    pub fn enabled(self, stream: &impl IsTerminal) -> bool {
+        self.resolve(
+            stream.is_terminal(),
+            std::env::var_os("NO_COLOR").as_deref(),
+        )
+    }
+
+    /// Resolves whether colours should be used given whether the output is a terminal and the
+    /// value of `NO_COLOR`, which only counts if it isn't empty.
+    fn resolve(self, terminal: bool, no_color: Option<&OsStr>) -> bool {
+        match self {
+            ColorChoice::Auto => terminal && no_color.is_none_or(|v| v.is_empty()),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// The error returned when parsing an unknown [`ColorChoice`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("Unknown color choice '{0}', expected one of: auto, always, never")]
+pub struct UnknownColorChoice(pub String);
+
+impl FromStr for ColorChoice {
+    type Err = UnknownColorChoice;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(UnknownColorChoice(s.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Part<F> {
+    Literal(String),
+    Field { field: F, spec: Spec },
+}
+
+/// A template that couldn't be parsed, see [`Template::new`] and [`Link::new`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{kind} at offset {position}")]
+pub struct TemplateError {
+    pub kind: TemplateErrorKind,
+    /// The byte offset in the template of the unmatched brace, or of the invalid field's `{`.
+    pub position: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum TemplateErrorKind {
+    #[error("unmatched '{{'")]
+    UnmatchedOpenBrace,
+    #[error("unmatched '}}'")]
+    UnmatchedCloseBrace,
+    #[error("unknown field '{0}'")]
+    UnknownField(String),
+    #[error("invalid spec '{0}'")]
+    InvalidSpec(String),
+}
+
+/// Parses a template whose fields are of type `F`.
+fn parse<F: FromStr<Err = TemplateErrorKind>>(
+    template: &str,
+) -> std::result::Result<Vec<Part<F>>, TemplateError> {
+    let error = |kind, position| TemplateError { kind, position };
+    let mut parts = vec![];
+    let mut literal = String::new();
+    let mut chars = template.char_indices().peekable();
+
+    while let Some((position, c)) = chars.next() {
+        match c {
+            '{' if chars.peek().is_some_and(|(_, c)| *c == '{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek().is_some_and(|(_, c)| *c == '}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut field = String::new();
+                let mut closed = false;
+                for (_, c) in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    field.push(c);
+                }
+                if !closed {
+                    return Err(error(TemplateErrorKind::UnmatchedOpenBrace, position));
+                }
+
+                let (name, spec) = field.split_once(':').unwrap_or((&field, ""));
+
+                if !literal.is_empty() {
+                    parts.push(Part::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(Part::Field {
+                    field: name.trim().parse().map_err(|e| error(e, position))?,
+                    spec: spec.parse().map_err(|e| error(e, position))?,
+                });
+            }
+            '}' => return Err(error(TemplateErrorKind::UnmatchedCloseBrace, position)),
+            c => literal.push(c),
+        }
+    }
+
+    if !literal.is_empty() {
+        parts.push(Part::Literal(literal));
+    }
+
+    Ok(parts)
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Field {
+    Timestamp,
+    Time,
+    Level,
+    File,
+    Line,
+    Id,
+    Fmt,
+    Message,
+}
+
+impl FromStr for Field {
+    type Err = TemplateErrorKind;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "timestamp" => Field::Timestamp,
+            "time" => Field::Time,
+            "level" => Field::Level,
+            "file" => Field::File,
+            "line" => Field::Line,
+            "id" => Field::Id,
+            "fmt" => Field::Fmt,
+            "message" => Field::Message,
+            _ => return Err(TemplateErrorKind::UnknownField(s.to_string())),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Spec {
+    fill: char,
+    align: Align,
+    width: usize,
+}
+
+impl Default for Spec {
+    fn default() -> Self {
+        Self {
+            fill: ' ',
+            align: Align::Left,
+            width: 0,
+        }
+    }
+}
+
+impl FromStr for Spec {
+    type Err = TemplateErrorKind;
+
+    /// Parses `[[fill]align][width]`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        fn align(c: char) -> Option<Align> {
+            match c {
+                '<' => Some(Align::Left),
+                '^' => Some(Align::Center),
+                '>' => Some(Align::Right),
+                _ => None,
+            }
+        }
+
+        let mut spec = Spec::default();
+        let mut chars = s.chars();
+        let rest = match (chars.next(), chars.next().and_then(align)) {
+            (Some(fill), Some(a)) => {
+                spec.fill = fill;
+                spec.align = a;
+                chars.as_str()
+            }
+            (Some(c), _) if align(c).is_some() => {
+                spec.align = align(c).unwrap();
+                &s[c.len_utf8()..]
+            }
+            _ => s,
+        };
+
+        if !rest.is_empty() {
+            spec.width = rest
+                .parse()
+                .map_err(|_| TemplateErrorKind::InvalidSpec(s.to_string()))?;
+        }
+
+        Ok(spec)
+    }
+}
+
+impl Spec {
+    fn pad(&self, value: &str) -> String {
+        let len = value.chars().count();
+        let padding = self.width.saturating_sub(len);
+        let (before, after) = match self.align {
+            Align::Left => (0, padding),
+            Align::Center => (padding / 2, padding - padding / 2),
+            Align::Right => (padding, 0),
+        };
+
+        let fill = |n| std::iter::repeat_n(self.fill, n);
+        fill(before)
+            .chain(value.chars())
+            .chain(fill(after))
+            .collect()
+    }
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        // Unwrap safety: the default template is valid.
+        Self::new(DEFAULT_TEMPLATE).unwrap()
+    }
+}
+
+impl Template {
+    /// Parses a template, see the [module level documentation](self) for the syntax.
+    pub fn new(template: &str) -> Result<Self> {
+        Ok(Self {
+            parts: parse(template)?,
+            color: false,
+            paths: Paths::AsIs,
+            link: None,
+        })
+    }
+
+    /// Whether to colour the output using ANSI escape codes, disabled by default.
+    ///
+    /// Use [`ColorChoice::enabled`] to only colour output written to a terminal.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// How to display the `file` field.
+    pub fn with_paths(mut self, paths: Paths) -> Self {
+        self.paths = paths;
+        self
+    }
+
+    /// Turns the `file` and `line` fields into OSC 8 hyperlinks pointing to `link`, disabled by
+    /// default.
+    ///
+    /// Terminals that don't support OSC 8 usually print the text as is, but some print garbage,
+    /// so links should only be enabled when writing to a terminal.
+    pub fn with_link(mut self, link: Option<Link>) -> Self {
+        self.link = link;
+        self
+    }
+
+    /// Renders `log` according to the template.
+    pub fn render(&self, log: &Log) -> String {
+        let mut output = String::new();
+        let url = self
+            .link
+            .as_ref()
+            .and_then(|link| link.url(log, &self.file(log.get_file())));
+
+        for part in &self.parts {
+            let (field, spec) = match part {
+                Part::Literal(literal) => {
+                    output.push_str(literal);
+                    continue;
+                }
+                Part::Field { field, spec } => (field, spec),
+            };
+
+            let value = match field {
+                Field::Timestamp => log.get_timestamp().map(iso8601).unwrap_or_default(),
+                Field::Time => log.get_timestamp().map(time_of_day).unwrap_or_default(),
+                Field::Level => log.get_level().to_string(),
+                Field::File => self.file(log.get_file()),
+                Field::Line => log.get_line().to_string(),
+                Field::Id => format!("{:#x}", log.get_id()),
+                Field::Fmt => log.get_fmt().to_string(),
+                Field::Message => {
+                    let mut message = match log.to_string() {
+                        Ok(message) => message,
+                        Err(e) => format!("Error: {e:#}"),
+                    };
+                    if !log.get_trailing().is_empty() {
+                        message.push_str(&format!(
+                            " [trailing: {}]",
+                            crate::hex_dump(log.get_trailing())
+                        ));
+                    }
+                    message
+                }
+            };
+            let value = spec.pad(&value);
+
+            // Padding is applied first, so escape codes don't count towards the width.
+            let color = match field {
+                _ if !self.color => None,
+                Field::Level => Some(level_color(log.get_level())),
+                Field::Timestamp | Field::Time | Field::File | Field::Line => Some(DIM),
+                _ => None,
+            };
+
+            let link = match field {
+                Field::File | Field::Line => url.as_deref(),
+                _ => None,
+            };
+
+            if let Some(url) = link {
+                output.extend([OSC8, url, ST]);
+            }
+            match color {
+                Some(color) => output.extend([color, &value, RESET]),
+                None => output.push_str(&value),
+            }
+            if link.is_some() {
+                output.extend([OSC8, ST]);
+            }
+        }
+
+        output
+    }
+
+    fn file(&self, file: &str) -> String {
+        match &self.paths {
+            Paths::AsIs => file.to_string(),
+            Paths::RelativeTo(base) => Path::new(file)
+                .strip_prefix(base)
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|_| file.to_string()),
+        }
+    }
+}
+
+/// Turns a log's source location into a URL, for example to open it in an editor or a git
+/// forge.
+///
+/// The URL is a template, using the same syntax as [`Template`], with the fields:
+/// | field      | description                                                                  |
+/// | ---------- | ---------------------------------------------------------------------------- |
+/// | `file`     | The source file, as returned by [`Log::get_file`].                           |
+/// | `path`     | The source file, as displayed by the template, see [`Template::with_paths`]. |
+/// | `line`     | The line in the source file.                                                 |
+/// | `commit`   | The commit the elf was built from, see [`Link::with_commit`].                |
+/// | `build_id` | The elf's build ID, in hex, see [`Link::with_build_id`].                     |
+///
+/// For example `https://github.com/org/repo/blob/{commit}/{path}#L{line}`.
+#[derive(Clone, Debug)]
+pub struct Link {
+    parts: Vec<Part<LinkField>>,
+    commit: Option<String>,
+    build_id: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum LinkField {
+    File,
+    Path,
+    Line,
+    Commit,
+    BuildId,
+}
+
+impl FromStr for LinkField {
+    type Err = TemplateErrorKind;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "file" => LinkField::File,
+            "path" => LinkField::Path,
+            "line" => LinkField::Line,
+            "commit" => LinkField::Commit,
+            "build_id" => LinkField::BuildId,
+            _ => return Err(TemplateErrorKind::UnknownField(s.to_string())),
+        })
+    }
+}
+
+impl Default for Link {
+    /// Links to the source file on the local machine.
+    fn default() -> Self {
+        // Unwrap safety: the default link is valid.
+        Self::new("file://{file}").unwrap()
+    }
+}
+
+impl Link {
+    pub fn new(url: &str) -> Result<Self> {
+        Ok(Self {
+            parts: parse(url)?,
+            commit: None,
+            build_id: None,
+        })
+    }
+
+    /// Sets the commit the elf was built from, usually looked up using the elf's build ID.
+    pub fn with_commit(mut self, commit: impl Into<String>) -> Self {
+        self.commit = Some(commit.into());
+        self
+    }
+
+    pub fn with_build_id(mut self, build_id: &[u8]) -> Self {
+        self.build_id = Some(build_id.iter().map(|b| format!("{b:02x}")).collect());
+        self
+    }
+
+    /// Builds the URL of the log's source location, `path` is the file as displayed.
+    ///
+    /// Returns `None` if the URL needs a commit or build ID that isn't known.
+    pub fn url(&self, log: &Log, path: &str) -> Option<String> {
+        let mut url = String::new();
+
+        for part in &self.parts {
+            let (field, spec) = match part {
+                Part::Literal(literal) => {
+                    url.push_str(literal);
+                    continue;
+                }
+                Part::Field { field, spec } => (field, spec),
+            };
+
+            let value = match field {
+                LinkField::File => encode_path(log.get_file()),
+                LinkField::Path => encode_path(path),
+                LinkField::Line => log.get_line().to_string(),
+                LinkField::Commit => self.commit.clone()?,
+                LinkField::BuildId => self.build_id.clone()?,
+            };
+            url.push_str(&spec.pad(&value));
+        }
+
+        Some(url)
+    }
+}
+
+/// Percent encodes the characters of a path that aren't allowed in URLs, keeping the separators.
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::new();
+
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'-'
+            | b'.'
+            | b'_'
+            | b'~'
+            | b'/'
+            | b':'
+            | b'@'
+            | b'+'
+            | b','
+            | b'=' => encoded.push(byte as char),
+            // Windows paths.
+            b'\\' => encoded.push('/'),
+            byte => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    encoded
+}
+
+/// Starts an OSC 8 hyperlink, followed by the URL and [`ST`], an empty URL ends the link.
+const OSC8: &str = "\x1b]8;;";
+/// String terminator, ends an OSC sequence.
+const ST: &str = "\x1b\\";
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+
+fn level_color(level: Level) -> &'static str {
+    match level {
+        Level::Error => "\x1b[1;31m",
+        Level::Warning => "\x1b[33m",
+        Level::Info => "\x1b[32m",
+        Level::Debug => "\x1b[34m",
+        Level::Verbose => "\x1b[90m",
+    }
+}
+
+/// Splits a timestamp into days since the unix epoch, and milliseconds into that day.
+fn split(timestamp: SystemTime) -> (i64, u64) {
+    let millis = timestamp
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+    ((millis / 86_400_000) as i64, millis % 86_400_000)
+}
+
+fn time_of_day(timestamp: SystemTime) -> String {
+    let (_, millis) = split(timestamp);
+    let seconds = millis / 1000;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        millis % 1000
+    )
+}
+
+fn iso8601(timestamp: SystemTime) -> String {
+    let (days, _) = split(timestamp);
+
+    // Converts days since the epoch to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}T{}Z", time_of_day(timestamp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_choice() {
+        let no_color = Some(OsStr::new("1"));
+        let empty = Some(OsStr::new(""));
+
+        assert!(ColorChoice::Auto.resolve(true, None));
+        assert!(ColorChoice::Auto.resolve(true, empty));
+        assert!(!ColorChoice::Auto.resolve(true, no_color));
+        assert!(!ColorChoice::Auto.resolve(false, None));
+
+        // Explicit choices ignore both.
+        assert!(ColorChoice::Always.resolve(false, no_color));
+        assert!(!ColorChoice::Never.resolve(true, None));
+
+        assert_eq!(
+            "always".parse::<ColorChoice>().unwrap(),
+            ColorChoice::Always
+        );
+        assert!(matches!(
+            "sometimes".parse::<ColorChoice>(),
+            Err(UnknownColorChoice(s)) if s == "sometimes"
+        ));
+    }
+
+    #[test]
+    fn encode_path() {
+        assert_eq!(super::encode_path("/src/main.c"), "/src/main.c");
+        assert_eq!(
+            super::encode_path("/my src/50%#1?.c"),
+            "/my%20src/50%25%231%3F.c"
+        );
+        assert_eq!(super::encode_path("C:\\src\\main.c"), "C:/src/main.c");
+        // UTF-8 is encoded byte by byte.
+        assert_eq!(super::encode_path("/é.c"), "/%C3%A9.c");
+        // Escape sequences can't end the OSC 8 sequence early.
+        assert_eq!(super::encode_path("/a\x1b\\b"), "/a%1B/b");
+    }
+
+    #[test]
+    fn spec() {
+        let pad = |spec: &str, value| spec.parse::<Spec>().unwrap().pad(value);
+
+        assert_eq!(pad("", "ab"), "ab");
+        assert_eq!(pad("5", "ab"), "ab   ");
+        assert_eq!(pad(">5", "ab"), "   ab");
+        assert_eq!(pad("^5", "ab"), " ab  ");
+        assert_eq!(pad("*<5", "ab"), "ab***");
+        assert_eq!(pad("<<4", "ab"), "ab<<");
+        assert_eq!(pad("é>4", "ab"), "ééab");
+        // Too long values aren't truncated.
+        assert_eq!(pad(">1", "abc"), "abc");
+        // The width counts characters, not bytes.
+        assert_eq!(pad("3", "é"), "é  ");
+
+        for spec in ["x", ">x", "-1", "<<<"] {
+            assert_eq!(
+                spec.parse::<Spec>().err(),
+                Some(TemplateErrorKind::InvalidSpec(spec.to_string()))
+            );
+        }
+    }
+}
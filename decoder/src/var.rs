@@ -1,9 +1,12 @@
 use std::collections::BTreeMap;
 
-use cdefmt_parser::r#type::Type;
+use cdefmt_parser::{
+    format::{FormatTrait, Kind},
+    r#type::Type,
+};
 use gimli::{Reader, ReaderOffset};
 
-use crate::Result;
+use crate::{MalformedType, Result, limits::Limits};
 
 #[derive(Debug, Clone)]
 pub enum Var {
@@ -27,18 +30,46 @@ pub enum Var {
     },
     Pointer(Box<Var>),
     Array(Vec<Var>),
+    /// An argument missing from a truncated frame, only produced by a lenient
+    /// [`Decoder`](crate::Decoder).
+    Truncated,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct StructureMember {
     pub name: String,
     pub value: Var,
 }
 
 impl Var {
+    /// Parses a value of type `ty`, returning it along with the number of bytes it was encoded in.
+    ///
+    /// The value is bounded by the default [`Limits`].
     pub fn parse<R: Reader>(ty: &Type, data: &mut R) -> Result<(Self, u64)> {
+        Self::parse_with_limits(ty, data, &Limits::default())
+    }
+
+    /// Like [`Self::parse`], failing with [`Error::LimitExceeded`] if the value exceeds `limits`.
+    pub fn parse_with_limits<R: Reader>(
+        ty: &Type,
+        data: &mut R,
+        limits: &Limits,
+    ) -> Result<(Self, u64)> {
+        Self::parse_nested(ty, data, limits, 0)
+    }
+
+    /// Parses a value nested `depth` levels deep within an argument.
+    fn parse_nested<R: Reader>(
+        ty: &Type,
+        data: &mut R,
+        limits: &Limits,
+        depth: usize,
+    ) -> Result<(Self, u64)> {
+        limits.check_depth(depth)?;
+
         Ok(match ty {
-            Type::Bool => (Var::Bool(data.read_u8()? == 0), 1),
+            Type::Bool => (Var::Bool(data.read_u8()? != 0), 1),
             Type::U8 => (Var::U8(data.read_u8()?), 1),
             Type::U16 => (Var::U16(data.read_u16()?), 2),
             Type::U32 => (Var::U32(data.read_u32()?), 4),
@@ -53,7 +84,7 @@ impl Var {
                 ty: inner_type,
                 valid_values,
             } => {
-                let (value, bytes) = Self::parse(inner_type, data)?;
+                let (value, bytes) = Self::parse_nested(inner_type, data, limits, depth + 1)?;
                 (
                     Var::Enumeration {
                         value: Box::new(value),
@@ -73,7 +104,7 @@ impl Var {
                             total_offset += bytes_to_skip;
                         }
 
-                        let (var, bytes) = Self::parse(&m.ty, data)?;
+                        let (var, bytes) = Self::parse_nested(&m.ty, data, limits, depth + 1)?;
                         total_offset += bytes;
 
                         Ok(StructureMember {
@@ -83,64 +114,275 @@ impl Var {
                     })
                     .collect::<Result<Vec<_>>>()?;
 
-                let bytes_to_skip = *size as u64 - total_offset;
+                let bytes_to_skip = (*size as u64)
+                    .checked_sub(total_offset)
+                    .ok_or(MalformedType::StructureOverflow)?;
                 data.skip(ReaderOffset::from_u64(bytes_to_skip)?)?;
 
                 (Var::Structure { members }, *size as u64)
             }
             Type::Pointer(ty) => {
-                let (value, bytes) = Self::parse(ty, data)?;
+                let (value, bytes) = Self::parse_nested(ty, data, limits, depth + 1)?;
                 (Var::Pointer(Box::new(value)), bytes)
             }
             Type::Array { ty, lengths } => {
-                let l = lengths[0];
+                let (&l, inner_lengths) = lengths
+                    .split_first()
+                    .ok_or(MalformedType::ArrayWithoutDimensions)?;
+
+                // Multidimensional arrays are arrays of arrays.
+                let element_ty = if inner_lengths.is_empty() {
+                    ty.as_ref().clone()
+                } else {
+                    Type::Array {
+                        ty: ty.clone(),
+                        lengths: inner_lengths.to_vec(),
+                    }
+                };
+
+                // Make sure the data holds all the elements before allocating them, the length may
+                // come from the frame itself.
+                let elements = lengths
+                    .iter()
+                    .try_fold(1u64, |total, length| total.checked_mul(*length))
+                    .unwrap_or(u64::MAX);
+                limits.check_array_elements(elements)?;
+                let element_size = element_ty.size() as u64;
+                if element_size == 0 && l > 0 {
+                    return Err(MalformedType::ZeroSizeElements.into());
+                }
+                let bytes = l
+                    .checked_mul(element_size)
+                    .filter(|bytes| *bytes <= data.len().into_u64())
+                    .ok_or(gimli::Error::UnexpectedEof(data.offset_id()))?;
 
                 let mut values = Vec::with_capacity(l as usize);
                 for _ in 0..l {
-                    let (val, _) = Self::parse(ty, data)?;
+                    let (val, _) = Self::parse_nested(&element_ty, data, limits, depth + 1)?;
                     values.push(val);
                 }
 
-                (Var::Array(values), 0)
+                (Var::Array(values), bytes)
             }
         })
     }
 
+    /// Returns the value as a `u64`.
+    ///
+    /// Booleans, integers, enumerations and pointers are converted using an `as` cast, so signed
+    /// values are sign extended.
+    /// Returns `None` for floats, structures and arrays.
+    pub fn try_as_u64(&self) -> Option<u64> {
+        match self {
+            Var::Bool(v) => Some(*v as u64),
+            Var::U8(v) => Some(*v as u64),
+            Var::U16(v) => Some(*v as u64),
+            Var::U32(v) => Some(*v as u64),
+            Var::U64(v) => Some(*v),
+            Var::I8(v) => Some(*v as u64),
+            Var::I16(v) => Some(*v as u64),
+            Var::I32(v) => Some(*v as u64),
+            Var::I64(v) => Some(*v as u64),
+            Var::Enumeration { value, .. } => value.try_as_u64(),
+            Var::Pointer(value) => value.try_as_u64(),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as an `i128`, which is wide enough to hold any signed or unsigned integer
+    /// without loss.
+    ///
+    /// Returns `None` for floats, structures and arrays.
+    pub fn try_as_i128(&self) -> Option<i128> {
+        match self {
+            Var::Bool(v) => Some(*v as i128),
+            Var::U8(v) => Some(*v as i128),
+            Var::U16(v) => Some(*v as i128),
+            Var::U32(v) => Some(*v as i128),
+            Var::U64(v) => Some(*v as i128),
+            Var::I8(v) => Some(*v as i128),
+            Var::I16(v) => Some(*v as i128),
+            Var::I32(v) => Some(*v as i128),
+            Var::I64(v) => Some(*v as i128),
+            Var::Enumeration { value, .. } => value.try_as_i128(),
+            Var::Pointer(value) => value.try_as_i128(),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a `u64`, floats are truncated and values that aren't numbers are 0.
+    #[deprecated(
+        since = "0.8.0",
+        note = "use `try_as_u64`, which returns `None` for values that aren't integers"
+    )]
     pub fn as_u64(&self) -> u64 {
         match self {
-            Var::Bool(v) => *v as u64,
-            Var::U8(v) => *v as u64,
-            Var::U16(v) => *v as u64,
-            Var::U32(v) => *v as u64,
-            Var::U64(v) => *v,
-            Var::I8(v) => *v as u64,
-            Var::I16(v) => *v as u64,
-            Var::I32(v) => *v as u64,
-            Var::I64(v) => *v as u64,
             Var::F32(v) => *v as u64,
             Var::F64(v) => *v as u64,
-            _ => todo!("Should probably return an Option here or something"),
+            _ => self.try_as_u64().unwrap_or_default(),
         }
     }
 
+    /// Returns the value as an `i128`, floats are truncated and values that aren't numbers are 0.
+    #[deprecated(
+        since = "0.8.0",
+        note = "use `try_as_i128`, which returns `None` for values that aren't integers"
+    )]
     pub fn as_i128(&self) -> i128 {
         match self {
-            Var::Bool(v) => *v as i128,
-            Var::U8(v) => *v as i128,
-            Var::U16(v) => *v as i128,
-            Var::U32(v) => *v as i128,
-            Var::U64(v) => *v as i128,
-            Var::I8(v) => *v as i128,
-            Var::I16(v) => *v as i128,
-            Var::I32(v) => *v as i128,
-            Var::I64(v) => *v as i128,
             Var::F32(v) => *v as i128,
             Var::F64(v) => *v as i128,
-            _ => todo!("Should probably return an Option here or something"),
+            _ => self.try_as_i128().unwrap_or_default(),
         }
     }
 
+    /// Returns the value as an `f64`.
+    ///
+    /// Integers are converted using an `as` cast, which may lose precision for large 64 bit
+    /// values.
+    /// Returns `None` for booleans, enumerations, pointers, structures and arrays.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Var::U8(v) => Some(*v as f64),
+            Var::U16(v) => Some(*v as f64),
+            Var::U32(v) => Some(*v as f64),
+            Var::U64(v) => Some(*v as f64),
+            Var::I8(v) => Some(*v as f64),
+            Var::I16(v) => Some(*v as f64),
+            Var::I32(v) => Some(*v as f64),
+            Var::I64(v) => Some(*v as f64),
+            Var::F32(v) => Some(*v as f64),
+            Var::F64(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Var::Bool(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the raw bytes of a `u8`/`i8` value or of an array of them.
+    ///
+    /// Multi dimensional arrays are flattened.
+    /// Returns `None` if any of the elements isn't a `u8` or `i8`.
+    pub fn as_bytes(&self) -> Option<Vec<u8>> {
+        match self {
+            Var::U8(v) => Some(vec![*v]),
+            Var::I8(v) => Some(vec![*v as u8]),
+            Var::Array(elements) => {
+                let mut bytes = Vec::with_capacity(elements.len());
+                for element in elements {
+                    bytes.extend(element.as_bytes()?);
+                }
+                Some(bytes)
+            }
+            _ => None,
+        }
+    }
+
+    /// Interprets the value as a C string.
+    ///
+    /// The string ends at the first null terminator, or at the end of the array if there's none.
+    /// Returns `None` if the value isn't a `char` array or the string isn't valid UTF-8.
+    pub fn as_str(&self) -> Option<String> {
+        let mut bytes = self.as_bytes()?;
+        if let Some(end) = bytes.iter().position(|b| *b == 0) {
+            bytes.truncate(end);
+        }
+        String::from_utf8(bytes).ok()
+    }
+
+    /// Returns the structure member called `name`.
+    pub fn member(&self, name: &str) -> Option<&Var> {
+        match self {
+            Var::Structure { members } => members.iter().find(|m| m.name == name).map(|m| &m.value),
+            _ => None,
+        }
+    }
+
+    /// Returns the array element at `index`.
+    pub fn index(&self, index: usize) -> Option<&Var> {
+        match self {
+            Var::Array(elements) => elements.get(index),
+            _ => None,
+        }
+    }
+
+    /// Looks up a nested value by path, for example `"foo.bar[2]"`.
+    ///
+    /// Path segments are separated by `.` and name structure members, while `[n]` indexes into
+    /// arrays.
+    /// An empty path returns `self`.
+    /// Returns `None` if the path is malformed or doesn't exist in the value.
+    pub fn get(&self, path: &str) -> Option<&Var> {
+        if path.is_empty() {
+            return Some(self);
+        }
+
+        let mut var = self;
+        for segment in path.split('.') {
+            let (name, mut indices) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+
+            if !name.is_empty() {
+                var = var.member(name)?;
+            } else if indices.is_empty() {
+                // Empty segment, for example `foo..bar`.
+                return None;
+            }
+
+            while !indices.is_empty() {
+                let rest = indices.strip_prefix('[')?;
+                let end = rest.find(']')?;
+                var = var.index(rest[..end].trim().parse().ok()?)?;
+                indices = &rest[end + 1..];
+            }
+        }
+
+        Some(var)
+    }
+
+    /// Iterates over the direct children of the value: structure member values or array
+    /// elements.
+    /// Scalar values have no children.
+    pub fn iter(&self) -> Iter<'_> {
+        match self {
+            Var::Structure { members } => Iter::Members(members.iter()),
+            Var::Array(elements) => Iter::Elements(elements.iter()),
+            _ => Iter::Elements([].iter()),
+        }
+    }
+
+    /// Returns the kind of the value, `None` if it's truncated.
+    pub fn kind(&self) -> Option<Kind> {
+        Some(match self {
+            Var::Bool(_) => Kind::Bool,
+            Var::U8(_) | Var::I8(_) => Kind::Char,
+            Var::U16(_) | Var::U32(_) | Var::U64(_) | Var::I16(_) | Var::I32(_) | Var::I64(_) => {
+                Kind::Integer
+            }
+            Var::F32(_) | Var::F64(_) => Kind::Float,
+            Var::Enumeration { .. } => Kind::Enumeration,
+            Var::Pointer(_) => Kind::Pointer,
+            Var::Structure { .. } => Kind::Structure,
+            Var::Array(_) => Kind::Array,
+            Var::Truncated => return None,
+        })
+    }
+
     fn format_as_string(&self) -> rformat::error::Result<String> {
+        let unsupported =
+            || rformat::error::FormatError::Custom(format!("Can't format {:?} as string!", self));
+
+        match self.kind() {
+            None => return Ok("<truncated>".to_string()),
+            Some(kind) if !FormatTrait::String.supports(kind) => return Err(unsupported()),
+            Some(_) => {}
+        }
+
         match self {
             Var::U8(v) => Ok(String::from_utf8_lossy(&[*v]).to_string()),
             Var::I8(v) => Ok(String::from_utf8_lossy(&[*v as u8]).to_string()),
@@ -149,17 +391,56 @@ impl Var {
                 .map(|e| e.format_as_string())
                 .collect::<rformat::error::Result<Vec<_>>>()?
                 .join("")),
-            _ => Err(rformat::error::FormatError::Custom(format!(
-                "Can't format {:?} as string!",
-                self
-            ))),
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+/// Iterator over the children of a [`Var`], see [`Var::iter`].
+pub enum Iter<'a> {
+    Members(std::slice::Iter<'a, StructureMember>),
+    Elements(std::slice::Iter<'a, Var>),
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Var;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Iter::Members(members) => members.next().map(|m| &m.value),
+            Iter::Elements(elements) => elements.next(),
         }
     }
 }
 
+impl<'a> IntoIterator for &'a Var {
+    type Item = &'a Var;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Formats truncated values, and returns early if the value's kind doesn't support the trait, see
+/// [`FormatTrait::supports`].
+macro_rules! check_supported {
+    ($f: expr, $var: expr, $trait: expr, $name: expr) => {
+        match $var.kind() {
+            None => return $f.pad("<truncated>"),
+            Some(kind) if !$trait.supports(kind) => {
+                return write!($f, "Can't format {:?} as {}!", $var, $name);
+            }
+            Some(_) => {}
+        }
+    };
+}
+
 macro_rules! format_enumeration {
     ($f: expr, $value: expr, $valid_values: expr) => {{
-        let value = $value.as_i128();
+        let Some(value) = $value.try_as_i128() else {
+            return write!($f, "Unknown({:?})", $value);
+        };
         if let Some(name) = $valid_values.get(&value) {
             write!($f, "{}(", name)?;
             value.fmt($f)?;
@@ -202,6 +483,8 @@ macro_rules! format_array {
 
 impl core::fmt::Binary for Var {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        check_supported!(f, self, FormatTrait::Binary, "binary");
+
         match self {
             Var::U8(v) => v.fmt(f),
             Var::U16(v) => v.fmt(f),
@@ -246,12 +529,15 @@ impl core::fmt::Display for Var {
             Var::Structure { members } => format_structure!(f, members),
             Var::Pointer(inner) => inner.fmt(f),
             Var::Array(elements) => format_array!(f, elements),
+            Var::Truncated => f.pad("<truncated>"),
         }
     }
 }
 
 impl core::fmt::LowerExp for Var {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        check_supported!(f, self, FormatTrait::LowerExp, "lower exponential");
+
         match self {
             Var::U8(v) => v.fmt(f),
             Var::U16(v) => v.fmt(f),
@@ -272,6 +558,8 @@ impl core::fmt::LowerExp for Var {
 
 impl core::fmt::LowerHex for Var {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        check_supported!(f, self, FormatTrait::LowerHex, "lower hexadecimal");
+
         match self {
             Var::U8(v) => v.fmt(f),
             Var::U16(v) => v.fmt(f),
@@ -295,6 +583,8 @@ impl core::fmt::LowerHex for Var {
 
 impl core::fmt::Octal for Var {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        check_supported!(f, self, FormatTrait::Octal, "octal");
+
         match self {
             Var::U8(v) => v.fmt(f),
             Var::U16(v) => v.fmt(f),
@@ -318,6 +608,8 @@ impl core::fmt::Octal for Var {
 
 impl core::fmt::Pointer for Var {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        check_supported!(f, self, FormatTrait::Pointer, "pointer");
+
         match self {
             Var::U8(v) => ((*v) as *const u8).fmt(f),
             Var::U16(v) => ((*v) as *const u16).fmt(f),
@@ -328,7 +620,7 @@ impl core::fmt::Pointer for Var {
             Var::I32(v) => ((*v) as *const i32).fmt(f),
             Var::I64(v) => ((*v) as *const i64).fmt(f),
             Var::Structure { members } => format_structure!(f, members),
-            Var::Pointer(inner) => (*inner).fmt(f),
+            Var::Pointer(inner) => core::fmt::Pointer::fmt(inner.as_ref(), f),
             Var::Array(elements) => format_array!(f, elements),
             _ => write!(f, "Can't format {:?} as pointer!", self),
         }
@@ -337,6 +629,8 @@ impl core::fmt::Pointer for Var {
 
 impl core::fmt::UpperExp for Var {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        check_supported!(f, self, FormatTrait::UpperExp, "upper exponential");
+
         match self {
             Var::U8(v) => v.fmt(f),
             Var::U16(v) => v.fmt(f),
@@ -348,12 +642,7 @@ impl core::fmt::UpperExp for Var {
             Var::I64(v) => v.fmt(f),
             Var::F32(v) => v.fmt(f),
             Var::F64(v) => v.fmt(f),
-            Var::Enumeration {
-                value,
-                valid_values,
-            } => format_enumeration!(f, value, valid_values),
             Var::Structure { members } => format_structure!(f, members),
-            Var::Pointer(inner) => inner.fmt(f),
             Var::Array(elements) => format_array!(f, elements),
             _ => write!(f, "Can't format {:?} as upper exponential!", self),
         }
@@ -362,6 +651,8 @@ impl core::fmt::UpperExp for Var {
 
 impl core::fmt::UpperHex for Var {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        check_supported!(f, self, FormatTrait::UpperHex, "upper hexadecimal");
+
         match self {
             Var::U8(v) => v.fmt(f),
             Var::U16(v) => v.fmt(f),
@@ -399,3 +690,333 @@ impl rformat::fmt::Custom for Var {
         }
     }
 }
+
+/// Serializes the value as a typed tree, for example `{"type": "u32", "value": 5}`.
+///
+/// Enumerations additionally carry the `name` of their value (if it's a valid one), structures
+/// carry a list of named `members` and arrays carry their `elements`.
+/// Truncated values only have a `type`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Var {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(None)?;
+        match self {
+            Var::Bool(v) => {
+                map.serialize_entry("type", "bool")?;
+                map.serialize_entry("value", v)?;
+            }
+            Var::U8(v) => {
+                map.serialize_entry("type", "u8")?;
+                map.serialize_entry("value", v)?;
+            }
+            Var::U16(v) => {
+                map.serialize_entry("type", "u16")?;
+                map.serialize_entry("value", v)?;
+            }
+            Var::U32(v) => {
+                map.serialize_entry("type", "u32")?;
+                map.serialize_entry("value", v)?;
+            }
+            Var::U64(v) => {
+                map.serialize_entry("type", "u64")?;
+                map.serialize_entry("value", v)?;
+            }
+            Var::I8(v) => {
+                map.serialize_entry("type", "i8")?;
+                map.serialize_entry("value", v)?;
+            }
+            Var::I16(v) => {
+                map.serialize_entry("type", "i16")?;
+                map.serialize_entry("value", v)?;
+            }
+            Var::I32(v) => {
+                map.serialize_entry("type", "i32")?;
+                map.serialize_entry("value", v)?;
+            }
+            Var::I64(v) => {
+                map.serialize_entry("type", "i64")?;
+                map.serialize_entry("value", v)?;
+            }
+            Var::F32(v) => {
+                map.serialize_entry("type", "f32")?;
+                map.serialize_entry("value", v)?;
+            }
+            Var::F64(v) => {
+                map.serialize_entry("type", "f64")?;
+                map.serialize_entry("value", v)?;
+            }
+            Var::Enumeration {
+                value,
+                valid_values,
+            } => {
+                map.serialize_entry("type", "enumeration")?;
+                map.serialize_entry("value", value)?;
+                let name = value.try_as_i128().and_then(|v| valid_values.get(&v));
+                map.serialize_entry("name", &name)?;
+            }
+            Var::Structure { members } => {
+                map.serialize_entry("type", "structure")?;
+                map.serialize_entry("members", members)?;
+            }
+            Var::Pointer(value) => {
+                map.serialize_entry("type", "pointer")?;
+                map.serialize_entry("value", value)?;
+            }
+            Var::Array(elements) => {
+                map.serialize_entry("type", "array")?;
+                map.serialize_entry("elements", elements)?;
+            }
+            Var::Truncated => {
+                map.serialize_entry("type", "truncated")?;
+            }
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A value of every kind.
+    fn samples() -> Vec<Var> {
+        vec![
+            Var::Bool(true),
+            Var::U8(b'a'),
+            Var::I8(b'b' as i8),
+            Var::U16(1),
+            Var::U32(2),
+            Var::U64(3),
+            Var::I16(-1),
+            Var::I32(-2),
+            Var::I64(-3),
+            Var::F32(1.5),
+            Var::F64(2.5),
+            Var::Enumeration {
+                value: Box::new(Var::I32(1)),
+                valid_values: BTreeMap::from([(1, "ONE".to_string())]),
+            },
+            Var::Pointer(Box::new(Var::U64(0x1000))),
+            Var::Structure {
+                members: vec![StructureMember {
+                    name: "a".to_string(),
+                    value: Var::U32(4),
+                }],
+            },
+            Var::Array(vec![Var::U8(b'c'), Var::U8(b'd')]),
+        ]
+    }
+
+    type Format = fn(&Var) -> String;
+
+    /// The values implement exactly the formatting traits the shared table allows, which is what
+    /// `Parser::check` validates format strings with.
+    #[test]
+    fn format_traits_match_table() {
+        let traits: [(FormatTrait, Format); 8] = [
+            (FormatTrait::Binary, |v| format!("{v:b}")),
+            (FormatTrait::Octal, |v| format!("{v:o}")),
+            (FormatTrait::LowerHex, |v| format!("{v:x}")),
+            (FormatTrait::UpperHex, |v| format!("{v:X}")),
+            (FormatTrait::LowerExp, |v| format!("{v:e}")),
+            (FormatTrait::UpperExp, |v| format!("{v:E}")),
+            // `{v:p}` would format the reference.
+            (FormatTrait::Pointer, |v| format!("{:p}", *v)),
+            (FormatTrait::String, |v| {
+                v.format_as_string()
+                    .unwrap_or_else(|_| format!("Can't format {v:?} as string!"))
+            }),
+        ];
+
+        for var in samples() {
+            let kind = var.kind().unwrap();
+            for (format_trait, format) in traits {
+                let formatted = format(&var);
+                assert_eq!(
+                    !formatted.starts_with("Can't format"),
+                    format_trait.supports(kind),
+                    "{format_trait:?} of {var:?}: {formatted}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn format_pointer() {
+        let pointer = Var::Pointer(Box::new(Var::U64(0x1000)));
+        assert_eq!(format!("{pointer:p}"), "0x1000");
+        assert_eq!(format!("{pointer:x}"), "1000");
+    }
+
+    #[test]
+    fn format_truncated() {
+        assert_eq!(format!("{:x}", Var::Truncated), "<truncated>");
+        assert_eq!(Var::Truncated.format_as_string().unwrap(), "<truncated>");
+    }
+
+    fn member(name: &str, value: Var) -> StructureMember {
+        StructureMember {
+            name: name.to_string(),
+            value,
+        }
+    }
+
+    /// `{ foo: { bar: [10, 11, 12] }, grid: [[1, 2], [3, 4]], flag: true }`
+    fn nested() -> Var {
+        let row = |a, b| Var::Array(vec![Var::U8(a), Var::U8(b)]);
+        Var::Structure {
+            members: vec![
+                member(
+                    "foo",
+                    Var::Structure {
+                        members: vec![member(
+                            "bar",
+                            Var::Array(vec![Var::U32(10), Var::U32(11), Var::U32(12)]),
+                        )],
+                    },
+                ),
+                member("grid", Var::Array(vec![row(1, 2), row(3, 4)])),
+                member("flag", Var::Bool(true)),
+            ],
+        }
+    }
+
+    #[test]
+    fn get() {
+        let var = nested();
+        let get = |path| var.get(path).and_then(Var::try_as_u64);
+
+        assert_eq!(get("foo.bar[2]"), Some(12));
+        assert_eq!(get("foo.bar[ 0 ]"), Some(10));
+        assert_eq!(get("grid[1][0]"), Some(3));
+        assert_eq!(get("flag"), Some(1));
+        assert!(matches!(var.get(""), Some(Var::Structure { .. })));
+        assert!(matches!(var.get("foo.bar"), Some(Var::Array(_))));
+
+        // Paths that don't exist in the value.
+        assert_eq!(get("foo.bar[3]"), None);
+        assert_eq!(get("foo.baz"), None);
+        assert_eq!(get("flag.value"), None);
+        assert_eq!(get("flag[0]"), None);
+        assert_eq!(get("foo[0]"), None);
+
+        // Malformed paths.
+        assert_eq!(get("foo..bar[0]"), None);
+        assert_eq!(get(".foo"), None);
+        assert_eq!(get("foo."), None);
+        assert_eq!(get("foo.bar[1"), None);
+        assert_eq!(get("foo.bar[x]"), None);
+        assert_eq!(get("foo.bar[-1]"), None);
+        assert_eq!(get("foo.bar[0]x"), None);
+    }
+
+    #[test]
+    fn member_and_index() {
+        let var = nested();
+        assert!(var.member("flag").is_some());
+        assert!(var.member("missing").is_none());
+        assert!(var.index(0).is_none());
+
+        let grid = var.member("grid").unwrap();
+        assert!(grid.index(1).is_some());
+        assert!(grid.index(2).is_none());
+        assert!(grid.member("flag").is_none());
+
+        assert_eq!(var.iter().count(), 3);
+        assert_eq!(Var::U8(0).iter().count(), 0);
+    }
+
+    #[test]
+    fn as_integer() {
+        assert_eq!(Var::Bool(true).try_as_u64(), Some(1));
+        assert_eq!(Var::U64(u64::MAX).try_as_u64(), Some(u64::MAX));
+        assert_eq!(Var::I8(-1).try_as_u64(), Some(u64::MAX));
+        assert_eq!(
+            Var::Pointer(Box::new(Var::U32(0x10))).try_as_u64(),
+            Some(0x10)
+        );
+        assert_eq!(Var::U64(u64::MAX).try_as_i128(), Some(u64::MAX as i128));
+        assert_eq!(Var::I64(i64::MIN).try_as_i128(), Some(i64::MIN as i128));
+
+        let enumeration = &samples()[11];
+        assert_eq!(enumeration.try_as_u64(), Some(1));
+        assert_eq!(enumeration.try_as_i128(), Some(1));
+
+        for var in [Var::F32(1.0), nested(), Var::Array(vec![]), Var::Truncated] {
+            assert_eq!(var.try_as_u64(), None, "{var:?}");
+            assert_eq!(var.try_as_i128(), None, "{var:?}");
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn as_integer_deprecated() {
+        assert_eq!(Var::I8(-1).as_u64(), u64::MAX);
+        assert_eq!(Var::I8(-1).as_i128(), -1);
+        assert_eq!(Var::F64(2.5).as_u64(), 2);
+        assert_eq!(Var::F32(-2.5).as_i128(), -2);
+        assert_eq!(nested().as_u64(), 0);
+        assert_eq!(Var::Truncated.as_i128(), 0);
+    }
+
+    #[test]
+    fn as_f64() {
+        assert_eq!(Var::F32(1.5).as_f64(), Some(1.5));
+        assert_eq!(Var::I16(-3).as_f64(), Some(-3.0));
+        assert_eq!(Var::U64(7).as_f64(), Some(7.0));
+
+        for var in [
+            Var::Bool(true),
+            Var::Pointer(Box::new(Var::U64(1))),
+            samples()[11].clone(),
+            nested(),
+            Var::Truncated,
+        ] {
+            assert_eq!(var.as_f64(), None, "{var:?}");
+        }
+    }
+
+    #[test]
+    fn as_bool() {
+        assert_eq!(Var::Bool(false).as_bool(), Some(false));
+        assert_eq!(Var::U8(1).as_bool(), None);
+        assert_eq!(Var::Truncated.as_bool(), None);
+    }
+
+    #[test]
+    fn as_bytes_and_str() {
+        let text = |s: &[u8]| Var::Array(s.iter().map(|b| Var::I8(*b as i8)).collect());
+
+        assert_eq!(Var::U8(0xff).as_bytes(), Some(vec![0xff]));
+        assert_eq!(text(b"ab\0c").as_bytes(), Some(b"ab\0c".to_vec()));
+        assert_eq!(text(b"ab\0c").as_str().as_deref(), Some("ab"));
+        assert_eq!(text(b"abc").as_str().as_deref(), Some("abc"));
+        assert_eq!(
+            nested().get("grid").unwrap().as_bytes(),
+            Some(vec![1, 2, 3, 4])
+        );
+
+        // Not a char array, or not UTF-8.
+        assert_eq!(Var::Array(vec![Var::U16(1)]).as_bytes(), None);
+        assert_eq!(Var::U32(1).as_bytes(), None);
+        assert_eq!(Var::Bool(true).as_str(), None);
+        assert_eq!(text(&[0xff, 0xfe]).as_str(), None);
+    }
+
+    #[test]
+    fn parse_bool() {
+        // C treats any nonzero value as true.
+        for (byte, expected) in [(0, false), (1, true), (2, true), (0xff, true)] {
+            let bytes = [byte];
+            let mut data = gimli::EndianSlice::new(&bytes, gimli::LittleEndian);
+            let (var, size) = Var::parse(&Type::Bool, &mut data).unwrap();
+            assert_eq!(var.as_bool(), Some(expected), "{byte:#x}");
+            assert_eq!(size, 1);
+        }
+    }
+}
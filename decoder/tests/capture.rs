@@ -0,0 +1,130 @@
+//! Writing frames to captures and reading them back.
+
+use std::time::{Duration, SystemTime};
+
+use cdefmt_decoder::{
+    Error,
+    capture::{Frame, Header, MAGIC, Reader, VERSION, Writer},
+};
+
+fn header() -> Header {
+    Header {
+        build_ids: vec![vec![0xaa; 20], vec![0xbb, 0xcc]],
+    }
+}
+
+fn frames() -> Vec<Frame> {
+    let at = |nanos| SystemTime::UNIX_EPOCH + Duration::from_nanos(nanos);
+    let frame = |nanos, source: &str, data: &[u8]| Frame {
+        timestamp: at(nanos),
+        source: source.to_string(),
+        data: data.to_vec(),
+    };
+
+    vec![
+        frame(1_700_000_000_123_456_789, "serial:/dev/ttyUSB0", &[1, 2, 3]),
+        frame(1_700_000_001_000_000_000, "tcp:10.0.0.2:4000", &[]),
+        frame(1_700_000_002_000_000_001, "serial:/dev/ttyUSB0", &[0; 300]),
+    ]
+}
+
+fn write(header: &Header, frames: &[Frame]) -> Vec<u8> {
+    let mut writer = Writer::new(Vec::new(), header).unwrap();
+    for frame in frames {
+        writer
+            .write(frame.timestamp, &frame.source, &frame.data)
+            .unwrap();
+    }
+    writer.into_inner()
+}
+
+fn read(capture: &[u8]) -> Result<Vec<Frame>, Error> {
+    Reader::new(capture)?.collect()
+}
+
+/// A record as the writer lays it out.
+fn record(kind: u8, payload: &[u8]) -> Vec<u8> {
+    let mut record = vec![kind];
+    record.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    record.extend_from_slice(payload);
+    record
+}
+
+#[test]
+fn round_trip() {
+    let capture = write(&header(), &frames());
+
+    let reader = Reader::new(capture.as_slice()).unwrap();
+    assert_eq!(reader.header(), &header());
+    assert_eq!(reader.collect::<Result<Vec<_>, _>>().unwrap(), frames());
+
+    // Each source is only defined once: 2 sources and 3 frames.
+    let header_size = MAGIC.len() + 2 + 2 + (1 + 20) + (1 + 2);
+    let sources = (5 + 2 + 19) + (5 + 2 + 17);
+    let frames = (5 + 10 + 3) + (5 + 10) + (5 + 10 + 300);
+    assert_eq!(capture.len(), header_size + sources + frames);
+}
+
+#[test]
+fn empty() {
+    let capture = write(&Header::default(), &[]);
+    assert_eq!(capture.len(), MAGIC.len() + 4);
+    assert_eq!(read(&capture).unwrap(), []);
+}
+
+#[test]
+fn unknown_records_are_skipped() {
+    let frames = frames();
+    let mut capture = write(&header(), &frames[..1]);
+    capture.extend(record(0x7f, b"from a newer version"));
+    capture.extend(record(0x80, b""));
+    capture.extend(write(&Header::default(), &frames[..1]).split_off(MAGIC.len() + 4));
+
+    assert_eq!(
+        read(&capture).unwrap(),
+        [frames[0].clone(), frames[0].clone()]
+    );
+}
+
+#[test]
+fn truncated_record() {
+    let frames = frames();
+    let capture = write(&header(), &frames);
+
+    // Cut in the middle of the last frame's payload, or of its length.
+    for cut in [1, 300, 301, 314] {
+        let mut reader = Reader::new(&capture[..capture.len() - cut]).unwrap();
+        assert_eq!(reader.next().unwrap().unwrap(), frames[0]);
+        assert_eq!(reader.next().unwrap().unwrap(), frames[1]);
+        assert!(
+            matches!(reader.next(), Some(Err(Error::TruncatedRecord))),
+            "cut {cut}"
+        );
+    }
+}
+
+#[test]
+fn malformed() {
+    let mut capture = MAGIC.to_vec();
+    capture.extend((VERSION + 1).to_le_bytes());
+    capture.extend(0u16.to_le_bytes());
+    assert!(matches!(
+        read(&capture),
+        Err(Error::UnsupportedCaptureVersion(v)) if v == VERSION + 1
+    ));
+
+    assert!(matches!(read(b"NOTACAPTURE!"), Err(Error::NotACapture)));
+
+    // A frame from a source that was never defined.
+    let mut capture = write(&Header::default(), &[]);
+    capture.extend(record(2, &[5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff]));
+    assert!(matches!(read(&capture), Err(Error::UndefinedSource(5))));
+
+    // Too short for the source index and timestamp.
+    let mut capture = write(&Header::default(), &[]);
+    capture.extend(record(2, &[0, 0, 0]));
+    assert!(matches!(
+        read(&capture),
+        Err(Error::MalformedRecord("frame"))
+    ));
+}
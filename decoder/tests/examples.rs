@@ -0,0 +1,247 @@
+//! Builds the C examples with the host's compilers, decodes the logs they write and compares them
+//! to the golden files in `tests/golden`.
+//!
+//! Every configuration of an example (compiler and optimization level) must decode to the same
+//! golden file, so differences in the debugging information emitted by each compiler are caught.
+//!
+//! The examples need the boost preprocessor and vmd headers, which are looked for in the
+//! directories listed in `CDEFMT_BOOST_INCLUDE`, in the cmake build directory (`build/_deps`), and
+//! in the system's include directories.
+//! Configurations whose compiler isn't installed are skipped, as are all of them when boost isn't
+//! found, while a missing golden file fails the test.
+//!
+//! Run with `CDEFMT_BLESS=1` to write the golden files from the first configuration that builds.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use cdefmt_decoder::{Decoder, Error};
+
+const ROOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/..");
+const GOLDEN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+
+const OPTIMIZATIONS: [&str; 2] = ["-O0", "-O2"];
+
+struct Example {
+    name: &'static str,
+    source: &'static str,
+    standard: &'static str,
+    compilers: [&'static str; 2],
+}
+
+const STDOUT: Example = Example {
+    name: "stdout",
+    source: "main.c",
+    standard: "-std=c11",
+    compilers: ["gcc", "clang"],
+};
+
+const STDOUT_CPP: Example = Example {
+    name: "stdout-cpp",
+    source: "main.cpp",
+    standard: "-std=c++17",
+    compilers: ["g++", "clang++"],
+};
+
+#[test]
+fn stdout() {
+    check(&STDOUT);
+}
+
+#[test]
+fn stdout_cpp() {
+    check(&STDOUT_CPP);
+}
+
+fn check(example: &Example) {
+    let Some(boost) = boost_include() else {
+        eprintln!(
+            "Skipping {}: could not find the boost headers, set CDEFMT_BOOST_INCLUDE to their \
+             directory",
+            example.name
+        );
+        return;
+    };
+
+    let golden_path = Path::new(GOLDEN).join(format!("{}.txt", example.name));
+    let mut bless = env::var_os("CDEFMT_BLESS").is_some();
+    let mut failures = Vec::new();
+
+    for compiler in example.compilers {
+        if !installed(compiler) {
+            eprintln!("Skipping {} {compiler}: not found", example.name);
+            continue;
+        }
+
+        for optimization in OPTIMIZATIONS {
+            let configuration = format!("{} {compiler} {optimization}", example.name);
+            let output = match run(example, compiler, optimization, &boost) {
+                Ok(output) => output,
+                Err(e) => {
+                    failures.push(format!("{configuration}: {e}"));
+                    continue;
+                }
+            };
+
+            if bless {
+                fs::create_dir_all(GOLDEN).unwrap();
+                fs::write(&golden_path, &output).unwrap();
+                bless = false;
+                continue;
+            }
+
+            let golden = fs::read_to_string(&golden_path).unwrap_or_else(|e| {
+                panic!(
+                    "Could not read {}: {e}, run with CDEFMT_BLESS=1 to create it",
+                    golden_path.display()
+                )
+            });
+
+            if let Some(difference) = difference(&golden, &output) {
+                failures.push(format!("{configuration}: {difference}"));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+/// Builds and runs the example, returning its decoded logs, one per line.
+fn run(
+    example: &Example,
+    compiler: &str,
+    optimization: &str,
+    boost: &[PathBuf],
+) -> Result<String, String> {
+    let directory = Path::new(ROOT).join("examples").join(example.name);
+    let binary = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join(format!("example-{}-{compiler}{optimization}", example.name));
+
+    let mut command = Command::new(compiler);
+    command
+        .arg(example.standard)
+        .arg(optimization)
+        .args(["-g", "-Wl,--build-id"])
+        .arg(format!("-T{}", directory.join("linkerscript.ld").display()))
+        .arg("-I")
+        .arg(&directory)
+        .arg("-I")
+        .arg(ROOT);
+    for include in boost {
+        command.arg("-I").arg(include);
+    }
+    let build = command
+        .arg(directory.join(example.source))
+        .arg("-o")
+        .arg(&binary)
+        .output()
+        .map_err(|e| format!("Could not run {compiler}: {e}"))?;
+    if !build.status.success() {
+        return Err(format!(
+            "Build failed:\n{}",
+            String::from_utf8_lossy(&build.stderr)
+        ));
+    }
+
+    // stdout is a pipe, so the example writes length-value pairs instead of pretty printing.
+    let execution = Command::new(&binary)
+        .stdout(Stdio::piped())
+        .output()
+        .map_err(|e| format!("Could not run the example: {e}"))?;
+    if !execution.status.success() {
+        return Err(format!("The example exited with {}", execution.status));
+    }
+
+    let elf = fs::read(&binary).map_err(|e| format!("Could not read the elf: {e}"))?;
+    let mut decoder =
+        Decoder::new(elf.as_slice()).map_err(|e| format!("Could not load the elf: {e}"))?;
+
+    let mut output = String::new();
+    let mut frames = execution.stdout.as_slice();
+    while let Some((len, rest)) = frames.split_first_chunk::<8>() {
+        let len = u64::from_ne_bytes(*len) as usize;
+        if rest.len() < len {
+            return Err("The example's output is truncated".to_string());
+        }
+        let (frame, rest) = rest.split_at(len);
+        frames = rest;
+
+        let line = match decoder.decode_log(frame) {
+            Ok(log) => {
+                // Only the file name, the path depends on where the repository is checked out.
+                let file = Path::new(log.get_file())
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy();
+                // The error's location would repeat the path.
+                let message = log.to_string().unwrap_or_else(|e| match e {
+                    Error::FormatError { source, .. } => format!("<error: {source}>"),
+                    e => format!("<error: {e}>"),
+                });
+                format!("{} {file}:{} {message}", log.get_level(), log.get_line())
+            }
+            Err(e) => format!("<error: {e}>"),
+        };
+        output.push_str(&line);
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+/// Describes the first line where `output` differs from `golden`.
+fn difference(golden: &str, output: &str) -> Option<String> {
+    let mut golden_lines = golden.lines();
+    let mut output_lines = output.lines();
+
+    for number in 1.. {
+        match (golden_lines.next(), output_lines.next()) {
+            (None, None) => return None,
+            (expected, found) if expected == found => continue,
+            (expected, found) => {
+                return Some(format!(
+                    "line {number}: expected {:?}, found {:?}",
+                    expected.unwrap_or("<end>"),
+                    found.unwrap_or("<end>")
+                ));
+            }
+        }
+    }
+
+    unreachable!()
+}
+
+fn installed(compiler: &str) -> bool {
+    Command::new(compiler)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Returns the extra include directories needed to find boost, `None` if it can't be found.
+fn boost_include() -> Option<Vec<PathBuf>> {
+    let mut include = env::var_os("CDEFMT_BOOST_INCLUDE")
+        .map(|paths| env::split_paths(&paths).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let dependencies = Path::new(ROOT).join("build").join("_deps");
+    include.extend(["preprocessor", "vmd"].map(|d| dependencies.join(format!("{d}-src/include"))));
+    include.retain(|path| path.is_dir());
+
+    let system = [Path::new("/usr/include"), Path::new("/usr/local/include")];
+    let found = |header: &str| {
+        include
+            .iter()
+            .map(PathBuf::as_path)
+            .chain(system)
+            .any(|path| path.join(header).is_file())
+    };
+
+    (found("boost/preprocessor/stringize.hpp") && found("boost/vmd/is_tuple.hpp"))
+        .then_some(include)
+}
@@ -0,0 +1,164 @@
+//! Filtering logs by their metadata before decoding them.
+
+use cdefmt_decoder::{Decoder, Error, filter::Filter};
+use cdefmt_encoder::{
+    Argument, Encoding,
+    elf::{self, ArgumentType, INIT_ID, LogSpec},
+    encode_to_vec,
+};
+use cdefmt_parser::{metadata::Level, path::PathMap, r#type::Type};
+
+const ARGS: &[(&str, ArgumentType)] = &[("a", ArgumentType::Value(Type::U32))];
+
+const LOGS: [LogSpec; 4] = [
+    (
+        Level::Error,
+        "/build/drivers/uart.c",
+        1,
+        "uart overrun {}",
+        ARGS,
+    ),
+    (
+        Level::Warning,
+        "/build/drivers/spi.c",
+        1,
+        "spi timeout {}",
+        ARGS,
+    ),
+    (Level::Info, "/build/app/main.c", 1, "started {}", ARGS),
+    (Level::Debug, "/build/app/main.c", 1, "tick {}", ARGS),
+];
+
+/// Indices into [`LOGS`] of the logs that `decoder` accepts.
+fn accepted(decoder: &mut Decoder, ids: &[u64]) -> Vec<usize> {
+    (0..ids.len())
+        .filter(|i| {
+            decoder
+                .decode_log_filtered(
+                    &encode_to_vec(Encoding::NATIVE, ids[*i], &[Argument::Value(&7u32)]).unwrap(),
+                )
+                .unwrap()
+                .is_some()
+        })
+        .collect()
+}
+
+fn filtered(filter: Filter) -> Vec<usize> {
+    let (elf, ids) = elf::build(Encoding::NATIVE, &LOGS).unwrap();
+    let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+    decoder.set_filter(filter);
+    accepted(&mut decoder, &ids)
+}
+
+#[test]
+fn empty() {
+    assert!(Filter::new().is_empty());
+    assert_eq!(filtered(Filter::new()), [0, 1, 2, 3]);
+}
+
+#[test]
+fn level() {
+    assert_eq!(filtered(Filter::new().with_level(Level::Warning)), [0, 1]);
+    assert_eq!(
+        filtered(Filter::new().with_level(Level::Verbose)),
+        [0, 1, 2, 3]
+    );
+}
+
+#[test]
+fn files() {
+    let filter = Filter::new().with_files(["**/drivers/*.c"]).unwrap();
+    assert_eq!(filtered(filter), [0, 1]);
+
+    // Any of the patterns.
+    let filter = Filter::new()
+        .with_files(["**/uart.c", "/build/app/*"])
+        .unwrap();
+    assert_eq!(filtered(filter), [0, 2, 3]);
+
+    assert!(matches!(
+        Filter::new().with_files(["a/[b"]),
+        Err(Error::Glob(_))
+    ));
+}
+
+#[test]
+fn ids() {
+    let (elf, ids) = elf::build(Encoding::NATIVE, &LOGS).unwrap();
+    let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+
+    let id = |i: usize| ids[i] as usize;
+    decoder.set_filter(Filter::new().with_allowed_ids([id(1), id(2)]));
+    assert_eq!(accepted(&mut decoder, &ids), [1, 2]);
+
+    // Denying takes precedence.
+    decoder.set_filter(
+        Filter::new()
+            .with_allowed_ids([id(1), id(2)])
+            .with_denied_ids([id(2)]),
+    );
+    assert_eq!(accepted(&mut decoder, &ids), [1]);
+
+    decoder.set_filter(Filter::new().with_denied_ids([id(0), id(3)]));
+    assert_eq!(accepted(&mut decoder, &ids), [1, 2]);
+}
+
+#[test]
+fn fmt() {
+    assert_eq!(
+        filtered(Filter::new().with_fmt("^(uart|spi) ").unwrap()),
+        [0, 1]
+    );
+    assert!(matches!(Filter::new().with_fmt("("), Err(Error::Regex(_))));
+}
+
+#[test]
+fn all_criteria() {
+    let filter = Filter::new()
+        .with_level(Level::Info)
+        .with_files(["/build/app/*"])
+        .unwrap();
+    assert_eq!(filtered(filter), [2]);
+}
+
+#[test]
+fn remapped_paths() {
+    let (elf, ids) = elf::build(Encoding::NATIVE, &LOGS).unwrap();
+    let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+    decoder.set_filter(Filter::new().with_files(["/src/drivers/*"]).unwrap());
+    assert_eq!(accepted(&mut decoder, &ids), []);
+
+    // The cached result for each log is dropped along with the old paths.
+    let mut path_map = PathMap::new();
+    path_map.add("/build", "/src");
+    decoder.set_path_map(path_map);
+    assert_eq!(accepted(&mut decoder, &ids), [0, 1]);
+
+    // And when the filter changes.
+    decoder.set_filter(Filter::new().with_files(["/src/app/*"]).unwrap());
+    assert_eq!(accepted(&mut decoder, &ids), [2, 3]);
+}
+
+#[test]
+fn init_log() {
+    let (elf, ids) = elf::build(Encoding::NATIVE, &LOGS).unwrap();
+    let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+    let build_id = decoder.parser().build_id().to_vec();
+    decoder.set_filter(Filter::new().with_allowed_ids([ids[0] as usize]));
+
+    // Filtered out, but still decoded so a wrong build ID is reported.
+    let frame = |build_id: &[u8]| {
+        encode_to_vec(Encoding::NATIVE, INIT_ID, &[Argument::Value(&build_id)]).unwrap()
+    };
+    let init = frame(&build_id);
+    assert!(decoder.decode_log_filtered(&init).unwrap().is_none());
+    let wrong = frame(&[0xff; 20]);
+    assert!(matches!(
+        decoder.decode_log_filtered(&wrong),
+        Err(Error::BuildIdMismatch { .. })
+    ));
+
+    decoder.set_filter(Filter::new().with_allowed_ids([INIT_ID as usize]));
+    let log = decoder.decode_log_filtered(&init).unwrap().unwrap();
+    assert_eq!(log.get_id(), 0);
+}
@@ -0,0 +1,108 @@
+//! Parity between the parser's format string checks and rformat, which formats the logs.
+
+use cdefmt_decoder::Decoder;
+use cdefmt_encoder::{
+    Argument, Encoding,
+    elf::{self, ArgumentType},
+    encode_to_vec,
+};
+use cdefmt_parser::{Parser, metadata::Level, r#type::Type};
+
+/// Format strings for a single `uint32_t` argument named `value`, covering the spec grammar
+/// `[[fill]align][sign][#][0][width][.precision][type]`.
+const SPECS: &[&str] = &[
+    "{}",
+    "{:x}",
+    "{:X}",
+    "{:o}",
+    "{:b}",
+    "{:e}",
+    "{:E}",
+    "{:?}",
+    "{:x?}",
+    "{:X?}",
+    "{:#x}",
+    "{:#010x}",
+    "{:08}",
+    "{:8}",
+    "{:.3}",
+    "{:8.3}",
+    "{:+}",
+    "{:-}",
+    "{:>8}",
+    "{:<8}",
+    "{:^8}",
+    "{:*^9}",
+    "{:<<5}",
+    "{:0>+#8x}",
+    "{:y}",
+    "{:xx}",
+    "{:.}",
+    "{:8.}",
+    "{:#}x",
+];
+
+/// Format strings for the same argument, covering how placeholders refer to arguments and
+/// escaped or unmatched braces. Each one uses the argument, since the parser also warns about
+/// unused arguments, which rformat doesn't check.
+const PLACEHOLDERS: &[&str] = &[
+    "{0}",
+    "{value}",
+    "{0:x}",
+    "{value:#x}",
+    "{} {0} {value}",
+    "{{}} {}",
+    "{} }}{{",
+    "{1}",
+    "{other}",
+    "{} {}",
+    "{",
+    "}",
+    "{} {",
+    "{} }",
+];
+
+/// Whether the parser reports any problem with the format string.
+fn parser_rejects(elf: &[u8]) -> bool {
+    !Parser::new(elf).unwrap().check().is_empty()
+}
+
+/// Whether rformat fails to format the log.
+fn rformat_rejects(elf: &[u8], id: u64) -> bool {
+    let mut decoder = Decoder::new(elf).unwrap();
+    let frame = encode_to_vec(Encoding::NATIVE, id, &[Argument::Value(&7u32)]).unwrap();
+    let log = decoder.decode_log(&frame).unwrap();
+
+    match log.to_string() {
+        Ok(message) => message.contains("Can't format"),
+        Err(_) => true,
+    }
+}
+
+/// Returns the format strings the parser and rformat disagree on.
+fn mismatches<'a>(formats: &[&'a str]) -> Vec<&'a str> {
+    let args = [("value", ArgumentType::Value(Type::U32))];
+
+    formats
+        .iter()
+        .copied()
+        .filter(|fmt| {
+            let (elf, ids) = elf::build(
+                Encoding::NATIVE,
+                &[(Level::Info, "format.c", 1, fmt, &args)],
+            )
+            .unwrap();
+            parser_rejects(&elf) != rformat_rejects(&elf, ids[0])
+        })
+        .collect()
+}
+
+#[test]
+fn spec_grammar() {
+    assert_eq!(mismatches(SPECS), [] as [&str; 0]);
+}
+
+#[test]
+fn placeholders() {
+    assert_eq!(mismatches(PLACEHOLDERS), [] as [&str; 0]);
+}
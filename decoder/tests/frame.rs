@@ -0,0 +1,112 @@
+//! Splitting streams into frames, and recovering from corrupted ones.
+
+use cdefmt_decoder::{
+    Error, Limits,
+    frame::{FrameDecoder, Framing},
+    limits::Limit,
+};
+use gimli::RunTimeEndian;
+
+const MAX_FRAME_SIZE: usize = 1024;
+
+fn frame_decoder(framing: Framing) -> FrameDecoder {
+    FrameDecoder::new(framing, RunTimeEndian::Little)
+        .with_limits(&Limits::new().with_max_frame_size(MAX_FRAME_SIZE))
+}
+
+/// Pushes `data` in small chunks, like a serial port would, returning the frames and how many
+/// errors were reported.
+/// Panics if the decoder ever buffers more than `max_pending` bytes.
+fn feed(frames: &mut FrameDecoder, data: &[u8], max_pending: usize) -> (Vec<Vec<u8>>, usize) {
+    let mut decoded = Vec::new();
+    let mut errors = 0;
+
+    for chunk in data.chunks(4096) {
+        frames.push(chunk);
+        loop {
+            match frames.next_frame() {
+                Ok(Some(frame)) => decoded.push(frame),
+                Ok(None) => break,
+                Err(Error::LimitExceeded {
+                    limit: Limit::FrameSize,
+                    ..
+                }) => errors += 1,
+                Err(e) => panic!("Unexpected error: {e}"),
+            }
+        }
+        assert!(
+            frames.pending() <= max_pending,
+            "{} bytes are buffered",
+            frames.pending()
+        );
+    }
+
+    (decoded, errors)
+}
+
+#[test]
+fn length_prefixed() {
+    let mut frames = frame_decoder(Framing::LengthPrefixed(4));
+    let mut data = Vec::new();
+    for frame in [&b"first"[..], b"", b"third"] {
+        data.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        data.extend_from_slice(frame);
+    }
+
+    let (decoded, errors) = feed(&mut frames, &data, data.len());
+    assert_eq!(decoded, [&b"first"[..], b"", b"third"]);
+    assert_eq!(errors, 0);
+    assert_eq!(frames.pending(), 0);
+}
+
+#[test]
+fn length_prefixed_huge_prefix() {
+    let mut frames = frame_decoder(Framing::LengthPrefixed(4));
+
+    // Every 4 bytes of the garbage are another oversized length, each of them is skipped.
+    let mut data = vec![0xff; 16 * 1024 * 1024];
+    data.extend_from_slice(&5u32.to_le_bytes());
+    data.extend_from_slice(b"valid");
+
+    // A chunk, along with a partial frame.
+    let (decoded, errors) = feed(&mut frames, &data, 4096 + MAX_FRAME_SIZE);
+    assert_eq!(decoded, [b"valid"]);
+    assert_eq!(errors, 16 * 1024 * 1024 / 4);
+}
+
+#[test]
+fn cobs() {
+    let mut frames = frame_decoder(Framing::Cobs);
+    // "\x11\x00\x22" followed by an empty frame.
+    let data = [0x02, 0x11, 0x02, 0x22, 0x00, 0x01, 0x00];
+
+    let (decoded, errors) = feed(&mut frames, &data, data.len());
+    assert_eq!(decoded, [&[0x11, 0x00, 0x22][..], &[]]);
+    assert_eq!(errors, 0);
+}
+
+#[test]
+fn cobs_missing_terminator() {
+    let mut frames = frame_decoder(Framing::Cobs);
+
+    // A stream that never contains a zero is dropped, up to the next terminator.
+    let mut data = vec![0x01; 16 * 1024 * 1024];
+    data.extend_from_slice(&[0x00, 0x03, 0x11, 0x22, 0x00]);
+
+    let (decoded, errors) = feed(&mut frames, &data, 4096 + MAX_FRAME_SIZE + 8);
+    assert_eq!(decoded, [[0x11, 0x22]]);
+    assert_eq!(errors, 1);
+}
+
+#[test]
+fn cobs_malformed() {
+    let mut frames = frame_decoder(Framing::Cobs);
+    // The code points past the end of the frame.
+    frames.push(&[0x05, 0x11, 0x00, 0x02, 0x22, 0x00]);
+
+    assert!(matches!(
+        frames.next_frame(),
+        Err(Error::MalformedCobsFrame)
+    ));
+    assert_eq!(frames.next_frame().unwrap(), Some(vec![0x22]));
+}
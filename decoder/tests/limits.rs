@@ -0,0 +1,136 @@
+//! Frames exceeding the decoder's limits are rejected with a typed error.
+
+use cdefmt_decoder::{Decoder, Error, Limits, limits::Limit};
+use cdefmt_encoder::{
+    Argument, Encoding,
+    elf::{self, ArgumentType},
+    encode_to_vec,
+};
+use cdefmt_parser::{
+    metadata::Level,
+    r#type::{StructureMember, Type},
+};
+
+const ENCODING: Encoding = Encoding::NATIVE;
+
+fn exceeded(result: cdefmt_decoder::Result<impl Sized>) -> Option<Limit> {
+    match result.err()? {
+        Error::LimitExceeded { limit, .. } => Some(limit),
+        _ => None,
+    }
+}
+
+#[test]
+fn frame_size() {
+    let args = [("samples", ArgumentType::DynamicArray(Type::U8))];
+    let (elf, ids) = elf::build(ENCODING, &[(Level::Info, "limits.c", 1, "{}", &args)]).unwrap();
+    let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+    let data = encode_to_vec(ENCODING, ids[0], &[Argument::DynamicArray(&[0u8; 100])]).unwrap();
+
+    assert!(decoder.decode_log(&data).is_ok());
+
+    decoder.set_limits(Limits::new().with_max_frame_size(64));
+    assert_eq!(exceeded(decoder.decode_log(&data)), Some(Limit::FrameSize));
+    assert_eq!(
+        exceeded(decoder.decode_log_filtered(&data)),
+        Some(Limit::FrameSize)
+    );
+}
+
+#[test]
+fn array_elements() {
+    let array = Type::Array {
+        ty: Box::new(Type::U16),
+        lengths: vec![8],
+    };
+    let args = [("array", ArgumentType::Value(array))];
+    let (elf, ids) = elf::build(ENCODING, &[(Level::Info, "limits.c", 1, "{}", &args)]).unwrap();
+    let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+    let data = encode_to_vec(ENCODING, ids[0], &[Argument::Value(&[0u16; 8])]).unwrap();
+
+    decoder.set_limits(Limits::new().with_max_array_elements(8));
+    assert!(decoder.decode_log(&data).is_ok());
+
+    decoder.set_limits(Limits::new().with_max_array_elements(7));
+    assert_eq!(
+        exceeded(decoder.decode_log(&data)),
+        Some(Limit::ArrayElements)
+    );
+}
+
+#[test]
+fn array_elements_multidimensional() {
+    // 4 rows of 4, each dimension is within the limit but the whole array isn't.
+    let array = Type::Array {
+        ty: Box::new(Type::U8),
+        lengths: vec![4, 4],
+    };
+    let args = [("grid", ArgumentType::Value(array))];
+    let (elf, ids) = elf::build(ENCODING, &[(Level::Info, "limits.c", 1, "{}", &args)]).unwrap();
+    let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+    let data = encode_to_vec(ENCODING, ids[0], &[Argument::Value(&[[0u8; 4]; 4])]).unwrap();
+
+    decoder.set_limits(Limits::new().with_max_array_elements(16));
+    assert!(decoder.decode_log(&data).is_ok());
+
+    decoder.set_limits(Limits::new().with_max_array_elements(15));
+    assert_eq!(
+        exceeded(decoder.decode_log(&data)),
+        Some(Limit::ArrayElements)
+    );
+}
+
+#[test]
+fn dynamic_data() {
+    let args = [
+        ("first", ArgumentType::DynamicArray(Type::U32)),
+        ("second", ArgumentType::DynamicArray(Type::U32)),
+    ];
+    let (elf, ids) = elf::build(ENCODING, &[(Level::Info, "limits.c", 1, "{}", &args)]).unwrap();
+    let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+    let data = encode_to_vec(
+        ENCODING,
+        ids[0],
+        &[
+            Argument::DynamicArray(&[1u32; 4]),
+            Argument::DynamicArray(&[2u32; 4]),
+        ],
+    )
+    .unwrap();
+
+    // Both arrays count towards the limit.
+    decoder.set_limits(Limits::new().with_max_dynamic_data(32));
+    assert!(decoder.decode_log(&data).is_ok());
+
+    decoder.set_limits(Limits::new().with_max_dynamic_data(31));
+    assert_eq!(
+        exceeded(decoder.decode_log(&data)),
+        Some(Limit::DynamicData)
+    );
+}
+
+#[test]
+fn depth() {
+    // A structure within a structure, containing a u8 nested 2 levels deep.
+    let mut ty = Type::U8;
+    for name in ["inner", "outer"] {
+        ty = Type::Structure {
+            members: vec![StructureMember {
+                offset: 0,
+                name: name.to_string(),
+                ty,
+            }],
+            size: 1,
+        };
+    }
+    let args = [("nested", ArgumentType::Value(ty))];
+    let (elf, ids) = elf::build(ENCODING, &[(Level::Info, "limits.c", 1, "{}", &args)]).unwrap();
+    let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+    let data = encode_to_vec(ENCODING, ids[0], &[Argument::Value(&7u8)]).unwrap();
+
+    decoder.set_limits(Limits::new().with_max_depth(2));
+    assert!(decoder.decode_log(&data).is_ok());
+
+    decoder.set_limits(Limits::new().with_max_depth(1));
+    assert_eq!(exceeded(decoder.decode_log(&data)), Some(Limit::Depth));
+}
@@ -0,0 +1,88 @@
+//! The layout of the pcapng blocks written for the frames.
+
+use std::time::{Duration, SystemTime};
+
+use cdefmt_decoder::pcapng::{LINKTYPE_USER0, Writer};
+
+/// Appends a block: its type, total length, body and total length again.
+fn block(pcapng: &mut Vec<u8>, ty: u32, body: &[u8]) {
+    assert_eq!(body.len() % 4, 0);
+    let len = (body.len() as u32 + 12).to_le_bytes();
+    pcapng.extend(ty.to_le_bytes());
+    pcapng.extend(len);
+    pcapng.extend(body);
+    pcapng.extend(len);
+}
+
+fn section_header() -> Vec<u8> {
+    let mut body = vec![
+        0x4d, 0x3c, 0x2b, 0x1a, // Byte order magic.
+        1, 0, 0, 0, // Version 1.0.
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, // Unknown section length.
+        4, 0, 6, 0, // shb_userappl.
+    ];
+    body.extend(b"cdefmt\0\0");
+    body.extend([0, 0, 0, 0]); // opt_endofopt.
+
+    let mut pcapng = vec![];
+    block(&mut pcapng, 0x0a0d_0d0a, &body);
+    pcapng
+}
+
+fn interface(pcapng: &mut Vec<u8>, link_type: u16, name: &[u8], padding: usize) {
+    let mut body = link_type.to_le_bytes().to_vec();
+    body.extend([0, 0, 0, 0, 0, 0]); // Reserved, no snapshot length.
+    body.extend([2, 0, name.len() as u8, 0]); // if_name.
+    body.extend(name);
+    body.extend(vec![0; padding]);
+    body.extend([9, 0, 1, 0, 9, 0, 0, 0]); // if_tsresol, nanoseconds.
+    body.extend([0, 0, 0, 0]);
+    block(pcapng, 1, &body);
+}
+
+fn packet(pcapng: &mut Vec<u8>, interface: u32, nanos: u64, data: &[u8], padding: usize) {
+    let mut body = interface.to_le_bytes().to_vec();
+    body.extend(((nanos >> 32) as u32).to_le_bytes());
+    body.extend((nanos as u32).to_le_bytes());
+    body.extend((data.len() as u32).to_le_bytes());
+    body.extend((data.len() as u32).to_le_bytes());
+    body.extend(data);
+    body.extend(vec![0; padding]);
+    block(pcapng, 6, &body);
+}
+
+#[test]
+fn section_header_only() {
+    let writer = Writer::new(Vec::new(), LINKTYPE_USER0).unwrap();
+    let pcapng = writer.into_inner();
+    assert_eq!(pcapng.len(), 44);
+    assert_eq!(pcapng, section_header());
+}
+
+#[test]
+fn blocks() {
+    let nanos = 0x0001_7e3a_1234_5678;
+    let at = |nanos| SystemTime::UNIX_EPOCH + Duration::from_nanos(nanos);
+
+    let mut writer = Writer::new(Vec::new(), LINKTYPE_USER0 + 1).unwrap();
+    writer
+        .write(at(nanos), "serial:/dev/ttyUSB0", &[1, 2, 3, 4, 5])
+        .unwrap();
+    writer
+        .write(at(nanos + 1), "tcp:10.0.0.2:4000", &[])
+        .unwrap();
+    writer
+        .write(at(nanos + 2), "serial:/dev/ttyUSB0", &[6, 7, 8, 9])
+        .unwrap();
+    let pcapng = writer.into_inner();
+
+    // Each source is described right before its first frame.
+    let mut expected = section_header();
+    interface(&mut expected, 148, b"serial:/dev/ttyUSB0", 1);
+    packet(&mut expected, 0, nanos, &[1, 2, 3, 4, 5], 3);
+    interface(&mut expected, 148, b"tcp:10.0.0.2:4000", 3);
+    packet(&mut expected, 1, nanos + 1, &[], 0);
+    packet(&mut expected, 0, nanos + 2, &[6, 7, 8, 9], 0);
+
+    assert_eq!(pcapng, expected);
+}
@@ -0,0 +1,547 @@
+//! Decodes frames produced by `cdefmt-encoder`, using elfs described by its `ElfBuilder`, so the
+//! decoder is tested without a C toolchain.
+
+use std::collections::BTreeMap;
+
+use cdefmt_decoder::{Decoder, Error, MalformedType, var::Var};
+use cdefmt_encoder::{
+    Argument, Encode, Encoding, Pointer, Writer,
+    elf::{ArgumentType, ElfBuilder, INIT_ID},
+    encode, encode_to_vec,
+};
+use cdefmt_parser::{
+    metadata::Level,
+    r#type::{StructureMember, Type},
+};
+
+const ENCODINGS: [Encoding; 2] = [
+    Encoding {
+        little_endian: true,
+        address_size: 8,
+    },
+    Encoding {
+        little_endian: false,
+        address_size: 4,
+    },
+];
+
+/// `struct { uint64_t a; uint32_t b; uint16_t c; }`, which is padded to 16 bytes.
+struct Sample {
+    a: u64,
+    b: u32,
+    c: u16,
+}
+
+impl Encode for Sample {
+    fn encode(&self, writer: &mut Writer) -> cdefmt_encoder::Result<()> {
+        self.a.encode(writer)?;
+        self.b.encode(writer)?;
+        self.c.encode(writer)?;
+        writer.padding(2)
+    }
+}
+
+fn sample_type() -> Type {
+    let member = |offset, name: &str, ty| StructureMember {
+        offset,
+        name: name.to_string(),
+        ty,
+    };
+
+    Type::Structure {
+        members: vec![
+            member(0, "a", Type::U64),
+            member(8, "b", Type::U32),
+            member(12, "c", Type::U16),
+        ],
+        size: 16,
+    }
+}
+
+fn enum_type() -> Type {
+    Type::Enumeration {
+        ty: Box::new(Type::I32),
+        valid_values: BTreeMap::from([(-2, "MINUS_TWO".to_string()), (3, "THREE".to_string())]),
+    }
+}
+
+/// A pointer as the parser describes it, by the unsigned integer as wide as the target's pointers.
+fn pointer_type(encoding: Encoding) -> Type {
+    Type::Pointer(Box::new(match encoding.address_size {
+        4 => Type::U32,
+        _ => Type::U64,
+    }))
+}
+
+fn value(ty: Type) -> ArgumentType {
+    ArgumentType::Value(ty)
+}
+
+/// For each of the [`ENCODINGS`], builds an elf with the logs `add_logs` adds, and runs `test` with
+/// a decoder for it along with whatever `add_logs` returned, usually the logs' ids.
+fn for_each_encoding<T>(
+    add_logs: impl Fn(&mut ElfBuilder, Encoding) -> T,
+    mut test: impl FnMut(Encoding, &mut Decoder, T),
+) {
+    for encoding in ENCODINGS {
+        let mut builder = ElfBuilder::new(encoding);
+        let logs = add_logs(&mut builder, encoding);
+        let elf = builder.build().unwrap();
+        let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+        test(encoding, &mut decoder, logs);
+    }
+}
+
+#[test]
+fn init_log() {
+    for_each_encoding(
+        |builder, _| builder.build_id(),
+        |encoding, decoder, build_id| {
+            let log = decoder
+                .decode_log(
+                    &encode_to_vec(encoding, INIT_ID, &[Argument::Value(&build_id)]).unwrap(),
+                )
+                .unwrap();
+            assert_eq!(log.get_id(), 0);
+            assert_eq!(log.get_args()[0].as_bytes().unwrap(), build_id);
+
+            let wrong = [0xff; 20];
+            let result = decoder
+                .decode_log(&encode_to_vec(encoding, INIT_ID, &[Argument::Value(&wrong)]).unwrap());
+            match result {
+                Err(Error::BuildIdMismatch { expected, found }) => {
+                    assert_eq!(expected, build_id);
+                    assert_eq!(found, wrong);
+                }
+                _ => panic!("Expected a build ID mismatch"),
+            }
+        },
+    );
+}
+
+#[test]
+fn errors() {
+    for_each_encoding(
+        |builder, _| {
+            builder.add_log(
+                Level::Info,
+                "errors.c",
+                40,
+                "{}",
+                &[("a_u32", value(Type::U32))],
+            )
+        },
+        |encoding, decoder, id| {
+            let data = encode_to_vec(encoding, id, &[Argument::Value(&7u32)]).unwrap();
+            assert!(matches!(
+                decoder.decode_log(&data[..data.len() - 1]),
+                Err(Error::TruncatedFrame { id: Some(i) }) if i == id as usize
+            ));
+            assert!(matches!(
+                decoder.decode_log(&data[..1]),
+                Err(Error::TruncatedFrame { id: None })
+            ));
+
+            let unknown = encode_to_vec(encoding, id + 1, &[Argument::Value(&7u32)]).unwrap();
+            let nearest = cdefmt_decoder::NearestLog {
+                id: id as usize,
+                file: "errors.c".to_string(),
+                line: 40,
+            };
+            match decoder.decode_log(&unknown) {
+                Err(e @ Error::UnknownLogId { .. }) => {
+                    let raw = unknown
+                        .iter()
+                        .map(|b| format!("{b:02x}"))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    assert_eq!(
+                        e.to_string(),
+                        format!(
+                            "<unknown log {:#x}> [raw: {raw}], nearest log {id:#x} at errors.c:40 (off by +1)",
+                            id + 1
+                        )
+                    );
+                    assert!(matches!(
+                        e,
+                        Error::UnknownLogId { id: i, frame, nearest: Some(n) }
+                            if i == id as usize + 1 && frame == unknown && n == nearest
+                    ));
+                }
+                result => panic!("Expected an unknown log id, got {result:?}"),
+            }
+        },
+    );
+}
+
+#[test]
+fn malformed_types() {
+    let parse = |ty: Type, data: &[u8]| {
+        let mut data = gimli::EndianSlice::new(data, gimli::RunTimeEndian::Little);
+        Var::parse(&ty, &mut data).map(|(var, _)| var)
+    };
+
+    let overflowing = Type::Structure {
+        members: vec![StructureMember {
+            offset: 0,
+            name: "a".to_string(),
+            ty: Type::U32,
+        }],
+        size: 2,
+    };
+    assert!(matches!(
+        parse(overflowing, &[0; 4]),
+        Err(Error::MalformedType(MalformedType::StructureOverflow))
+    ));
+
+    let dimensionless = Type::Array {
+        ty: Box::new(Type::U8),
+        lengths: vec![],
+    };
+    assert!(matches!(
+        parse(dimensionless, &[0; 4]),
+        Err(Error::MalformedType(MalformedType::ArrayWithoutDimensions))
+    ));
+}
+
+#[test]
+fn scalars() {
+    for_each_encoding(
+        |builder, encoding| {
+            builder.add_log(
+                Level::Info,
+                "scalars.c",
+                10,
+                "{} {} {} {} {} {} {} {}",
+                &[
+                    ("a_u8", value(Type::U8)),
+                    ("a_i16", value(Type::I16)),
+                    ("a_u32", value(Type::U32)),
+                    ("an_i64", value(Type::I64)),
+                    ("a_f32", value(Type::F32)),
+                    ("a_f64", value(Type::F64)),
+                    ("a_bool", value(Type::Bool)),
+                    ("a_pointer", value(pointer_type(encoding))),
+                ],
+            )
+        },
+        |encoding, decoder, id| {
+            let data = encode_to_vec(
+                encoding,
+                id,
+                &[
+                    Argument::Value(&0xabu8),
+                    Argument::Value(&-1234i16),
+                    Argument::Value(&0xdead_beefu32),
+                    Argument::Value(&-5_000_000_000i64),
+                    Argument::Value(&1.5f32),
+                    Argument::Value(&-0.25f64),
+                    Argument::Value(&true),
+                    Argument::Value(&Pointer(0x1000)),
+                ],
+            )
+            .unwrap();
+            let log = decoder.decode_log(&data).unwrap();
+
+            assert_eq!(log.get_level(), Level::Info);
+            assert_eq!(log.get_file(), "scalars.c");
+            assert_eq!(log.get_line(), 10);
+
+            let args = log.get_args();
+            assert!(matches!(args[0], Var::U8(0xab)));
+            assert!(matches!(args[1], Var::I16(-1234)));
+            assert!(matches!(args[2], Var::U32(0xdead_beef)));
+            assert!(matches!(args[3], Var::I64(-5_000_000_000)));
+            assert_eq!(args[4].as_f64(), Some(1.5));
+            assert_eq!(args[5].as_f64(), Some(-0.25));
+            assert_eq!(args[6].as_bool(), Some(true));
+            assert_eq!(args[7].try_as_u64(), Some(0x1000));
+            assert!(matches!(args[7], Var::Pointer(_)));
+        },
+    );
+}
+
+#[test]
+fn compound_types() {
+    for_each_encoding(
+        |builder, _| {
+            builder.add_log(
+                Level::Warning,
+                "compound.c",
+                20,
+                "{} {} {}",
+                &[
+                    ("sample", value(sample_type())),
+                    ("an_enum", value(enum_type())),
+                    (
+                        "an_array",
+                        value(Type::Array {
+                            ty: Box::new(Type::U16),
+                            lengths: vec![3],
+                        }),
+                    ),
+                ],
+            )
+        },
+        |encoding, decoder, id| {
+            let sample = Sample {
+                a: u64::MAX,
+                b: 7,
+                c: 0x1234,
+            };
+            let data = encode_to_vec(
+                encoding,
+                id,
+                &[
+                    Argument::Value(&sample),
+                    Argument::Value(&-2i32),
+                    Argument::Value(&[1u16, 2, 3]),
+                ],
+            )
+            .unwrap();
+            let log = decoder.decode_log(&data).unwrap();
+            let args = log.get_args();
+
+            let member = |name| args[0].member(name).and_then(Var::try_as_u64);
+            assert_eq!(member("a"), Some(u64::MAX));
+            assert_eq!(member("b"), Some(7));
+            assert_eq!(member("c"), Some(0x1234));
+
+            assert_eq!(args[1].try_as_i128(), Some(-2));
+            assert!(matches!(args[1], Var::Enumeration { .. }));
+
+            let elements = (0..3)
+                .map(|i| args[2].index(i).and_then(Var::try_as_u64))
+                .collect::<Vec<_>>();
+            assert_eq!(elements, [Some(1), Some(2), Some(3)]);
+        },
+    );
+}
+
+#[test]
+fn dynamic_arrays() {
+    for_each_encoding(
+        |builder, _| {
+            builder.add_log(
+                Level::Debug,
+                "dynamic.c",
+                30,
+                "{} {} {}",
+                &[
+                    ("name", ArgumentType::DynamicArray(Type::I8)),
+                    ("after", value(Type::U32)),
+                    ("samples", ArgumentType::DynamicArray(Type::U16)),
+                ],
+            )
+        },
+        |encoding, decoder, id| {
+            let samples: &[u16] = &[10, 20, 30, 40];
+            let data = encode_to_vec(
+                encoding,
+                id,
+                &[
+                    Argument::DynamicArray(&"sensor"),
+                    Argument::Value(&99u32),
+                    Argument::DynamicArray(&samples),
+                ],
+            )
+            .unwrap();
+
+            // The id, the two sizes and the u32, followed by the arrays' elements.
+            let address_size = encoding.address_size as usize;
+            assert_eq!(data.len(), 3 * address_size + 4 + 6 + 8);
+
+            let log = decoder.decode_log(&data).unwrap();
+            let args = log.get_args();
+            assert_eq!(args[0].as_str().as_deref(), Some("sensor"));
+            assert_eq!(args[1].try_as_u64(), Some(99));
+            let samples = (0..4)
+                .map(|i| args[2].index(i).and_then(Var::try_as_u64))
+                .collect::<Vec<_>>();
+            assert_eq!(samples, [Some(10), Some(20), Some(30), Some(40)]);
+        },
+    );
+}
+
+#[test]
+fn named_args() {
+    for_each_encoding(
+        |builder, _| {
+            builder.add_log(
+                Level::Info,
+                "named.c",
+                60,
+                "{} reads {}, {}",
+                &[
+                    ("dev->name", ArgumentType::DynamicArray(Type::I8)),
+                    ("sample.b", value(Type::U32)),
+                    ("sample.b", value(Type::U32)),
+                ],
+            )
+        },
+        |encoding, decoder, id| {
+            let data = encode_to_vec(
+                encoding,
+                id,
+                &[
+                    Argument::DynamicArray(&"adc"),
+                    Argument::Value(&7u32),
+                    Argument::Value(&8u32),
+                ],
+            )
+            .unwrap();
+            let log = decoder.decode_log(&data).unwrap();
+
+            assert_eq!(log.get_id(), id as usize);
+            assert_eq!(log.get_fmt(), "{} reads {}, {}");
+
+            let names = log
+                .get_named_args()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>();
+            assert_eq!(names, ["dev->name", "sample.b", "sample.b"]);
+            assert_eq!(
+                log.get_arg("dev->name").and_then(Var::as_str).as_deref(),
+                Some("adc")
+            );
+            // The first of the arguments sharing a name.
+            assert_eq!(log.get_arg("sample.b").and_then(Var::try_as_u64), Some(7));
+            assert!(log.get_arg("sample").is_none());
+        },
+    );
+}
+
+#[test]
+fn formatted_message() {
+    for_each_encoding(
+        |builder, _| {
+            builder.add_log(
+                Level::Info,
+                "message.c",
+                70,
+                "{{{}}} of {count}: {sample}, {3} {flag}",
+                &[
+                    ("done", value(Type::U8)),
+                    ("count", value(Type::U16)),
+                    ("sample", value(sample_type())),
+                    ("state", value(enum_type())),
+                    ("flag", value(Type::Bool)),
+                ],
+            )
+        },
+        |encoding, decoder, id| {
+            let sample = Sample { a: 1, b: 2, c: 3 };
+            let data = encode_to_vec(
+                encoding,
+                id,
+                &[
+                    Argument::Value(&3u8),
+                    Argument::Value(&5u16),
+                    Argument::Value(&sample),
+                    Argument::Value(&3i32),
+                    Argument::Value(&true),
+                ],
+            )
+            .unwrap();
+            let log = decoder.decode_log(&data).unwrap();
+            // Placeholders may be implicit, indexed or named by their argument.
+            assert_eq!(
+                log.to_string().unwrap(),
+                "{3} of 5: { a: 1, b: 2, c: 3 }, THREE(3) true"
+            );
+        },
+    );
+}
+
+#[test]
+fn lenient() {
+    for_each_encoding(
+        |builder, _| {
+            let fixed = builder.add_log(
+                Level::Warning,
+                "lenient.c",
+                40,
+                "{} {} {}",
+                &[
+                    ("count", value(Type::U32)),
+                    ("sample", value(sample_type())),
+                    ("flag", value(Type::Bool)),
+                ],
+            );
+            let dynamic = builder.add_log(
+                Level::Warning,
+                "lenient.c",
+                41,
+                "{} {}",
+                &[
+                    ("after", value(Type::U32)),
+                    ("samples", ArgumentType::DynamicArray(Type::U16)),
+                ],
+            );
+            (fixed, dynamic)
+        },
+        |encoding, decoder, (fixed, dynamic)| {
+            let sample = Sample { a: 1, b: 2, c: 3 };
+            let data = encode_to_vec(
+                encoding,
+                fixed,
+                &[
+                    Argument::Value(&7u32),
+                    Argument::Value(&sample),
+                    Argument::Value(&true),
+                ],
+            )
+            .unwrap();
+            // Cut in the middle of the structure.
+            let address_size = encoding.address_size as usize;
+            let truncated = &data[..address_size + 4 + 5];
+
+            assert!(matches!(
+                decoder.decode_log(truncated),
+                Err(Error::TruncatedFrame { id: Some(id) }) if id as u64 == fixed
+            ));
+
+            decoder.set_lenient(true);
+            let log = decoder.decode_log(truncated).unwrap();
+            assert!(log.is_truncated());
+            assert_eq!(log.to_string().unwrap(), "7 <truncated> <truncated>");
+            let args = log.get_args();
+            assert_eq!(args[0].try_as_u64(), Some(7));
+            assert!(matches!(args[1], Var::Truncated));
+            assert!(matches!(args[2], Var::Truncated));
+            assert_eq!(log.get_trailing(), &data[address_size + 4..][..5]);
+
+            // Bytes the elf doesn't describe are kept, the arguments are all there.
+            let mut extra = data.clone();
+            extra.extend_from_slice(&[0xde, 0xad]);
+            let log = decoder.decode_log(&extra).unwrap();
+            assert!(!log.is_truncated());
+            assert_eq!(log.get_trailing(), [0xde, 0xad]);
+
+            // A dynamic array whose elements are cut, its size is still part of the fixed members.
+            let samples: &[u16] = &[10, 20, 30];
+            let data = encode_to_vec(
+                encoding,
+                dynamic,
+                &[Argument::Value(&99u32), Argument::DynamicArray(&samples)],
+            )
+            .unwrap();
+            let log = decoder.decode_log(&data[..data.len() - 1]).unwrap();
+            assert_eq!(log.to_string().unwrap(), "99 <truncated>");
+            let args = log.get_args();
+            assert_eq!(args[0].try_as_u64(), Some(99));
+            assert!(matches!(args[1], Var::Truncated));
+            assert_eq!(log.get_trailing().len(), 5);
+        },
+    );
+}
+
+#[test]
+fn buffer_too_small() {
+    let mut buf = [0; 10];
+    let args = [Argument::Value(&1u32), Argument::Value(&2u32)];
+    assert_eq!(
+        encode(&mut buf, ENCODINGS[0], 0x40, &args),
+        Err(cdefmt_encoder::Error::BufferTooSmall)
+    );
+}
@@ -0,0 +1,141 @@
+//! Serializing decoded logs, as written by the `json` and `ndjson` output formats.
+
+#![cfg(feature = "serde")]
+
+use std::{
+    collections::BTreeMap,
+    time::{Duration, SystemTime},
+};
+
+use cdefmt_decoder::Decoder;
+use cdefmt_encoder::{
+    Argument, Encode, Encoding, Pointer, Writer,
+    elf::{self, ArgumentType},
+    encode_to_vec,
+};
+use cdefmt_parser::{
+    metadata::Level,
+    r#type::{StructureMember, Type},
+};
+use serde_json::json;
+
+struct Point {
+    x: i16,
+    y: i16,
+}
+
+impl Encode for Point {
+    fn encode(&self, writer: &mut Writer) -> cdefmt_encoder::Result<()> {
+        self.x.encode(writer)?;
+        self.y.encode(writer)
+    }
+}
+
+#[test]
+fn log() {
+    let member = |offset, name: &str| StructureMember {
+        offset,
+        name: name.to_string(),
+        ty: Type::I16,
+    };
+    let point = Type::Structure {
+        members: vec![member(0, "x"), member(2, "y")],
+        size: 4,
+    };
+    let state = Type::Enumeration {
+        ty: Box::new(Type::U8),
+        valid_values: BTreeMap::from([(1, "BUSY".to_string())]),
+    };
+    let value = ArgumentType::Value;
+
+    let (elf, ids) = elf::build(
+        Encoding::NATIVE,
+        &[(
+            Level::Warning,
+            "src/main.c",
+            7,
+            "{} at {} is {}, regs {} ok {} {}",
+            &[
+                ("dev->name", ArgumentType::DynamicArray(Type::I8)),
+                ("dev->pos", value(point)),
+                ("state", value(state.clone())),
+                ("regs", value(Type::Pointer(Box::new(Type::U64)))),
+                ("ok", value(Type::Bool)),
+                ("state2", value(state)),
+            ],
+        )],
+    )
+    .unwrap();
+    let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+
+    let frame = encode_to_vec(
+        Encoding::NATIVE,
+        ids[0],
+        &[
+            Argument::DynamicArray(&"uart"),
+            Argument::Value(&Point { x: 1, y: -2 }),
+            Argument::Value(&1u8),
+            Argument::Value(&Pointer(0x2000)),
+            Argument::Value(&false),
+            Argument::Value(&9u8),
+        ],
+    )
+    .unwrap();
+    let mut log = decoder.decode_log(&frame).unwrap();
+    log.set_timestamp(SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_250));
+
+    let char = |c: u8| json!({ "type": "i8", "value": c });
+    let expected = json!({
+        "timestamp": 1_700_000_000.25,
+        "id": ids[0],
+        "level": "warning",
+        "file": "src/main.c",
+        "line": 7,
+        "fmt": "{} at {} is {}, regs {} ok {} {}",
+        "args": [
+            {
+                "name": "dev->name",
+                "value": {
+                    "type": "array",
+                    "elements": [char(b'u'), char(b'a'), char(b'r'), char(b't')],
+                },
+            },
+            {
+                "name": "dev->pos",
+                "value": {
+                    "type": "structure",
+                    "members": [
+                        { "name": "x", "value": { "type": "i16", "value": 1 } },
+                        { "name": "y", "value": { "type": "i16", "value": -2 } },
+                    ],
+                },
+            },
+            {
+                "name": "state",
+                "value": {
+                    "type": "enumeration",
+                    "value": { "type": "u8", "value": 1 },
+                    "name": "BUSY",
+                },
+            },
+            {
+                "name": "regs",
+                "value": { "type": "pointer", "value": { "type": "u64", "value": 0x2000 } },
+            },
+            { "name": "ok", "value": { "type": "bool", "value": false } },
+            {
+                "name": "state2",
+                "value": {
+                    "type": "enumeration",
+                    "value": { "type": "u8", "value": 9 },
+                    "name": null,
+                },
+            },
+        ],
+        "message": log.to_string().unwrap(),
+        "error": null,
+        "trailing": null,
+    });
+
+    assert_eq!(serde_json::to_value(&log).unwrap(), expected);
+}
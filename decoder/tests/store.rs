@@ -0,0 +1,135 @@
+//! Finding elfs by their build ID, and switching between them as init logs arrive.
+
+// The elfs are searched through symbolic links.
+#![cfg(unix)]
+
+use std::{fs, os::unix::fs::symlink, path::PathBuf};
+
+use cdefmt_decoder::{Error, store::Store, store::StoreDecoder};
+use cdefmt_encoder::{
+    Argument, Encoding,
+    elf::{self, ArgumentType, INIT_ID},
+    encode_to_vec,
+};
+use cdefmt_parser::{Parser, metadata::Level, r#type::Type};
+
+/// An elf with a single log in `file`, returning it along with its build ID and the log's id.
+fn single_log(file: &str) -> (Vec<u8>, [u8; 20], u64) {
+    let args = [("a", ArgumentType::Value(Type::U32))];
+    let (elf, ids) = elf::build(Encoding::NATIVE, &[(Level::Info, file, 1, "{}", &args)]).unwrap();
+    let build_id = Parser::new(elf.as_slice())
+        .unwrap()
+        .build_id()
+        .try_into()
+        .unwrap();
+    (elf, build_id, ids[0])
+}
+
+/// A new empty directory, removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("cdefmt-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn add_dir() {
+    let dir = TempDir::new("store");
+    let (first, first_id, _) = single_log("first.c");
+    let (second, second_id, _) = single_log("second.c");
+
+    fs::create_dir_all(dir.0.join("v2/debug")).unwrap();
+    fs::write(dir.0.join("first.elf"), first).unwrap();
+    fs::write(dir.0.join("v2/debug/second.elf"), second).unwrap();
+    fs::write(dir.0.join("v2/notes.txt"), "not an elf").unwrap();
+    // A loop, and another path to a directory that is searched anyway.
+    symlink(&dir.0, dir.0.join("v2/debug/loop")).unwrap();
+    symlink(dir.0.join("v2"), dir.0.join("v2-link")).unwrap();
+
+    let mut store = Store::new();
+    assert_eq!(store.add_dir(&dir.0).unwrap(), 2);
+    assert_eq!(store.len(), 2);
+
+    assert!(store.get(&first_id).is_some());
+    let path = store.path(&second_id).unwrap();
+    assert!(path.ends_with("second.elf"), "{}", path.display());
+    assert!(store.get(&[0xff; 20]).is_none());
+
+    let mut build_ids = store.build_ids().collect::<Vec<_>>();
+    build_ids.sort();
+    let mut expected = [&first_id[..], &second_id[..]];
+    expected.sort();
+    assert_eq!(build_ids, expected);
+}
+
+#[test]
+fn add_file() {
+    let dir = TempDir::new("store-file");
+    fs::write(dir.0.join("notes.txt"), "not an elf").unwrap();
+
+    let mut store = Store::new();
+    assert!(store.add_file(dir.0.join("notes.txt")).is_err());
+    assert!(matches!(
+        store.add_file(dir.0.join("missing.elf")),
+        Err(Error::File { .. })
+    ));
+    assert!(store.is_empty());
+}
+
+#[test]
+fn switches_elfs() {
+    let dir = TempDir::new("store-decoder");
+    let (first, first_id, first_log) = single_log("first.c");
+    let (second, second_id, second_log) = single_log("second.c");
+    fs::write(dir.0.join("first.elf"), first).unwrap();
+    fs::write(dir.0.join("second.elf"), second).unwrap();
+
+    let store = Store::open(&dir.0).unwrap();
+    let mut decoder = StoreDecoder::new(&store);
+    let init = |build_id: &[u8; 20]| {
+        encode_to_vec(Encoding::NATIVE, INIT_ID, &[Argument::Value(build_id)]).unwrap()
+    };
+    // Both elfs have a single log, so it has the same id in both.
+    assert_eq!(first_log, second_log);
+    let log = encode_to_vec(Encoding::NATIVE, first_log, &[Argument::Value(&7u32)]).unwrap();
+
+    assert!(matches!(
+        decoder.decode_log(&log),
+        Err(Error::NoElfSelected)
+    ));
+
+    decoder.decode_log(&init(&first_id)).unwrap();
+    assert_eq!(decoder.decode_log(&log).unwrap().get_file(), "first.c");
+
+    // The device was updated.
+    decoder.decode_log(&init(&second_id)).unwrap();
+    assert_eq!(decoder.decode_log(&log).unwrap().get_file(), "second.c");
+
+    // And rolled back.
+    decoder.decode_log(&init(&first_id)).unwrap();
+    assert_eq!(decoder.decode_log(&log).unwrap().get_file(), "first.c");
+
+    // An elf that isn't in the store, the current one reports the mismatch.
+    assert!(matches!(
+        decoder.decode_log(&init(&[0xff; 20])),
+        Err(Error::BuildIdMismatch { .. })
+    ));
+
+    assert!(matches!(
+        decoder.select(&[0xff; 20]),
+        Err(Error::UnknownBuildId(_))
+    ));
+    decoder.select(&second_id).unwrap();
+    assert_eq!(decoder.decode_log(&log).unwrap().get_file(), "second.c");
+}
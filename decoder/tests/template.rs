@@ -0,0 +1,180 @@
+//! Rendering logs as text using templates.
+
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use cdefmt_decoder::{
+    Decoder, Error,
+    log::Log,
+    template::{DEFAULT_TEMPLATE, Link, Paths, Template, TemplateError, TemplateErrorKind},
+};
+use cdefmt_encoder::{
+    Argument, Encoding,
+    elf::{self, ArgumentType},
+    encode_to_vec,
+};
+use cdefmt_parser::{metadata::Level, r#type::Type};
+
+const FILE: &str = "/home/me/project/src/my main.c";
+
+/// Renders a single `Warning` log at [`FILE`]:42 whose message is `count: 7` using each template.
+fn render(templates: &[Template]) -> Vec<String> {
+    let args = [("count", ArgumentType::Value(Type::U32))];
+    let (elf, ids) = elf::build(
+        Encoding::NATIVE,
+        &[(Level::Warning, FILE, 42, "count: {}", &args)],
+    )
+    .unwrap();
+    let mut decoder = Decoder::new(elf.as_slice()).unwrap();
+
+    let frame = encode_to_vec(Encoding::NATIVE, ids[0], &[Argument::Value(&7u32)]).unwrap();
+    let mut log: Log = decoder.decode_log(&frame).unwrap();
+    // 2024-02-29T13:05:09.123Z
+    log.set_timestamp(SystemTime::UNIX_EPOCH + Duration::from_millis(1_709_211_909_123));
+
+    templates.iter().map(|t| t.render(&log)).collect()
+}
+
+fn template(template: &str) -> Template {
+    Template::new(template).unwrap()
+}
+
+#[test]
+fn fields() {
+    let id = render(&[template("{id}")]).remove(0);
+    assert!(id.starts_with("0x"), "{id}");
+
+    assert_eq!(
+        render(&[
+            template(DEFAULT_TEMPLATE),
+            template("{timestamp} {time}"),
+            template("{file}:{line} {fmt}"),
+            template("{{{level}}} }}{{"),
+        ]),
+        [
+            "Warning > count: 7",
+            "2024-02-29T13:05:09.123Z 13:05:09.123",
+            &format!("{FILE}:42 count: {{}}"),
+            "{Warning} }{",
+        ]
+    );
+}
+
+#[test]
+fn padding() {
+    assert_eq!(
+        render(&[
+            template("[{level:>9}]"),
+            template("[{level:-^11}]"),
+            template("[{line:04}]"),
+            template("[{line:0>4}]"),
+            template("[{level:3}]"),
+        ]),
+        [
+            "[  Warning]",
+            "[--Warning--]",
+            "[42  ]",
+            "[0042]",
+            "[Warning]"
+        ]
+    );
+}
+
+#[test]
+fn invalid() {
+    use TemplateErrorKind::*;
+
+    for (template, kind, position) in [
+        ("{level", UnmatchedOpenBrace, 0),
+        ("level}", UnmatchedCloseBrace, 5),
+        ("{{level}} {lvl}", UnknownField("lvl".to_string()), 10),
+        ("{message} {level:>x}", InvalidSpec(">x".to_string()), 10),
+        ("é{}", UnknownField(String::new()), 2),
+    ] {
+        match Template::new(template) {
+            Err(Error::Template(e)) => {
+                assert_eq!(e, TemplateError { kind, position }, "{template}")
+            }
+            result => panic!("{template}: {result:?}"),
+        }
+    }
+
+    assert!(matches!(
+        Link::new("https://{host}/{path}"),
+        Err(Error::Template(TemplateError { kind: UnknownField(field), position: 8 }))
+            if field == "host"
+    ));
+}
+
+#[test]
+fn colors() {
+    let [plain, colored] = render(&[
+        template("{time} {level} {message}"),
+        template("{time} {level} {message}").with_color(true),
+    ])
+    .try_into()
+    .unwrap();
+
+    assert_eq!(plain, "13:05:09.123 Warning count: 7");
+    assert_eq!(
+        colored,
+        "\x1b[2m13:05:09.123\x1b[0m \x1b[33mWarning\x1b[0m count: 7"
+    );
+
+    // Padding doesn't count the escape codes.
+    let [padded] = render(&[template("{level:>8}|").with_color(true)])
+        .try_into()
+        .unwrap();
+    assert_eq!(padded, "\x1b[33m Warning\x1b[0m|");
+}
+
+#[test]
+fn relative_paths() {
+    let relative = |dir: &str| template("{file}").with_paths(Paths::RelativeTo(PathBuf::from(dir)));
+
+    assert_eq!(
+        render(&[
+            relative("/home/me/project"),
+            relative("/home/me/project/"),
+            // Only whole components.
+            relative("/home/me/proj"),
+            relative("/elsewhere"),
+        ]),
+        ["src/my main.c", "src/my main.c", FILE, FILE]
+    );
+}
+
+#[test]
+fn links() {
+    let link = |url: &str| Link::new(url).unwrap();
+    let linked = |link: Link| {
+        template("{level} {file}:{line}")
+            .with_paths(Paths::RelativeTo(PathBuf::from("/home/me/project")))
+            .with_link(Some(link))
+    };
+
+    let [local, forge, no_commit, build_id] = render(&[
+        linked(Link::default()),
+        linked(link("https://git.example.com/blob/{commit}/{path}#L{line}").with_commit("abc123")),
+        linked(link("https://git.example.com/blob/{commit}/{path}")),
+        linked(link("debuginfod:{build_id}").with_build_id(&[0x01, 0xab])),
+    ])
+    .try_into()
+    .unwrap();
+
+    // Both the file and the line are wrapped in their own OSC 8 sequence.
+    let osc8 = |url: &str, text: &str| format!("\x1b]8;;{url}\x1b\\{text}\x1b]8;;\x1b\\");
+    let expected =
+        |url: &str| format!("Warning {}:{}", osc8(url, "src/my main.c"), osc8(url, "42"));
+
+    assert_eq!(local, expected("file:///home/me/project/src/my%20main.c"));
+    assert_eq!(
+        forge,
+        expected("https://git.example.com/blob/abc123/src/my%20main.c#L42")
+    );
+    assert_eq!(build_id, expected("debuginfod:01ab"));
+    // Links needing an unknown commit are left out.
+    assert_eq!(no_commit, "Warning src/my main.c:42");
+}
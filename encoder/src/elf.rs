@@ -0,0 +1,474 @@
+//! Building elfs that describe logs, as the C macros and the linker would.
+//!
+//! The elf contains what the decoder reads: the `.cdefmt` section with every log's metadata, a
+//! `cdefmt_log_metadata<counter>` symbol per log, the GNU build ID note and the debugging
+//! information of each log's arguments structure, `cdefmt_log_args_t<counter>`, in a compilation
+//! unit named after the log's file, see [`ElfBuilder::set_compilation_unit`].
+//! Together with [`encode`](crate::encode) this allows testing the decoder without a C toolchain.
+
+use std::collections::BTreeMap;
+
+use anyhow::bail;
+use cdefmt_parser::{
+    metadata::{Level, SCHEMA_VERSION},
+    r#type::{StructureMember, Type},
+};
+use gimli::write::{self as dwarf, AttributeValue, UnitEntryId};
+use object::{
+    Architecture, BinaryFormat, Endianness, SectionKind, SymbolFlags, SymbolKind, SymbolScope,
+    write::{Object, Symbol, SymbolSection},
+};
+
+use crate::Encoding;
+
+/// The size of the GNU build ID sent by the init log, `CDEFMT_GNU_BUILD_ID_SIZE`.
+pub const BUILD_ID_SIZE: usize = 20;
+
+/// The id of the init log, which is always placed at the start of the `.cdefmt` section.
+pub const INIT_ID: u64 = 0;
+
+/// The file the init log is attributed to.
+const INIT_FILE: &str = "cdefmt_init.c";
+
+const NT_GNU_BUILD_ID: u32 = 3;
+
+/// The type of a log argument.
+#[derive(Clone, Debug)]
+pub enum ArgumentType {
+    /// An argument passed by value.
+    Value(Type),
+    /// An argument passed using `CDEFMT_DYNAMIC_ARRAY`, whose elements have the given type.
+    DynamicArray(Type),
+}
+
+/// A log's level, file, line, format string and arguments, as passed to [`ElfBuilder::add_log`].
+pub type LogSpec<'a> = (Level, &'a str, u32, &'a str, &'a [(&'a str, ArgumentType)]);
+
+struct Log {
+    counter: u32,
+    file: String,
+    args: Vec<ArgumentType>,
+}
+
+/// Builds an elf containing logs.
+pub struct ElfBuilder {
+    encoding: Encoding,
+    build_id: Option<[u8; BUILD_ID_SIZE]>,
+    logs: Vec<Log>,
+    /// Names of compilation units that differ from their logs' file.
+    unit_names: BTreeMap<String, String>,
+    /// The contents of the `.cdefmt` section.
+    section: Vec<u8>,
+    /// The offset of each log's metadata in the `.cdefmt` section, which is its id.
+    ids: Vec<u64>,
+}
+
+impl ElfBuilder {
+    /// Creates an elf for a target with the given encoding, containing only the init log.
+    pub fn new(encoding: Encoding) -> Self {
+        let mut builder = Self {
+            encoding,
+            build_id: None,
+            logs: vec![],
+            unit_names: BTreeMap::new(),
+            section: vec![],
+            ids: vec![],
+        };
+
+        let build_id = Type::Array {
+            ty: Box::new(Type::U8),
+            lengths: vec![BUILD_ID_SIZE as u64],
+        };
+        builder.add_log(
+            Level::Error,
+            INIT_FILE,
+            1,
+            "cdefmt init, build-id: {}",
+            &[("build_id", ArgumentType::Value(build_id))],
+        );
+
+        builder
+    }
+
+    /// Sets the elf's build ID, by default it's derived from the logs' metadata, so it changes
+    /// whenever a log is added.
+    pub fn with_build_id(mut self, build_id: [u8; BUILD_ID_SIZE]) -> Self {
+        self.build_id = Some(build_id);
+        self
+    }
+
+    /// The build ID that the init log has to send.
+    pub fn build_id(&self) -> [u8; BUILD_ID_SIZE] {
+        self.build_id.unwrap_or_else(|| hash(&self.section))
+    }
+
+    /// Names the compilation unit of the logs in `file` `name`, as when the elf is built using
+    /// prefix maps or relative paths, by default it's named `file`.
+    pub fn set_compilation_unit(&mut self, file: &str, name: &str) {
+        self.unit_names.insert(file.to_string(), name.to_string());
+    }
+
+    /// Adds a log, returning its id.
+    ///
+    /// `args` are the names of the arguments, as written in the log macro, and their types.
+    pub fn add_log(
+        &mut self,
+        level: Level,
+        file: &str,
+        line: u32,
+        fmt: &str,
+        args: &[(&str, ArgumentType)],
+    ) -> u64 {
+        let id = self.section.len() as u64;
+        let counter = self.logs.len() as u32;
+
+        // The strings' lengths include their null terminators.
+        let header = [
+            SCHEMA_VERSION,
+            counter,
+            line,
+            file.len() as u32 + 1,
+            fmt.len() as u32 + 1,
+            args.len() as u32,
+        ];
+        for value in header {
+            self.section.extend(self.u32_bytes(value));
+        }
+        self.section.push(level as u8);
+        push_str(&mut self.section, file);
+        push_str(&mut self.section, fmt);
+
+        for (name, _) in args {
+            self.section.extend(self.u32_bytes(name.len() as u32 + 1));
+            push_str(&mut self.section, name);
+        }
+
+        self.logs.push(Log {
+            counter,
+            file: file.to_string(),
+            args: args.iter().map(|(_, ty)| ty.clone()).collect(),
+        });
+        self.ids.push(id);
+
+        id
+    }
+
+    /// Adds each of `logs`, returning their ids.
+    pub fn add_logs(&mut self, logs: &[LogSpec]) -> Vec<u64> {
+        logs.iter()
+            .map(|(level, file, line, fmt, args)| self.add_log(*level, file, *line, fmt, args))
+            .collect()
+    }
+
+    /// Builds the elf.
+    pub fn build(&self) -> anyhow::Result<Vec<u8>> {
+        let (architecture, endianness) =
+            match (self.encoding.address_size, self.encoding.little_endian) {
+                (8, true) => (Architecture::X86_64, Endianness::Little),
+                (8, false) => (Architecture::PowerPc64, Endianness::Big),
+                (4, true) => (Architecture::Arm, Endianness::Little),
+                (4, false) => (Architecture::PowerPc, Endianness::Big),
+                (size, _) => bail!("Unsupported address size for an elf: {size}"),
+            };
+        let mut object = Object::new(BinaryFormat::Elf, architecture, endianness);
+
+        let section = object.add_section(vec![], b".cdefmt".to_vec(), SectionKind::ReadOnlyData);
+        object.set_section_data(section, self.section.clone(), 1);
+
+        for (log, id) in self.logs.iter().zip(&self.ids) {
+            object.add_symbol(Symbol {
+                name: format!("cdefmt_log_metadata{}", log.counter).into_bytes(),
+                value: *id,
+                size: 0,
+                kind: SymbolKind::Data,
+                scope: SymbolScope::Compilation,
+                weak: false,
+                section: SymbolSection::Section(section),
+                flags: SymbolFlags::None,
+            });
+        }
+
+        let note = object.add_section(vec![], b".note.gnu.build-id".to_vec(), SectionKind::Note);
+        object.set_section_data(note, self.build_id_note(), 4);
+
+        let endian = if self.encoding.little_endian {
+            gimli::RunTimeEndian::Little
+        } else {
+            gimli::RunTimeEndian::Big
+        };
+        let mut sections = dwarf::Sections::new(dwarf::EndianVec::new(endian));
+        self.dwarf().write(&mut sections)?;
+        sections.for_each(|id, data| -> anyhow::Result<()> {
+            if !data.slice().is_empty() {
+                let section =
+                    object.add_section(vec![], id.name().as_bytes().to_vec(), SectionKind::Debug);
+                object.set_section_data(section, data.slice().to_vec(), 1);
+            }
+            Ok(())
+        })?;
+
+        Ok(object.write()?)
+    }
+
+    fn u32_bytes(&self, value: u32) -> [u8; 4] {
+        if self.encoding.little_endian {
+            value.to_le_bytes()
+        } else {
+            value.to_be_bytes()
+        }
+    }
+
+    fn build_id_note(&self) -> Vec<u8> {
+        let mut note = vec![];
+        // The name's size, the descriptor's size and the note's type.
+        for value in [4, BUILD_ID_SIZE as u32, NT_GNU_BUILD_ID] {
+            note.extend(self.u32_bytes(value));
+        }
+        note.extend(b"GNU\0");
+        note.extend(self.build_id());
+        note
+    }
+
+    /// Describes each log's arguments structure, in a compilation unit per file.
+    fn dwarf(&self) -> dwarf::Dwarf {
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: self.encoding.address_size,
+        };
+
+        let mut dwarf = dwarf::Dwarf::new();
+        let mut units = BTreeMap::new();
+
+        for log in &self.logs {
+            let unit = *units.entry(log.file.as_str()).or_insert_with(|| {
+                let id = dwarf
+                    .units
+                    .add(dwarf::Unit::new(encoding, dwarf::LineProgram::none()));
+                let unit = dwarf.units.get_mut(id);
+                let root = unit.root();
+                let name = self.unit_names.get(&log.file).unwrap_or(&log.file);
+                set_name(unit, root, name);
+                unit.get_mut(root).set(
+                    gimli::DW_AT_producer,
+                    AttributeValue::String(b"cdefmt-encoder".to_vec()),
+                );
+                id
+            });
+
+            let args = self.args_type(&log.args);
+            let unit = dwarf.units.get_mut(unit);
+            add_type(
+                unit,
+                &args,
+                Some(&format!("cdefmt_log_args_t{}", log.counter)),
+            );
+        }
+
+        dwarf
+    }
+
+    /// Returns the packed arguments structure generated by `__CDEFMT_LOG`.
+    fn args_type(&self, args: &[ArgumentType]) -> Type {
+        let size_t = match self.encoding.address_size {
+            2 => Type::U16,
+            4 => Type::U32,
+            _ => Type::U64,
+        };
+        let address_size = self.encoding.address_size as usize;
+
+        let mut members = vec![StructureMember {
+            offset: 0,
+            name: "log_id".to_string(),
+            ty: Type::Pointer(Box::new(size_t.clone())),
+        }];
+        let mut offset = address_size;
+
+        for (i, arg) in args.iter().enumerate() {
+            let (name, ty) = match arg {
+                ArgumentType::Value(ty) => (format!("arg{i}"), ty.clone()),
+                ArgumentType::DynamicArray(element) => {
+                    let ty = Type::Structure {
+                        members: vec![
+                            StructureMember {
+                                offset: 0,
+                                name: "size".to_string(),
+                                ty: size_t.clone(),
+                            },
+                            StructureMember {
+                                offset: address_size as u64,
+                                name: "type".to_string(),
+                                ty: Type::Array {
+                                    ty: Box::new(element.clone()),
+                                    lengths: vec![0],
+                                },
+                            },
+                        ],
+                        size: address_size,
+                    };
+                    (format!("dynamic_array_{i}"), ty)
+                }
+            };
+
+            let size = ty.size();
+            members.push(StructureMember {
+                offset: offset as u64,
+                name,
+                ty,
+            });
+            offset += size;
+        }
+
+        members.push(StructureMember {
+            offset: offset as u64,
+            name: "dynamic_data".to_string(),
+            ty: Type::Array {
+                ty: Box::new(Type::U8),
+                lengths: vec![0],
+            },
+        });
+
+        Type::Structure {
+            members,
+            size: offset,
+        }
+    }
+}
+
+/// Builds an elf for a target with the given encoding containing `logs`, returning it along with
+/// the logs' ids.
+pub fn build(encoding: Encoding, logs: &[LogSpec]) -> anyhow::Result<(Vec<u8>, Vec<u64>)> {
+    let mut builder = ElfBuilder::new(encoding);
+    let ids = builder.add_logs(logs);
+    Ok((builder.build()?, ids))
+}
+
+/// Adds the DIEs describing `ty` to the unit, returning the type's DIE.
+fn add_type(unit: &mut dwarf::Unit, ty: &Type, name: Option<&str>) -> UnitEntryId {
+    let root = unit.root();
+
+    let base = |unit: &mut dwarf::Unit, name: &str, encoding: gimli::DwAte| {
+        let id = unit.add(root, gimli::DW_TAG_base_type);
+        set_name(unit, id, name);
+        let entry = unit.get_mut(id);
+        entry.set(
+            gimli::DW_AT_byte_size,
+            AttributeValue::Udata(ty.size() as u64),
+        );
+        entry.set(gimli::DW_AT_encoding, AttributeValue::Encoding(encoding));
+        id
+    };
+
+    match ty {
+        Type::Bool => base(unit, "_Bool", gimli::DW_ATE_boolean),
+        Type::U8 => base(unit, "unsigned char", gimli::DW_ATE_unsigned_char),
+        Type::U16 => base(unit, "short unsigned int", gimli::DW_ATE_unsigned),
+        Type::U32 => base(unit, "unsigned int", gimli::DW_ATE_unsigned),
+        Type::U64 => base(unit, "long unsigned int", gimli::DW_ATE_unsigned),
+        Type::I8 => base(unit, "signed char", gimli::DW_ATE_signed_char),
+        Type::I16 => base(unit, "short int", gimli::DW_ATE_signed),
+        Type::I32 => base(unit, "int", gimli::DW_ATE_signed),
+        Type::I64 => base(unit, "long int", gimli::DW_ATE_signed),
+        Type::F32 => base(unit, "float", gimli::DW_ATE_float),
+        Type::F64 => base(unit, "double", gimli::DW_ATE_float),
+        Type::Enumeration {
+            ty: storage,
+            valid_values,
+        } => {
+            let storage_id = add_type(unit, storage, None);
+            let id = unit.add(root, gimli::DW_TAG_enumeration_type);
+            let entry = unit.get_mut(id);
+            entry.set(
+                gimli::DW_AT_byte_size,
+                AttributeValue::Udata(ty.size() as u64),
+            );
+            entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(storage_id));
+
+            let signed = matches!(**storage, Type::I8 | Type::I16 | Type::I32 | Type::I64);
+            for (value, name) in valid_values {
+                let enumerator = unit.add(id, gimli::DW_TAG_enumerator);
+                set_name(unit, enumerator, name);
+                let value = if signed {
+                    AttributeValue::Sdata(*value as i64)
+                } else {
+                    AttributeValue::Udata(*value as u64)
+                };
+                unit.get_mut(enumerator)
+                    .set(gimli::DW_AT_const_value, value);
+            }
+            id
+        }
+        Type::Structure { members, size } => {
+            let id = unit.add(root, gimli::DW_TAG_structure_type);
+            if let Some(name) = name {
+                set_name(unit, id, name);
+            }
+            unit.get_mut(id)
+                .set(gimli::DW_AT_byte_size, AttributeValue::Udata(*size as u64));
+
+            for member in members {
+                let member_ty = add_type(unit, &member.ty, None);
+                let member_id = unit.add(id, gimli::DW_TAG_member);
+                set_name(unit, member_id, &member.name);
+                let entry = unit.get_mut(member_id);
+                entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(member_ty));
+                entry.set(
+                    gimli::DW_AT_data_member_location,
+                    AttributeValue::Udata(member.offset),
+                );
+            }
+            id
+        }
+        // Only the pointer's width is known, it's described as a `void*`.
+        Type::Pointer(_) => {
+            let id = unit.add(root, gimli::DW_TAG_pointer_type);
+            unit.get_mut(id).set(
+                gimli::DW_AT_byte_size,
+                AttributeValue::Udata(ty.size() as u64),
+            );
+            id
+        }
+        Type::Array {
+            ty: element,
+            lengths,
+        } => {
+            let element_id = add_type(unit, element, None);
+            let id = unit.add(root, gimli::DW_TAG_array_type);
+            unit.get_mut(id)
+                .set(gimli::DW_AT_type, AttributeValue::UnitRef(element_id));
+
+            for length in lengths {
+                let dimension = unit.add(id, gimli::DW_TAG_subrange_type);
+                unit.get_mut(dimension)
+                    .set(gimli::DW_AT_count, AttributeValue::Udata(*length));
+            }
+            id
+        }
+    }
+}
+
+fn set_name(unit: &mut dwarf::Unit, id: UnitEntryId, name: &str) {
+    unit.get_mut(id).set(
+        gimli::DW_AT_name,
+        AttributeValue::String(name.as_bytes().to_vec()),
+    );
+}
+
+fn push_str(section: &mut Vec<u8>, s: &str) {
+    section.extend(s.as_bytes());
+    section.push(0);
+}
+
+/// Derives a build ID from `data`, using FNV-1a with a different offset basis for each 8 bytes.
+fn hash(data: &[u8]) -> [u8; BUILD_ID_SIZE] {
+    let mut build_id = [0; BUILD_ID_SIZE];
+    for (i, chunk) in build_id.chunks_mut(8).enumerate() {
+        let mut hash = 0xcbf2_9ce4_8422_2325u64 ^ i as u64;
+        for byte in data {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        chunk.copy_from_slice(&hash.to_le_bytes()[..chunk.len()]);
+    }
+    build_id
+}
@@ -0,0 +1,308 @@
+//! Encodes cdefmt log frames, using the same wire layout as the C macros in `cdefmt.h`.
+//!
+//! A frame is the log's arguments structure, which is packed:
+//! * The log id, a pointer to the log's metadata, so it's as wide as the target's pointers.
+//! * The arguments, in order. Dynamic arrays are replaced by their length in bytes, as a `size_t`.
+//! * The elements of the dynamic arrays, in order.
+//!
+//! The encoder is `no_std` and doesn't allocate, so it can be used both by Rust firmware logging
+//! alongside C code, and by host tests that need frames without a C toolchain, the `std` feature
+//! adds [`encode_to_vec`].
+//! The `elf` feature adds [`elf::ElfBuilder`], which builds an elf describing the logs, for
+//! decoding the frames in tests.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::fmt;
+
+#[cfg(feature = "elf")]
+pub mod elf;
+
+/// The target's byte order and pointer width.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Encoding {
+    pub little_endian: bool,
+    /// The size of pointers and `size_t`, in bytes: 2, 4 or 8.
+    pub address_size: u8,
+}
+
+impl Encoding {
+    /// The encoding of the target the encoder is compiled for.
+    pub const NATIVE: Self = Self {
+        little_endian: cfg!(target_endian = "little"),
+        address_size: core::mem::size_of::<usize>() as u8,
+    };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The frame doesn't fit in the buffer.
+    BufferTooSmall,
+    /// A pointer sized value doesn't fit in the target's pointers.
+    AddressOverflow(u64),
+    /// The encoding's address size isn't 2, 4 or 8.
+    UnsupportedAddressSize(u8),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::BufferTooSmall => write!(f, "The frame doesn't fit in the buffer"),
+            Error::AddressOverflow(value) => {
+                write!(
+                    f,
+                    "The value {value:#x} doesn't fit in the target's pointers"
+                )
+            }
+            Error::UnsupportedAddressSize(size) => {
+                write!(f, "Unsupported address size: {size}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// A log argument.
+#[derive(Clone, Copy)]
+pub enum Argument<'a> {
+    /// A fixed size argument, passed by value to the log macro.
+    Value(&'a dyn Encode),
+    /// An argument passed using `CDEFMT_DYNAMIC_ARRAY` or `CDEFMT_DYNAMIC_STRING`, for example a
+    /// slice or a `&str`.
+    DynamicArray(&'a dyn Encode),
+}
+
+/// Encodes the log `id` with its `args` into `buf`, returning the frame's length.
+///
+/// `id` is the address of the log's metadata in the `.cdefmt` section.
+pub fn encode(buf: &mut [u8], encoding: Encoding, id: u64, args: &[Argument]) -> Result<usize> {
+    let mut writer = Writer::new(buf, encoding)?;
+    write_frame(&mut writer, id, args)?;
+    Ok(writer.position())
+}
+
+/// Encodes the log `id` with its `args` into a buffer of the frame's size, see [`encode`].
+#[cfg(feature = "std")]
+pub fn encode_to_vec(encoding: Encoding, id: u64, args: &[Argument]) -> Result<std::vec::Vec<u8>> {
+    let mut counter = Writer::counter(encoding)?;
+    write_frame(&mut counter, id, args)?;
+
+    let mut buf = std::vec![0; counter.position()];
+    encode(&mut buf, encoding, id, args)?;
+    Ok(buf)
+}
+
+fn write_frame(writer: &mut Writer, id: u64, args: &[Argument]) -> Result<()> {
+    writer.address(id)?;
+
+    for arg in args {
+        match arg {
+            Argument::Value(value) => value.encode(writer)?,
+            Argument::DynamicArray(elements) => {
+                let size = encoded_size(*elements, writer.encoding())?;
+                writer.address(size as u64)?;
+            }
+        }
+    }
+
+    for arg in args {
+        if let Argument::DynamicArray(elements) = arg {
+            elements.encode(writer)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the number of bytes `value` is encoded to.
+pub fn encoded_size(value: &dyn Encode, encoding: Encoding) -> Result<usize> {
+    let mut writer = Writer::counter(encoding)?;
+    value.encode(&mut writer)?;
+    Ok(writer.position())
+}
+
+/// Writes values in the target's encoding.
+pub struct Writer<'buf> {
+    /// `None` when only counting how many bytes would be written.
+    buf: Option<&'buf mut [u8]>,
+    position: usize,
+    encoding: Encoding,
+}
+
+impl<'buf> Writer<'buf> {
+    pub fn new(buf: &'buf mut [u8], encoding: Encoding) -> Result<Self> {
+        Self::with_buffer(Some(buf), encoding)
+    }
+
+    fn counter(encoding: Encoding) -> Result<Self> {
+        Self::with_buffer(None, encoding)
+    }
+
+    fn with_buffer(buf: Option<&'buf mut [u8]>, encoding: Encoding) -> Result<Self> {
+        if !matches!(encoding.address_size, 2 | 4 | 8) {
+            return Err(Error::UnsupportedAddressSize(encoding.address_size));
+        }
+
+        Ok(Self {
+            buf,
+            position: 0,
+            encoding,
+        })
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// The number of bytes written so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        let end = self.position + bytes.len();
+        if let Some(buf) = &mut self.buf {
+            buf.get_mut(self.position..end)
+                .ok_or(Error::BufferTooSmall)?
+                .copy_from_slice(bytes);
+        }
+        self.position = end;
+        Ok(())
+    }
+
+    /// Writes `len` zero bytes, for padding between structure members.
+    pub fn padding(&mut self, len: usize) -> Result<()> {
+        for _ in 0..len {
+            self.bytes(&[0])?;
+        }
+        Ok(())
+    }
+
+    /// Writes a pointer or a `size_t`.
+    pub fn address(&mut self, value: u64) -> Result<()> {
+        let overflow = Error::AddressOverflow(value);
+        match self.encoding.address_size {
+            2 => self.u16(value.try_into().map_err(|_| overflow)?),
+            4 => self.u32(value.try_into().map_err(|_| overflow)?),
+            _ => self.u64(value),
+        }
+    }
+
+    pub fn u8(&mut self, value: u8) -> Result<()> {
+        self.bytes(&[value])
+    }
+
+    pub fn u16(&mut self, value: u16) -> Result<()> {
+        self.endian(value.to_le_bytes(), value.to_be_bytes())
+    }
+
+    pub fn u32(&mut self, value: u32) -> Result<()> {
+        self.endian(value.to_le_bytes(), value.to_be_bytes())
+    }
+
+    pub fn u64(&mut self, value: u64) -> Result<()> {
+        self.endian(value.to_le_bytes(), value.to_be_bytes())
+    }
+
+    fn endian<const N: usize>(&mut self, le: [u8; N], be: [u8; N]) -> Result<()> {
+        self.bytes(if self.encoding.little_endian {
+            &le
+        } else {
+            &be
+        })
+    }
+}
+
+/// A value that can be passed as a log argument.
+///
+/// Implementations must write the value the way the C compiler lays it out in memory, structures
+/// included, since the decoder reads them using the elf's debugging information.
+pub trait Encode {
+    fn encode(&self, writer: &mut Writer) -> Result<()>;
+}
+
+macro_rules! encode_integer {
+    ($($ty:ty => $unsigned:ident),* $(,)?) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, writer: &mut Writer) -> Result<()> {
+                    writer.$unsigned(*self as _)
+                }
+            }
+        )*
+    };
+}
+
+encode_integer!(
+    u8 => u8,
+    u16 => u16,
+    u32 => u32,
+    u64 => u64,
+    i8 => u8,
+    i16 => u16,
+    i32 => u32,
+    i64 => u64,
+);
+
+impl Encode for bool {
+    fn encode(&self, writer: &mut Writer) -> Result<()> {
+        writer.u8(*self as u8)
+    }
+}
+
+impl Encode for f32 {
+    fn encode(&self, writer: &mut Writer) -> Result<()> {
+        writer.u32(self.to_bits())
+    }
+}
+
+impl Encode for f64 {
+    fn encode(&self, writer: &mut Writer) -> Result<()> {
+        writer.u64(self.to_bits())
+    }
+}
+
+/// `size_t`, as wide as the target's pointers.
+impl Encode for usize {
+    fn encode(&self, writer: &mut Writer) -> Result<()> {
+        writer.address(*self as u64)
+    }
+}
+
+/// A pointer, only its address is logged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pointer(pub u64);
+
+impl Encode for Pointer {
+    fn encode(&self, writer: &mut Writer) -> Result<()> {
+        writer.address(self.0)
+    }
+}
+
+impl<T: Encode, const N: usize> Encode for [T; N] {
+    fn encode(&self, writer: &mut Writer) -> Result<()> {
+        self.as_slice().encode(writer)
+    }
+}
+
+impl<T: Encode> Encode for [T] {
+    fn encode(&self, writer: &mut Writer) -> Result<()> {
+        self.iter().try_for_each(|element| element.encode(writer))
+    }
+}
+
+/// The string's bytes, without a null terminator, like `CDEFMT_DYNAMIC_STRING`.
+impl Encode for str {
+    fn encode(&self, writer: &mut Writer) -> Result<()> {
+        writer.bytes(self.as_bytes())
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode(&self, writer: &mut Writer) -> Result<()> {
+        (**self).encode(writer)
+    }
+}
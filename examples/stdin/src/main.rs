@@ -1,15 +1,34 @@
 use std::{io::Read, path::PathBuf};
 
+use cdefmt_decoder::template::{self, ColorChoice, Template};
 use clap::Parser;
 use gimli::Reader;
 
-/// Simple program to greet a person
+/// Decodes length-value encoded cdefmt logs read from stdin and prints them to stdout.
 #[derive(clap::Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// Name of the person to greet
+    /// Path to the original, unstripped, elf that generated the logs.
     #[arg(short, long)]
     elf: PathBuf,
+
+    /// Output format of the decoded logs.
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Template of the text format, for example `{time} {level:>7} {file}:{line} {message}`.
+    #[arg(long, default_value = template::DEFAULT_TEMPLATE)]
+    template: String,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum Format {
+    /// Human readable text.
+    Text,
+    /// A single JSON array, written incrementally.
+    Json,
+    /// One JSON object per line.
+    Ndjson,
 }
 
 fn main() {
@@ -33,9 +52,11 @@ fn main_impl() -> std::result::Result<(), anyhow::Error> {
     let count = decoder.precache_log_metadata()?;
     let duration = start.elapsed();
 
-    println!("pre-cached {count} logs in {}[ms]", duration.as_millis());
+    eprintln!("pre-cached {count} logs in {}[ms]", duration.as_millis());
 
     let endianness = decoder.get_endianness();
+    let template =
+        Template::new(&args.template)?.with_color(ColorChoice::Auto.enabled(&std::io::stdout()));
 
     // stdout example writes length-value pairs
     // read the length, then use that to read the value.
@@ -43,6 +64,11 @@ fn main_impl() -> std::result::Result<(), anyhow::Error> {
     let mut stdin = std::io::stdin();
     let mut len = [0; std::mem::size_of::<u64>()];
     let mut buff = vec![0; 0];
+    let mut first = true;
+
+    if let Format::Json = args.format {
+        println!("[");
+    }
 
     while stdin.read_exact(&mut len).is_ok() {
         let len = gimli::EndianSlice::new(&len, endianness).read_u64()? as usize;
@@ -51,15 +77,34 @@ fn main_impl() -> std::result::Result<(), anyhow::Error> {
         let current_buff = &mut buff[..len];
 
         stdin.read_exact(current_buff)?;
-        let log = decoder
-            .decode_log(current_buff)
-            .and_then(|l| l.to_string().map(|s| (s, l.get_level())));
+        let log = decoder.decode_log(current_buff).map(|mut l| {
+            l.set_timestamp(std::time::SystemTime::now());
+            l
+        });
 
-        match log {
-            Ok((log, level)) => println!("{:<7} > {}", format!("{:?}", level), log),
-            Err(e) => println!("Error: {:?}", e),
+        match args.format {
+            Format::Text => match log {
+                Ok(log) => println!("{}", template.render(&log)),
+                Err(e) => println!("Error: {e}"),
+            },
+            Format::Json => match log {
+                Ok(log) => {
+                    let separator = if first { "" } else { ",\n" };
+                    print!("{separator}{}", serde_json::to_string_pretty(&log)?);
+                    first = false;
+                }
+                Err(e) => eprintln!("Error: {e}"),
+            },
+            Format::Ndjson => match log {
+                Ok(log) => println!("{}", serde_json::to_string(&log)?),
+                Err(e) => eprintln!("Error: {e}"),
+            },
         }
     }
 
+    if let Format::Json = args.format {
+        println!("\n]");
+    }
+
     Ok(())
 }
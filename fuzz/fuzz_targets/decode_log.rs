@@ -0,0 +1,30 @@
+//! Decodes arbitrary frames, as received from untrusted devices.
+
+#![no_main]
+
+use cdefmt_decoder::Decoder;
+use cdefmt_encoder::{Writer, encoded_size};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let elf = cdefmt_fuzz::elf();
+    let mut decoder = Decoder::new(elf.data.as_slice()).unwrap();
+
+    // Any id.
+    let _ = decoder.decode_log(data);
+
+    // A known id, so the arguments are parsed.
+    let Some((selector, args)) = data.split_first() else {
+        return;
+    };
+    let id = elf.ids[*selector as usize % elf.ids.len()];
+
+    let mut frame = vec![0; encoded_size(&0usize, cdefmt_fuzz::ENCODING).unwrap() + args.len()];
+    let mut writer = Writer::new(&mut frame, cdefmt_fuzz::ENCODING).unwrap();
+    writer.address(id).unwrap();
+    writer.bytes(args).unwrap();
+
+    if let Ok(log) = decoder.decode_log(&frame) {
+        let _ = log.to_string();
+    }
+});
@@ -0,0 +1,16 @@
+//! Parses an arbitrary elf, including its debugging information.
+
+#![no_main]
+
+use cdefmt_parser::Parser;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(parser) = Parser::new(data) else {
+        return;
+    };
+
+    for log in parser.iter_logs() {
+        let _ = log;
+    }
+});
@@ -0,0 +1,27 @@
+//! Parses logs' metadata from an arbitrary `.cdefmt` section.
+
+#![no_main]
+
+use cdefmt_parser::Parser;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let elf = cdefmt_fuzz::elf();
+
+    // Overwrite the start of the section, the rest keeps describing valid logs.
+    let mut elf_data = elf.data.clone();
+    let len = data.len().min(elf.section.len());
+    elf_data[elf.section.start..][..len].copy_from_slice(&data[..len]);
+
+    let parser = Parser::new(elf_data.as_slice()).unwrap();
+
+    for log in parser.iter_logs() {
+        let _ = log;
+    }
+
+    for id in (0..elf.section.len()).chain([usize::MAX]) {
+        if let Ok(metadata) = parser.get_log_metadata(id) {
+            let _ = parser.get_log_args_type(&metadata);
+        }
+    }
+});
@@ -0,0 +1,99 @@
+//! An elf shared by the fuzz targets, containing logs with every kind of argument.
+
+use std::{collections::BTreeMap, ops::Range, sync::OnceLock};
+
+use cdefmt_encoder::{
+    Encoding,
+    elf::{ArgumentType, ElfBuilder, INIT_ID},
+};
+use cdefmt_parser::{
+    metadata::Level,
+    r#type::{StructureMember, Type},
+};
+use object::{Object, ObjectSection};
+
+pub const ENCODING: Encoding = Encoding::NATIVE;
+
+pub struct Elf {
+    pub data: Vec<u8>,
+    pub ids: Vec<u64>,
+    /// The file range of the `.cdefmt` section.
+    pub section: Range<usize>,
+}
+
+pub fn elf() -> &'static Elf {
+    static ELF: OnceLock<Elf> = OnceLock::new();
+    ELF.get_or_init(build)
+}
+
+fn build() -> Elf {
+    let member = |offset, name: &str, ty| StructureMember {
+        offset,
+        name: name.to_string(),
+        ty,
+    };
+    let structure = Type::Structure {
+        members: vec![
+            member(0, "a", Type::U16),
+            member(4, "b", Type::U32),
+            member(8, "c", Type::F64),
+        ],
+        size: 16,
+    };
+    let nested = Type::Structure {
+        members: vec![
+            member(0, "inner", structure.clone()),
+            member(16, "d", Type::Bool),
+        ],
+        size: 24,
+    };
+    let enumeration = Type::Enumeration {
+        ty: Box::new(Type::I8),
+        valid_values: BTreeMap::from([(-1, "MINUS_ONE".to_string()), (1, "ONE".to_string())]),
+    };
+    let matrix = Type::Array {
+        ty: Box::new(Type::I16),
+        lengths: vec![2, 3],
+    };
+    let pointer = Type::Pointer(Box::new(match ENCODING.address_size {
+        4 => Type::U32,
+        _ => Type::U64,
+    }));
+
+    let mut builder = ElfBuilder::new(ENCODING);
+    let mut ids = vec![INIT_ID];
+    let logs: [&[(&str, ArgumentType)]; 5] = [
+        &[],
+        &[
+            ("a_u8", ArgumentType::Value(Type::U8)),
+            ("an_i64", ArgumentType::Value(Type::I64)),
+            ("a_f32", ArgumentType::Value(Type::F32)),
+            ("a_pointer", ArgumentType::Value(pointer)),
+        ],
+        &[
+            ("nested", ArgumentType::Value(nested)),
+            ("an_enum", ArgumentType::Value(enumeration)),
+            ("matrix", ArgumentType::Value(matrix)),
+        ],
+        &[
+            ("string", ArgumentType::DynamicArray(Type::I8)),
+            ("a_u32", ArgumentType::Value(Type::U32)),
+            ("structures", ArgumentType::DynamicArray(structure)),
+        ],
+        &[("samples", ArgumentType::DynamicArray(Type::U64))],
+    ];
+    for (line, args) in logs.into_iter().enumerate() {
+        let fmt = vec!["{}"; args.len()].join(" ");
+        ids.push(builder.add_log(Level::Info, "fuzz.c", line as u32, &fmt, args));
+    }
+
+    let data = builder.build().expect("Failed to build the elf");
+    let file = object::File::parse(data.as_slice()).expect("Failed to parse the elf");
+    let (offset, size) = file
+        .section_by_name(".cdefmt")
+        .and_then(|section| section.file_range())
+        .expect("The elf has no .cdefmt section");
+    let section = offset as usize..(offset + size) as usize;
+
+    Elf { data, ids, section }
+}
@@ -0,0 +1,160 @@
+//! Exporting a catalog of every log contained in an elf, for documentation and review.
+
+use std::{borrow::Cow, io::Write};
+
+use crate::{Parser, Result, metadata::Level, r#type::Type};
+
+/// A single log in the catalog.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Entry<'elf> {
+    pub id: usize,
+    pub level: Level,
+    pub file: Cow<'elf, str>,
+    pub line: usize,
+    pub fmt: &'elf str,
+    pub arguments: Vec<Argument<'elf>>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Argument<'elf> {
+    /// The expression that was passed to the log macro.
+    pub name: &'elf str,
+    /// `None` if the log's argument types weren't found in the elf.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub ty: Option<Type>,
+    /// The argument's C type, see [`Type::c_name`].
+    pub c_type: Option<String>,
+}
+
+impl Entry<'_> {
+    /// Renders the arguments as a C parameter list, for example `uint32_t some_u32, bool flag`.
+    pub fn parameters(&self) -> String {
+        self.arguments
+            .iter()
+            .map(|a| match &a.ty {
+                Some(ty) => ty.c_declaration(a.name),
+                None => a.name.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Collects all of the logs in the elf, sorted by id.
+pub fn collect<'elf>(parser: &Parser<'elf>) -> Result<Vec<Entry<'elf>>> {
+    let mut entries = parser
+        .iter_logs()
+        .map(|log| {
+            let (metadata, ty) = log?;
+            let types = ty.and_then(|ty| ty.log_arguments());
+
+            let arguments = metadata
+                .names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    let ty = types.as_ref().and_then(|t| t.get(i)).cloned();
+                    Argument {
+                        name,
+                        c_type: ty.as_ref().map(Type::c_name),
+                        ty,
+                    }
+                })
+                .collect();
+
+            Ok(Entry {
+                id: metadata.id,
+                level: metadata.level,
+                file: metadata.file,
+                line: metadata.line,
+                fmt: metadata.fmt,
+                arguments,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    entries.sort_by_key(|e| e.id);
+    Ok(entries)
+}
+
+/// Writes the catalog as CSV, with a header row.
+pub fn write_csv(entries: &[Entry], mut writer: impl Write) -> std::io::Result<()> {
+    writeln!(writer, "id,level,file,line,fmt,arguments")?;
+
+    for entry in entries {
+        writeln!(
+            writer,
+            "{:#x},{},{},{},{},{}",
+            entry.id,
+            entry.level,
+            csv_field(&entry.file),
+            entry.line,
+            csv_field(entry.fmt),
+            csv_field(&entry.parameters())
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Writes the catalog as a Markdown table.
+pub fn write_markdown(entries: &[Entry], mut writer: impl Write) -> std::io::Result<()> {
+    writeln!(writer, "| ID | Level | Location | Format | Arguments |")?;
+    writeln!(writer, "|----|-------|----------|--------|-----------|")?;
+
+    for entry in entries {
+        writeln!(
+            writer,
+            "| {:#x} | {} | {}:{} | {} | {} |",
+            entry.id,
+            entry.level,
+            markdown_cell(&entry.file),
+            entry.line,
+            markdown_code(entry.fmt),
+            markdown_code(&entry.parameters())
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Quotes a CSV field if needed, as described by RFC 4180.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Escapes the characters that would break a Markdown table cell.
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+/// Renders `text` as a Markdown code span inside a table cell.
+///
+/// Line breaks are shown as the `\n` escape used to write them in the C source.
+fn markdown_code(text: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+
+    // The code span's delimiter has to be longer than any run of backticks inside it.
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest + 1);
+
+    let text = text
+        .replace('|', "\\|")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r");
+    format!("{fence} {text} {fence}")
+}
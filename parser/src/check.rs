@@ -0,0 +1,224 @@
+//! Validation of all of the logs in an elf, without decoding any of them.
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    Parser,
+    format::{self, Argument, FormatTrait, Placeholder},
+    metadata::Metadata,
+    r#type::Type,
+};
+
+/// A problem found in a log, which would prevent it from being decoded or formatted.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub id: usize,
+    /// The log's source file and line, `None` if the log's metadata couldn't be parsed.
+    pub location: Option<(String, usize)>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some((file, line)) => write!(f, "{file}:{line}: {} [{:#x}]", self.message, self.id),
+            None => write!(f, "<unknown>: {} [{:#x}]", self.message, self.id),
+        }
+    }
+}
+
+impl Parser<'_> {
+    /// Checks every log in the elf, returning all of the problems found.
+    ///
+    /// Unlike [`Parser::iter_logs`], a broken log doesn't stop the check, so a single run reports
+    /// everything that has to be fixed.
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        let mut counters = HashMap::new();
+
+        for &id in &self.metadata_addresses {
+            let id = id as usize;
+            let metadata = match self.get_log_metadata(id) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    diagnostics.push(Diagnostic {
+                        id,
+                        location: None,
+                        message: format!("Malformed metadata: {e:#}"),
+                    });
+                    continue;
+                }
+            };
+
+            let mut report = |message: String| {
+                diagnostics.push(Diagnostic {
+                    id,
+                    location: Some((metadata.file.to_string(), metadata.line)),
+                    message,
+                })
+            };
+
+            // The argument types are looked up by file and counter, so a duplicate would make one
+            // of the logs decode using the other's types.
+            if let Some((other_id, other_line)) = counters.insert(
+                (metadata.file.clone(), metadata.counter),
+                (id, metadata.line),
+            ) && other_id != id
+            {
+                report(format!(
+                    "Counter {} is also used by the log at line {other_line} [{other_id:#x}]",
+                    metadata.counter
+                ));
+            }
+
+            let arguments = match self.get_log_args_type(&metadata) {
+                Ok(Some(ty)) => match ty.log_arguments() {
+                    Some(arguments) => Some(arguments),
+                    None => {
+                        report("The arguments type isn't a structure".to_string());
+                        None
+                    }
+                },
+                Ok(None) => {
+                    report(format!(
+                        "Missing type cdefmt_log_args_t{} in the debugging information",
+                        metadata.counter
+                    ));
+                    None
+                }
+                Err(e) => {
+                    report(format!("Unable to parse the arguments type: {e:#}"));
+                    None
+                }
+            };
+
+            if let Some(arguments) = &arguments
+                && arguments.len() != metadata.names.len()
+            {
+                report(format!(
+                    "The arguments type has {} members but the metadata has {} argument names",
+                    arguments.len(),
+                    metadata.names.len()
+                ));
+            }
+
+            for message in check_fmt(&metadata, arguments.as_deref()) {
+                report(message);
+            }
+        }
+
+        diagnostics
+    }
+}
+
+/// Checks that the placeholders in the log's format string refer to existing arguments, and that
+/// every argument is used.
+///
+/// If `arguments` are known, the placeholders' format specs are checked against their types.
+fn check_fmt(metadata: &Metadata, arguments: Option<&[Type]>) -> Vec<String> {
+    let placeholders = match format::placeholders(metadata.fmt) {
+        Ok(placeholders) => placeholders,
+        Err(e) => return vec![format!("Invalid format string \"{}\": {e}", metadata.fmt)],
+    };
+
+    let mut messages = vec![];
+    let mut used = vec![false; metadata.names.len()];
+    let mut next = 0;
+
+    for placeholder in &placeholders {
+        let index = match placeholder.argument {
+            Argument::Next => {
+                next += 1;
+                Some(next - 1)
+            }
+            Argument::Index(index) => Some(index),
+            Argument::Name(name) => metadata.names.iter().position(|n| *n == name),
+        };
+
+        let Some(index) = index.filter(|i| *i < metadata.names.len()) else {
+            messages.push(format!(
+                "Placeholder {} has no matching argument, the log has {} arguments",
+                describe(metadata.fmt, placeholder),
+                metadata.names.len()
+            ));
+            continue;
+        };
+        used[index] = true;
+
+        if let Some(ty) = arguments.and_then(|a| a.get(index))
+            && let Some(problem) = check_spec(placeholder, ty)
+        {
+            messages.push(format!(
+                "Placeholder {} can't format '{}': {problem}",
+                describe(metadata.fmt, placeholder),
+                metadata.names[index]
+            ));
+        }
+    }
+
+    for (name, _) in metadata.names.iter().zip(used).filter(|(_, used)| !used) {
+        messages.push(format!("Argument '{name}' isn't used by the format string"));
+    }
+
+    messages
+}
+
+/// Returns why `ty` can't be formatted by the placeholder, if it can't.
+///
+/// Uses the same table as the decoder's values, see [`FormatTrait::supports`].
+fn check_spec(placeholder: &Placeholder, ty: &Type) -> Option<String> {
+    let spec = placeholder.spec.ty;
+    let Some(format_trait) = FormatTrait::from_spec(spec) else {
+        return Some(format!("unknown format type '{spec}'"));
+    };
+
+    let ty = unsupported(ty, format_trait)?;
+    Some(match format_trait {
+        FormatTrait::String => "'s' requires a char array or a char".to_string(),
+        _ => format!("'{spec}' isn't supported for {} values", name(ty)),
+    })
+}
+
+/// Returns the first type within `ty` that can't be formatted using `format_trait`.
+fn unsupported(ty: &Type, format_trait: FormatTrait) -> Option<&Type> {
+    if !format_trait.supports(ty.into()) {
+        return Some(ty);
+    }
+
+    match ty {
+        Type::Structure { members, .. } => members
+            .iter()
+            .find_map(|m| unsupported(&m.ty, format_trait)),
+        Type::Pointer(ty) | Type::Array { ty, .. } => unsupported(ty, format_trait),
+        _ => None,
+    }
+}
+
+fn name(ty: &Type) -> &'static str {
+    match ty {
+        Type::Bool => "bool",
+        Type::U8 => "u8",
+        Type::U16 => "u16",
+        Type::U32 => "u32",
+        Type::U64 => "u64",
+        Type::I8 => "i8",
+        Type::I16 => "i16",
+        Type::I32 => "i32",
+        Type::I64 => "i64",
+        Type::F32 => "f32",
+        Type::F64 => "f64",
+        Type::Enumeration { .. } => "enumeration",
+        Type::Structure { .. } => "structure",
+        Type::Pointer(_) => "pointer",
+        Type::Array { .. } => "array",
+    }
+}
+
+/// The placeholder's text as it appears in the format string, for example `{some_u32:x}`.
+fn describe<'a>(fmt: &'a str, placeholder: &Placeholder) -> &'a str {
+    let start = placeholder.offset;
+    let end = fmt[start..]
+        .find('}')
+        .map_or(fmt.len(), |end| start + end + 1);
+    &fmt[start..end]
+}
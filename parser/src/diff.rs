@@ -0,0 +1,180 @@
+//! Comparing the logs of two elfs, for example two firmware releases.
+//!
+//! Log ids and counters change with every build, so logs are matched by their source instead:
+//! first by file and format string, then the remaining logs by file and line, allowing for lines
+//! that moved because of code added or removed above them.
+
+use std::collections::HashMap;
+
+use crate::catalog::Entry;
+
+/// The default maximum distance, in lines, between two logs matched by their line.
+pub const DEFAULT_MAX_DISTANCE: usize = 10;
+
+/// A difference between the two elfs.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "change", rename_all = "lowercase"))]
+pub enum Change<'a> {
+    /// The log only exists in the new elf.
+    Added { log: &'a Entry<'a> },
+    /// The log only exists in the old elf.
+    Removed { log: &'a Entry<'a> },
+    /// The log exists in both elfs, but some of its `fields` differ.
+    Changed {
+        old: &'a Entry<'a>,
+        new: &'a Entry<'a>,
+        fields: Vec<Field>,
+    },
+}
+
+impl Change<'_> {
+    /// The log's location, in the new elf unless it was removed.
+    fn location(&self) -> (&str, usize) {
+        match self {
+            Change::Added { log } | Change::Removed { log } | Change::Changed { new: log, .. } => {
+                (&log.file, log.line)
+            }
+        }
+    }
+}
+
+/// A field that differs between two matched logs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Field {
+    Fmt,
+    Level,
+    /// The arguments' names or types.
+    Arguments,
+}
+
+/// Matches the logs of two elfs, returning the logs that were added, removed or changed, sorted
+/// by location.
+///
+/// Logs whose format string changed are matched by line, if they are at most `max_distance` lines
+/// away from where they're expected to be.
+pub fn diff<'a>(
+    old: &'a [Entry<'a>],
+    new: &'a [Entry<'a>],
+    max_distance: usize,
+) -> Vec<Change<'a>> {
+    let mut old_matched = vec![false; old.len()];
+    let mut new_matched = vec![false; new.len()];
+    let mut pairs = vec![];
+
+    // Logs with the same file and format string, if a file has several identical logs they're
+    // paired in order of appearance.
+    let mut by_fmt = HashMap::<_, Vec<usize>>::new();
+    for (i, log) in by_line(new) {
+        by_fmt.entry((&*log.file, log.fmt)).or_default().push(i);
+    }
+    for (i, log) in by_line(old) {
+        if let Some(candidates) = by_fmt.get_mut(&(&*log.file, log.fmt))
+            && !candidates.is_empty()
+        {
+            let j = candidates.remove(0);
+            old_matched[i] = true;
+            new_matched[j] = true;
+            pairs.push((i, j));
+        }
+    }
+
+    // The remaining logs are matched by line, corrected by how much the closest log above,
+    // matched by format string, moved.
+    let mut shifts = HashMap::<_, Vec<(usize, isize)>>::new();
+    for &(i, j) in &pairs {
+        let shift = new[j].line as isize - old[i].line as isize;
+        shifts
+            .entry(&*old[i].file)
+            .or_default()
+            .push((old[i].line, shift));
+    }
+    for shifts in shifts.values_mut() {
+        shifts.sort();
+    }
+
+    let mut candidates = vec![];
+    for (i, old_log) in old.iter().enumerate().filter(|(i, _)| !old_matched[*i]) {
+        let shift = shifts
+            .get(&*old_log.file)
+            .and_then(|s| s.iter().rev().find(|(line, _)| *line < old_log.line))
+            .map_or(0, |(_, shift)| *shift);
+        let expected = old_log.line as isize + shift;
+
+        for (j, new_log) in new.iter().enumerate().filter(|(j, _)| !new_matched[*j]) {
+            let distance = (new_log.line as isize - expected).unsigned_abs();
+            if new_log.file == old_log.file && distance <= max_distance {
+                candidates.push((distance, i, j));
+            }
+        }
+    }
+
+    candidates.sort();
+    for (_, i, j) in candidates {
+        if !old_matched[i] && !new_matched[j] {
+            old_matched[i] = true;
+            new_matched[j] = true;
+            pairs.push((i, j));
+        }
+    }
+
+    let mut changes = pairs
+        .into_iter()
+        .filter_map(|(i, j)| {
+            let fields = compare(&old[i], &new[j]);
+            (!fields.is_empty()).then_some(Change::Changed {
+                old: &old[i],
+                new: &new[j],
+                fields,
+            })
+        })
+        .chain(
+            old.iter()
+                .zip(old_matched)
+                .filter(|(_, matched)| !matched)
+                .map(|(log, _)| Change::Removed { log }),
+        )
+        .chain(
+            new.iter()
+                .zip(new_matched)
+                .filter(|(_, matched)| !matched)
+                .map(|(log, _)| Change::Added { log }),
+        )
+        .collect::<Vec<_>>();
+
+    changes.sort_by(|a, b| a.location().cmp(&b.location()));
+    changes
+}
+
+/// Iterates over the logs and their indices, sorted by file and line.
+fn by_line<'a>(logs: &'a [Entry<'a>]) -> impl Iterator<Item = (usize, &'a Entry<'a>)> {
+    let mut logs = logs.iter().enumerate().collect::<Vec<_>>();
+    logs.sort_by(|(_, a), (_, b)| (&a.file, a.line).cmp(&(&b.file, b.line)));
+    logs.into_iter()
+}
+
+fn compare(old: &Entry, new: &Entry) -> Vec<Field> {
+    let mut fields = vec![];
+
+    if old.fmt != new.fmt {
+        fields.push(Field::Fmt);
+    }
+
+    if old.level != new.level {
+        fields.push(Field::Level);
+    }
+
+    let arguments = |log: &Entry| {
+        log.arguments
+            .iter()
+            .map(|a| (a.name.to_string(), a.c_type.clone()))
+            .collect::<Vec<_>>()
+    };
+    if arguments(old) != arguments(new) {
+        fields.push(Field::Arguments);
+    }
+
+    fields
+}
@@ -0,0 +1,402 @@
+//! Generating a Wireshark dissector for the logs of an elf.
+//!
+//! The dissector is a Lua plugin containing every log's level, location, format string and
+//! argument layout, so frames exported using `cdefmt_decoder::pcapng` are decoded by Wireshark
+//! itself, next to the rest of the traffic.
+//! Log ids change with every build, so the dissector has to be regenerated along with the elf.
+
+use std::{fmt::Write as _, io::Write};
+
+use gimli::RunTimeEndian;
+
+use crate::{
+    Error, Parser, Result,
+    r#type::{StructureMember, Type},
+};
+
+/// The first of the 16 link types reserved for private use (`LINKTYPE_USER0` to
+/// `LINKTYPE_USER15`).
+pub const LINKTYPE_USER0: u16 = 147;
+
+/// Writes a Lua dissector of the elf's logs, registered for frames of the given link type, which
+/// has to be one of `LINKTYPE_USER0` to `LINKTYPE_USER15`.
+pub fn write_lua(parser: &Parser, link_type: u16, mut writer: impl Write) -> Result<()> {
+    let user = link_type
+        .checked_sub(LINKTYPE_USER0)
+        .filter(|user| *user < 16)
+        .ok_or(Error::InvalidLinkType(link_type))?;
+
+    let mut logs = parser
+        .iter_logs()
+        .map(|log| {
+            let (metadata, ty) = log?;
+            let mut entry = String::new();
+
+            write!(
+                entry,
+                "    [{:#x}] = {{\n        level = {}, file = {}, line = {},\n        fmt = {},\n",
+                metadata.id,
+                lua_string(&metadata.level.to_string()),
+                lua_string(&metadata.file),
+                metadata.line,
+                lua_string(metadata.fmt),
+            )?;
+
+            // Logs whose types weren't found are shown without their arguments.
+            if let Some(Type::Structure { members, .. }) = ty {
+                entry.push_str("        args = {\n");
+                let arguments = members
+                    .iter()
+                    // Skip the log id.
+                    .skip(1)
+                    .filter(|m| m.name != "dynamic_data");
+                for (name, member) in metadata.names.iter().zip(arguments) {
+                    writeln!(
+                        entry,
+                        "            {{ name = {}, type = {} }},",
+                        lua_string(name),
+                        lua_argument(member)
+                    )?;
+                }
+                entry.push_str("        },\n");
+            }
+            entry.push_str("    },\n");
+
+            Ok((metadata.id, entry))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    logs.sort_by_key(|(id, _)| *id);
+
+    let build_id = parser
+        .build_id()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+
+    writeln!(
+        writer,
+        "-- Wireshark dissector of cdefmt logs, generated from the elf with build ID {build_id}."
+    )?;
+    writeln!(
+        writer,
+        "-- Log ids change with every build, regenerate it whenever the elf is rebuilt."
+    )?;
+    writeln!(writer)?;
+    writeln!(
+        writer,
+        "local ADDRESS_SIZE = {}",
+        parser.address_size().bytes()
+    )?;
+    writeln!(
+        writer,
+        "local LITTLE_ENDIAN = {}",
+        parser.endian() == RunTimeEndian::Little
+    )?;
+    writeln!(writer, "local WTAP_ENCAP = wtap.USER{user}")?;
+    writeln!(writer)?;
+    writeln!(writer, "local logs = {{")?;
+    for (_, entry) in logs {
+        writer.write_all(entry.as_bytes())?;
+    }
+    writeln!(writer, "}}")?;
+    writer.write_all(DISSECTOR.as_bytes())?;
+
+    Ok(())
+}
+
+/// Renders an argument's type, dynamic arrays are `{ size, type[0] }` structures whose elements
+/// follow the fixed size arguments.
+fn lua_argument(member: &StructureMember) -> String {
+    if let (Some(element), Type::Structure { members, size }) = (member.dynamic_array(), &member.ty)
+        && let Some(length) = members.first()
+    {
+        return format!(
+            "{{ kind = \"dynamic\", size = {size}, length = {}, type = {} }}",
+            lua_type(&length.ty),
+            lua_type(element)
+        );
+    }
+
+    lua_type(&member.ty)
+}
+
+/// Renders a type as a Lua table, which the dissector walks to decode the value.
+fn lua_type(ty: &Type) -> String {
+    let scalar = |kind: &str| format!("{{ kind = \"{kind}\" }}");
+
+    match ty {
+        Type::Bool => scalar("bool"),
+        Type::U8 => scalar("u8"),
+        Type::U16 => scalar("u16"),
+        Type::U32 => scalar("u32"),
+        Type::U64 => scalar("u64"),
+        Type::I8 => scalar("i8"),
+        Type::I16 => scalar("i16"),
+        Type::I32 => scalar("i32"),
+        Type::I64 => scalar("i64"),
+        Type::F32 => scalar("f32"),
+        Type::F64 => scalar("f64"),
+        Type::Enumeration { ty, valid_values } => {
+            // Keyed by the value's decimal text, since 64 bit values aren't Lua numbers.
+            let values = valid_values
+                .iter()
+                .map(|(value, name)| format!("[\"{value}\"] = {}, ", lua_string(name)))
+                .collect::<String>();
+            format!(
+                "{{ kind = \"enum\", type = {}, values = {{ {values}}} }}",
+                lua_type(ty)
+            )
+        }
+        Type::Structure { members, size } => {
+            let members = members
+                .iter()
+                .map(|m| {
+                    format!(
+                        "{{ name = {}, offset = {}, type = {} }}, ",
+                        lua_string(&m.name),
+                        m.offset,
+                        lua_type(&m.ty)
+                    )
+                })
+                .collect::<String>();
+            format!("{{ kind = \"struct\", size = {size}, members = {{ {members}}} }}")
+        }
+        Type::Pointer(ty) => format!("{{ kind = \"pointer\", type = {} }}", lua_type(ty)),
+        Type::Array { ty, lengths } => {
+            let count = if lengths.is_empty() {
+                0
+            } else {
+                lengths.iter().product::<u64>()
+            };
+            format!(
+                "{{ kind = \"array\", count = {count}, type = {} }}",
+                lua_type(ty)
+            )
+        }
+    }
+}
+
+/// Quotes `s` as a Lua string literal.
+fn lua_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            // Lua's decimal escapes take up to 3 digits, so pad them in case a digit follows.
+            c if c.is_ascii_control() => {
+                let _ = write!(quoted, "\\{:03}", c as u8);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The part of the dissector that doesn't depend on the elf.
+const DISSECTOR: &str = r#"
+local cdefmt = Proto("cdefmt", "cdefmt log")
+
+local fields = {
+    id = ProtoField.uint64("cdefmt.id", "ID", base.HEX),
+    level = ProtoField.string("cdefmt.level", "Level"),
+    file = ProtoField.string("cdefmt.file", "File"),
+    line = ProtoField.uint32("cdefmt.line", "Line"),
+    fmt = ProtoField.string("cdefmt.fmt", "Format"),
+    message = ProtoField.string("cdefmt.message", "Message"),
+}
+cdefmt.fields = { fields.id, fields.level, fields.file, fields.line, fields.fmt, fields.message }
+
+local experts = {
+    unknown = ProtoExpert.new("cdefmt.unknown", "Unknown log id, was the dissector generated from this elf?",
+        expert.group.UNDECODED, expert.severity.WARN),
+    malformed = ProtoExpert.new("cdefmt.malformed", "Malformed log frame",
+        expert.group.MALFORMED, expert.severity.ERROR),
+}
+cdefmt.experts = { experts.unknown, experts.malformed }
+
+local function integer(size, signed)
+    local method = (LITTLE_ENDIAN and "le_" or "") .. (signed and "int" or "uint") .. (size == 8 and "64" or "")
+    return { size = size, read = function(range) return range[method](range) end }
+end
+
+local function float(size)
+    local method = LITTLE_ENDIAN and "le_float" or "float"
+    return { size = size, read = function(range) return range[method](range) end }
+end
+
+local SCALARS = {
+    bool = { size = 1, read = function(range) return range:uint() ~= 0 end },
+    u8 = integer(1, false),
+    u16 = integer(2, false),
+    u32 = integer(4, false),
+    u64 = integer(8, false),
+    i8 = integer(1, true),
+    i16 = integer(2, true),
+    i32 = integer(4, true),
+    i64 = integer(8, true),
+    f32 = float(4),
+    f64 = float(8),
+}
+
+local function size_of(ty)
+    local scalar = SCALARS[ty.kind]
+    if scalar then
+        return scalar.size
+    elseif ty.kind == "enum" or ty.kind == "pointer" then
+        return size_of(ty.type)
+    elseif ty.kind == "array" then
+        return ty.count * size_of(ty.type)
+    end
+    return ty.size
+end
+
+-- Adds the value of type `ty` at `offset` to `tree`, returns the value's text.
+local function dissect(tvb, offset, ty, tree, name)
+    local scalar = SCALARS[ty.kind]
+    if scalar then
+        local range = tvb(offset, scalar.size)
+        local text = tostring(scalar.read(range))
+        tree:add(range, name .. ": " .. text)
+        return text
+    elseif ty.kind == "enum" or ty.kind == "pointer" then
+        local inner = SCALARS[ty.type.kind]
+        local range = tvb(offset, inner.size)
+        local value = inner.read(range)
+        local text
+        if ty.kind == "enum" then
+            text = ty.values[tostring(value)] or tostring(value)
+        elseif type(value) == "number" then
+            text = string.format("0x%x", value)
+        else
+            text = "0x" .. value:tohex()
+        end
+        tree:add(range, name .. ": " .. text)
+        return text
+    end
+
+    local parts = {}
+    local subtree = tree:add(tvb(offset, size_of(ty)), name)
+    if ty.kind == "struct" then
+        for _, member in ipairs(ty.members) do
+            local text = dissect(tvb, offset + member.offset, member.type, subtree, member.name)
+            parts[#parts + 1] = member.name .. ": " .. text
+        end
+        parts = "{ " .. table.concat(parts, ", ") .. " }"
+    else
+        local size = size_of(ty.type)
+        for i = 0, ty.count - 1 do
+            parts[#parts + 1] = dissect(tvb, offset + i * size, ty.type, subtree, "[" .. i .. "]")
+        end
+        parts = "[" .. table.concat(parts, ", ") .. "]"
+    end
+    subtree:set_text(name .. ": " .. parts)
+    return parts
+end
+
+-- Adds the log's arguments to `tree`, returns the arguments' texts.
+local function dissect_arguments(tvb, log, tree)
+    local values = {}
+    local dynamic = {}
+    local offset = ADDRESS_SIZE
+
+    for i, arg in ipairs(log.args) do
+        if arg.type.kind == "dynamic" then
+            local length = SCALARS[arg.type.length.kind]
+            local bytes = length.read(tvb(offset, length.size))
+            dynamic[#dynamic + 1] = { index = i, bytes = tonumber(tostring(bytes)) }
+        else
+            values[i] = dissect(tvb, offset, arg.type, tree, arg.name)
+        end
+        offset = offset + size_of(arg.type)
+    end
+
+    -- The elements of dynamic arrays follow the fixed size arguments, in order.
+    for _, array in ipairs(dynamic) do
+        local arg = log.args[array.index]
+        local size = size_of(arg.type.type)
+        local count = size > 0 and math.floor(array.bytes / size) or 0
+        local ty = { kind = "array", count = count, type = arg.type.type }
+        values[array.index] = dissect(tvb, offset, ty, tree, arg.name)
+        offset = offset + count * size
+    end
+
+    return values
+end
+
+-- Replaces the placeholders of the log's format string with the arguments' texts, their format
+-- specs are ignored.
+local function format(log, values)
+    local next_index = 0
+    local text = log.fmt:gsub("{{", "\1"):gsub("}}", "\2")
+
+    text = text:gsub("{([^:}]*)[^}]*}", function(argument)
+        argument = argument:match("^%s*(.-)%s*$")
+        local index
+        if argument == "" then
+            next_index = next_index + 1
+            index = next_index
+        elseif argument:match("^%d+$") then
+            index = tonumber(argument) + 1
+        else
+            for i, arg in ipairs(log.args or {}) do
+                if arg.name == argument then
+                    index = i
+                end
+            end
+        end
+        -- Unknown arguments are left as they are.
+        return index and values[index]
+    end)
+
+    return (text:gsub("\1", "{"):gsub("\2", "}"))
+end
+
+function cdefmt.dissector(tvb, pinfo, tree)
+    pinfo.cols.protocol = "CDEFMT"
+    local subtree = tree:add(cdefmt, tvb())
+
+    if tvb:len() < ADDRESS_SIZE then
+        subtree:add_proto_expert_info(experts.malformed, "The frame is shorter than a log id")
+        return
+    end
+
+    local id_range = tvb(0, ADDRESS_SIZE)
+    local id = tonumber(tostring(integer(ADDRESS_SIZE, false).read(id_range)))
+    subtree:add(fields.id, id_range, UInt64.new(id))
+
+    local log = logs[id]
+    if not log then
+        pinfo.cols.info = string.format("Unknown log 0x%x", id)
+        subtree:add_proto_expert_info(experts.unknown)
+        return
+    end
+
+    subtree:add(fields.level, log.level)
+    subtree:add(fields.file, log.file)
+    subtree:add(fields.line, log.line)
+    subtree:add(fields.fmt, log.fmt)
+
+    local values = {}
+    if log.args then
+        local ok, err = pcall(function()
+            values = dissect_arguments(tvb, log, subtree)
+        end)
+        if not ok then
+            subtree:add_proto_expert_info(experts.malformed, tostring(err))
+        end
+    end
+
+    local message = format(log, values)
+    subtree:add(fields.message, message)
+    subtree:append_text(", " .. message)
+    pinfo.cols.info = string.format("[%s] %s:%d %s", log.level, log.file, log.line, message)
+end
+
+DissectorTable.get("wtap_encap"):add(WTAP_ENCAP, cdefmt)
+"#;
@@ -7,6 +7,7 @@ use std::fmt::{self};
 
 use crate::Error;
 use crate::Result;
+use crate::path::{self, PathMap};
 use crate::r#type::{StructureMember, Type};
 
 macro_rules! some {
@@ -30,10 +31,12 @@ fn get_attribute<R: Reader>(
 ) -> Result<AttributeValue<R, R::Offset>> {
     entry
         .attr_value(attribute)
-        .ok_or(Error::NoAttribute(attribute).into())
+        .ok_or(Error::NoAttribute(attribute))
 }
 
-pub(crate) struct SourceLocation {
+/// Where a DIE is declared in the source code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
     pub file: String,
     pub line: u64,
     pub column: u64,
@@ -81,8 +84,6 @@ impl fmt::Display for SourceLocation {
     }
 }
 
-use anyhow::{Context, Result as AnyhowResult};
-
 pub trait ContextLocationExt<T> {
     fn with_parse_context<R>(
         self,
@@ -90,23 +91,23 @@ pub trait ContextLocationExt<T> {
         dwarf: &gimli::Dwarf<R>,
         unit: &gimli::Unit<R>,
         entry: &gimli::DebuggingInformationEntry<R>,
-    ) -> AnyhowResult<T>
+    ) -> Result<T>
     where
         R: gimli::Reader;
 }
 
-impl<T> ContextLocationExt<T> for AnyhowResult<T> {
+impl<T> ContextLocationExt<T> for Result<T> {
     fn with_parse_context<R>(
         self,
         item: &str,
         dwarf: &gimli::Dwarf<R>,
         unit: &gimli::Unit<R>,
         entry: &gimli::DebuggingInformationEntry<R>,
-    ) -> AnyhowResult<T>
+    ) -> Result<T>
     where
         R: gimli::Reader,
     {
-        self.with_context(|| {
+        self.map_err(|source| {
             // Bind to a variable so we can borrow it later.
             let name = get_attribute(entry, gimli::DW_AT_name)
                 .ok()
@@ -117,11 +118,11 @@ impl<T> ContextLocationExt<T> for AnyhowResult<T> {
                 .and_then(|n| n.to_string().ok())
                 .unwrap_or(std::borrow::Cow::Borrowed("<unnamed>"));
 
-            let location = SourceLocation::extract(dwarf, unit, entry);
-            if let Some(location) = location {
-                format!("{location}: in {item} `{name}`")
-            } else {
-                format!("<unknown>: in {item} `{name}`")
+            Error::Dwarf {
+                item: item.to_string(),
+                name: name.to_string(),
+                location: SourceLocation::extract(dwarf, unit, entry),
+                source: Box::new(source),
             }
         })
     }
@@ -171,6 +172,7 @@ impl<'elf> Dwarf<'elf> {
 
     /// Tries to find the type whose name is `type_name` and is located in the compilation unit
     /// `compilation_unit_name`.
+    /// See [`find_compilation_unit`] for how `path_map` is used to find the compilation unit.
     ///
     /// Output:
     /// * Returns `Ok(Some)` if the type is successfully found.
@@ -180,16 +182,21 @@ impl<'elf> Dwarf<'elf> {
         &'elf self,
         compilation_unit_name: &str,
         type_name: &str,
+        path_map: &PathMap,
     ) -> Result<Option<Type>> {
         let dwarf = self.borrow();
-        let compilation_unit = some!(find_compilation_unit(&dwarf, compilation_unit_name)?);
+        let compilation_unit = some!(find_compilation_unit(
+            &dwarf,
+            compilation_unit_name,
+            path_map
+        )?);
         let unit_offset = some!(find_type_die(&dwarf, &compilation_unit, type_name)?);
 
         // Unwrap safety: the DIE at unit_offset must exist, otherwise find_type_die would have returned an error.
         let entry = compilation_unit.entry(unit_offset).unwrap();
 
         parse_ctx!(
-            parse_type(&dwarf, &compilation_unit, unit_offset).map(Some),
+            parse_type(&dwarf, &compilation_unit, unit_offset, 0).map(Some),
             "type",
             &dwarf,
             &compilation_unit,
@@ -207,7 +214,18 @@ impl<'elf> Dwarf<'elf> {
     }
 }
 
-/// Tries to find the compilation unit by name.
+/// The shortest path suffix a unit has to share with the log's file, a file name alone is too common
+/// to tell units apart, for example every directory may have its own `main.c`.
+const MIN_COMMON_SUFFIX: usize = 2;
+
+/// Tries to find the compilation unit of the source file `name`.
+///
+/// The unit's name is usually identical to `__FILE__`, but the two differ when the elf was built
+/// using prefix maps or relative paths, so units are matched in order of preference:
+/// 1. A unit whose name, or name joined to its compilation directory, is the same path as `name`,
+///    either as is or after applying `path_map`.
+/// 2. The unit sharing the longest path suffix with `name`, at least the file name and its
+///    directory, as long as there's exactly one such unit.
 ///
 /// Output:
 /// * Returns `Ok(Some)` if the compilation unit is successfully found.
@@ -216,28 +234,76 @@ impl<'elf> Dwarf<'elf> {
 fn find_compilation_unit<R: Reader>(
     dwarf: &gimli::Dwarf<R>,
     name: &str,
+    path_map: &PathMap,
 ) -> Result<Option<Unit<R>>> {
+    let mut best = None;
+    let mut best_suffix = MIN_COMMON_SUFFIX - 1;
+    let mut ambiguous = false;
+
     // Iterate over all the unit headers.
     let mut iter = dwarf.units();
     while let Some(header) = iter.next()? {
         let unit = dwarf.unit(header)?;
 
-        // Iterate over the Debugging Information Entries (DIEs) in the unit header, usually the
-        // the first one is the compilation unit DIE.
-        let mut entries = unit.entries();
-        while let Some(entry) = entries.next_dfs()? {
-            if entry.tag() != gimli::DW_TAG_compile_unit {
-                continue;
-            }
+        let Some(mut paths) = compilation_unit_paths(dwarf, &unit)? else {
+            continue;
+        };
+        let remapped = paths
+            .iter()
+            .map(|p| path_map.remap(p).into_owned())
+            .collect::<Vec<_>>();
+        paths.extend(remapped);
+
+        if paths.iter().any(|p| path::same(p, name)) {
+            return Ok(Some(unit));
+        }
 
-            let name_attribute = get_attribute(entry, gimli::DW_AT_name)?;
-            let unit_name = dwarf.attr_string(&unit, name_attribute)?;
-            let unit_name = unit_name.to_string()?;
+        let suffix = paths
+            .iter()
+            .map(|p| path::common_suffix(p, name))
+            .max()
+            .unwrap_or_default();
+
+        if suffix > best_suffix {
+            best = Some(unit);
+            best_suffix = suffix;
+            ambiguous = false;
+        } else if suffix >= MIN_COMMON_SUFFIX && suffix == best_suffix {
+            ambiguous = true;
+        }
+    }
 
-            if name == unit_name {
-                return Ok(Some(unit));
-            }
+    // Picking the wrong unit would silently decode the arguments using the wrong type.
+    Ok(if ambiguous { None } else { best })
+}
+
+/// Returns the paths a compilation unit may be known by: its name, and its name joined to its
+/// compilation directory.
+/// Returns `Ok(None)` if the unit has no compilation unit DIE.
+fn compilation_unit_paths<R: Reader>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &Unit<R>,
+) -> Result<Option<Vec<String>>> {
+    // Iterate over the Debugging Information Entries (DIEs) in the unit header, usually the
+    // the first one is the compilation unit DIE.
+    let mut entries = unit.entries();
+    while let Some(entry) = entries.next_dfs()? {
+        if entry.tag() != gimli::DW_TAG_compile_unit {
+            continue;
         }
+
+        let name_attribute = get_attribute(entry, gimli::DW_AT_name)?;
+        let unit_name = dwarf.attr_string(unit, name_attribute)?;
+        let unit_name = unit_name.to_string()?.to_string();
+
+        let mut paths = vec![];
+        if let Some(dir_attribute) = entry.attr_value(gimli::DW_AT_comp_dir) {
+            let dir = dwarf.attr_string(unit, dir_attribute)?;
+            paths.push(path::join(&dir.to_string()?, &unit_name));
+        }
+        paths.insert(0, unit_name);
+
+        return Ok(Some(paths));
     }
 
     Ok(None)
@@ -297,7 +363,11 @@ fn find_type_die<R: Reader>(
     }
 }
 
-/// Parses the type whose description starts at the provided offset.
+/// Types nested deeper than this are rejected, as C types are never this deep.
+const MAX_TYPE_DEPTH: usize = 64;
+
+/// Parses the type whose description starts at the provided offset, `depth` is the number of
+/// types it's nested in.
 ///
 /// Output:
 /// * Returns `Ok` if the type DIE is successfully parsed.
@@ -306,7 +376,13 @@ fn parse_type<R: Reader>(
     dwarf: &gimli::Dwarf<R>,
     unit: &Unit<R>,
     start_offset: UnitOffset<R::Offset>,
+    depth: usize,
 ) -> Result<Type> {
+    // Malformed debugging information may contain cyclic references.
+    if depth > MAX_TYPE_DEPTH {
+        return Err(Error::TypeTooDeep);
+    }
+
     let mut entries = unit.entries_at_offset(start_offset)?;
 
     if let Some(entry) = entries.next_dfs()? {
@@ -319,7 +395,7 @@ fn parse_type<R: Reader>(
                 parse_ctx!(parse_base(&entry), "base type", dwarf, unit, &entry)
             }
             gimli::DW_TAG_enumeration_type => parse_ctx!(
-                parse_enumeration(dwarf, unit, entries),
+                parse_enumeration(dwarf, unit, entries, depth),
                 "enumeration",
                 dwarf,
                 unit,
@@ -329,14 +405,14 @@ fn parse_type<R: Reader>(
                 parse_ctx!(parse_pointer(&entry), "pointer type", dwarf, unit, &entry)
             }
             gimli::DW_TAG_structure_type => parse_ctx!(
-                parse_structure(dwarf, unit, entries),
+                parse_structure(dwarf, unit, entries, depth),
                 "structure",
                 dwarf,
                 unit,
                 &entry
             ),
             gimli::DW_TAG_array_type => parse_ctx!(
-                parse_array(dwarf, unit, entries),
+                parse_array(dwarf, unit, entries, depth),
                 "array",
                 dwarf,
                 unit,
@@ -359,24 +435,18 @@ fn parse_type<R: Reader>(
 
                 if let AttributeValue::UnitRef(unit_ref) = type_ref {
                     parse_ctx!(
-                        parse_type(dwarf, unit, unit_ref),
+                        parse_type(dwarf, unit, unit_ref, depth + 1),
                         ty_name,
                         dwarf,
                         unit,
                         &entry
                     )
                 } else {
-                    parse_ctx!(
-                        Err(Error::BadAttribute.into()),
-                        ty_name,
-                        dwarf,
-                        unit,
-                        &entry
-                    )
+                    parse_ctx!(Err(Error::BadAttribute), ty_name, dwarf, unit, &entry)
                 }
             }
             _ => parse_ctx!(
-                Err(Error::UnexpectedTag(tag).into()),
+                Err(Error::UnexpectedTag(tag)),
                 "unknown type",
                 dwarf,
                 unit,
@@ -384,7 +454,7 @@ fn parse_type<R: Reader>(
             ),
         }
     } else {
-        Err(Error::NoDIE(start_offset.0.into_u64()).into())
+        Err(Error::NoDIE(start_offset.0.into_u64()))
     }
 }
 
@@ -397,11 +467,12 @@ fn parse_enumeration<R: Reader>(
     dwarf: &gimli::Dwarf<R>,
     unit: &Unit<R>,
     mut entries: EntriesCursor<'_, R>,
+    depth: usize,
 ) -> Result<Type> {
     // Figure out the type of the storage used by the enum.
     // Unwrap safety: this function is called by `parse_type`, so the current entry must exist.
     let enum_entry = entries.current().unwrap();
-    let ty = parse_enumeration_storage(dwarf, unit, enum_entry)?;
+    let ty = parse_enumeration_storage(dwarf, unit, enum_entry, depth)?;
     let curr_depth = entries.depth();
 
     let mut valid_values = BTreeMap::default();
@@ -429,22 +500,17 @@ fn parse_enumeration<R: Reader>(
         let name = dwarf.attr_string(unit, name)?;
         let name = name.to_string()?;
 
+        let value = get_attribute(entry, gimli::DW_AT_const_value)?;
         let value = match ty {
-            Type::I8 | Type::I16 | Type::I32 | Type::I64 => entry
-                .attr_value(gimli::DW_AT_const_value)
-                .ok_or(crate::Error::NoAttribute(gimli::DW_AT_const_value))?
-                .sdata_value()
-                // Unwrap safety: DW_AT_const_value of enum whose underlying type is a signed
-                // integer must contain signed data.
-                .unwrap() as i128,
-            Type::U8 | Type::U16 | Type::U32 | Type::U64 => entry
-                .attr_value(gimli::DW_AT_const_value)
-                .ok_or(crate::Error::NoAttribute(gimli::DW_AT_const_value))?
-                .udata_value()
-                // Unwrap safety: DW_AT_const_value of enum whose underlying type is an unsigned
-                // integer must contain unsigned data.
-                .unwrap() as i128,
-            _ => unreachable!("C enums must have integer types!"),
+            // DW_AT_const_value of enum whose underlying type is a signed integer must contain
+            // signed data, and vice versa.
+            Type::I8 | Type::I16 | Type::I32 | Type::I64 => {
+                value.sdata_value().ok_or(Error::BadAttribute)? as i128
+            }
+            Type::U8 | Type::U16 | Type::U32 | Type::U64 => {
+                value.udata_value().ok_or(Error::BadAttribute)? as i128
+            }
+            _ => return Err(Error::NonIntegerEnum),
         };
 
         valid_values.insert(value, name.to_string());
@@ -471,11 +537,12 @@ fn parse_enumeration_storage<R: Reader>(
     dwarf: &gimli::Dwarf<R>,
     unit: &Unit<R>,
     entry: &DebuggingInformationEntry<R>,
+    depth: usize,
 ) -> Result<Type> {
     match entry.attr_value(gimli::DW_AT_type) {
         Some(AttributeValue::UnitRef(unit_offset)) => {
             parse_ctx!(
-                parse_type(dwarf, unit, unit_offset),
+                parse_type(dwarf, unit, unit_offset, depth + 1),
                 "enum type type",
                 dwarf,
                 unit,
@@ -499,13 +566,14 @@ fn parse_structure<R: Reader>(
     dwarf: &gimli::Dwarf<R>,
     unit: &Unit<R>,
     mut entries: EntriesCursor<R>,
+    depth: usize,
 ) -> Result<Type> {
     // Unwrap should be safe here.
     let struct_entry = entries.current().unwrap();
 
     // TODO: handle DW_AT_bit_size
     let size = get_attribute(struct_entry, gimli::DW_AT_byte_size)?;
-    let size = size.udata_value().unwrap() as usize;
+    let size = size.udata_value().ok_or(Error::BadAttribute)? as usize;
     let curr_depth = entries.depth();
 
     let mut members = vec![];
@@ -540,14 +608,14 @@ fn parse_structure<R: Reader>(
             // Get the type of the member.
             let ty = match get_attribute(member_entry, gimli::DW_AT_type)? {
                 AttributeValue::UnitRef(unit_offset) => parse_ctx!(
-                    parse_type(dwarf, unit, unit_offset),
+                    parse_type(dwarf, unit, unit_offset, depth + 1),
                     "structure member",
                     dwarf,
                     unit,
                     &member_entry
                 )?,
                 _ => {
-                    return Err(Error::BadAttribute.into());
+                    return Err(Error::BadAttribute);
                 }
             };
 
@@ -574,7 +642,7 @@ fn parse_array_dimension<R: Reader>(entry: &DebuggingInformationEntry<R>) -> Res
         if let Some(value) = value.udata_value() {
             return Ok(value);
         } else {
-            return Err(Error::BadAttribute.into());
+            return Err(Error::BadAttribute);
         }
     }
 
@@ -595,7 +663,10 @@ fn parse_array_dimension<R: Reader>(entry: &DebuggingInformationEntry<R>) -> Res
         return Ok(0);
     }
 
-    Ok(1 + upper_bound - lower_bound)
+    upper_bound
+        .checked_sub(lower_bound)
+        .and_then(|length| length.checked_add(1))
+        .ok_or(Error::BadAttribute)
 }
 
 /// Parses the array type whose DIE is pointed to by the entries cursor.
@@ -607,13 +678,14 @@ fn parse_array<R: Reader>(
     dwarf: &gimli::Dwarf<R>,
     unit: &Unit<R>,
     mut entries: EntriesCursor<'_, R>,
+    depth: usize,
 ) -> Result<Type> {
     // Unwrap safety: this function is called by `parse_type`, so the current entry must exist.
     let array_entry = entries.current().unwrap();
     let ty = match array_entry.attr_value(gimli::DW_AT_type) {
         Some(AttributeValue::UnitRef(unit_offset)) => {
             parse_ctx!(
-                parse_type(dwarf, unit, unit_offset),
+                parse_type(dwarf, unit, unit_offset, depth + 1),
                 "array type",
                 dwarf,
                 unit,
@@ -700,10 +772,10 @@ fn parse_base<R: Reader>(entry: &DebuggingInformationEntry<R>) -> Result<Type> {
                 (8, gimli::DW_ATE_signed) => Ok(Type::I64),
                 (4, gimli::DW_ATE_float) => Ok(Type::F32),
                 (8, gimli::DW_ATE_float) => Ok(Type::F64),
-                _ => Err(Error::UnsupportedBaseType(encoding, byte_size).into()),
+                _ => Err(Error::UnsupportedBaseType(encoding, byte_size)),
             }
         }
-        _ => Err(Error::BadAttribute.into()),
+        _ => Err(Error::BadAttribute),
     }
 }
 
@@ -721,9 +793,9 @@ fn parse_pointer<R: Reader>(entry: &DebuggingInformationEntry<R>) -> Result<Type
             2 => Type::U16,
             4 => Type::U32,
             8 => Type::U64,
-            _ => return Err(Error::UnsupportedPointerSize(byte_size).into()),
+            _ => return Err(Error::UnsupportedPointerSize(byte_size)),
         })))
     } else {
-        Err(Error::BadAttribute.into())
+        Err(Error::BadAttribute)
     }
 }
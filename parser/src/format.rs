@@ -0,0 +1,297 @@
+//! Parsing of the logs' format strings.
+//!
+//! The decoder formats logs at runtime, so mistakes in a format string are only found once a log
+//! is received.
+//! Parsing the format strings up front allows checking them against the logs' arguments before
+//! the elf is deployed, see [`Parser::check`](crate::Parser::check).
+
+use std::fmt;
+
+use crate::r#type::Type;
+
+/// Which argument a placeholder refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Argument<'a> {
+    /// `{}`, the argument following the one used by the previous `{}`.
+    Next,
+    /// `{2}`
+    Index(usize),
+    /// `{some_struct.b}`, the expression that was passed to the log macro.
+    Name(&'a str),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sign {
+    Plus,
+    Minus,
+}
+
+/// The part of a placeholder following the `:`, using the same syntax as Rust's `format!`:
+/// `[[fill]align][sign][#][0][width][.precision][type]`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Spec<'a> {
+    pub fill: Option<char>,
+    pub align: Option<Align>,
+    pub sign: Option<Sign>,
+    pub alternate: bool,
+    pub zero: bool,
+    pub width: Option<usize>,
+    pub precision: Option<usize>,
+    /// The formatting trait, for example `x` or `?`, empty for `Display`.
+    pub ty: &'a str,
+}
+
+/// A single `{...}` in a format string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Placeholder<'a> {
+    /// Byte offset of the opening `{` in the format string.
+    pub offset: usize,
+    pub argument: Argument<'a>,
+    pub spec: Spec<'a>,
+}
+
+/// The formatting trait selected by a spec's type, see [`Spec::ty`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FormatTrait {
+    /// No type.
+    Display,
+    /// `?`, `x?` and `X?`.
+    Debug,
+    /// `b`
+    Binary,
+    /// `o`
+    Octal,
+    /// `x`
+    LowerHex,
+    /// `X`
+    UpperHex,
+    /// `e`
+    LowerExp,
+    /// `E`
+    UpperExp,
+    /// `p`
+    Pointer,
+    /// `s`, formats characters and character arrays as a string.
+    String,
+}
+
+/// The kind of a value, as far as formatting it is concerned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Bool,
+    /// 8 bit integers, which can also be formatted as characters.
+    Char,
+    Integer,
+    Float,
+    Enumeration,
+    Pointer,
+    Structure,
+    Array,
+}
+
+impl From<&Type> for Kind {
+    fn from(ty: &Type) -> Self {
+        match ty {
+            Type::Bool => Kind::Bool,
+            Type::U8 | Type::I8 => Kind::Char,
+            Type::U16 | Type::U32 | Type::U64 | Type::I16 | Type::I32 | Type::I64 => Kind::Integer,
+            Type::F32 | Type::F64 => Kind::Float,
+            Type::Enumeration { .. } => Kind::Enumeration,
+            Type::Pointer(_) => Kind::Pointer,
+            Type::Structure { .. } => Kind::Structure,
+            Type::Array { .. } => Kind::Array,
+        }
+    }
+}
+
+impl FormatTrait {
+    /// Returns the trait selected by a spec's type, `None` if it's unknown.
+    pub fn from_spec(ty: &str) -> Option<Self> {
+        Some(match ty {
+            "" => FormatTrait::Display,
+            "?" | "x?" | "X?" => FormatTrait::Debug,
+            "b" => FormatTrait::Binary,
+            "o" => FormatTrait::Octal,
+            "x" => FormatTrait::LowerHex,
+            "X" => FormatTrait::UpperHex,
+            "e" => FormatTrait::LowerExp,
+            "E" => FormatTrait::UpperExp,
+            "p" => FormatTrait::Pointer,
+            "s" => FormatTrait::String,
+            _ => return None,
+        })
+    }
+
+    /// Returns whether values of the given kind can be formatted using the trait.
+    ///
+    /// This is the table the decoder's values implement the traits by. Structures and arrays are
+    /// formatted element by element, so their elements have to be supported as well.
+    pub fn supports(self, kind: Kind) -> bool {
+        use Kind::*;
+
+        match self {
+            FormatTrait::Display | FormatTrait::Debug => true,
+            FormatTrait::Binary
+            | FormatTrait::Octal
+            | FormatTrait::LowerHex
+            | FormatTrait::UpperHex => !matches!(kind, Bool | Float),
+            FormatTrait::LowerExp | FormatTrait::UpperExp => {
+                !matches!(kind, Bool | Enumeration | Pointer)
+            }
+            FormatTrait::Pointer => !matches!(kind, Bool | Float | Enumeration),
+            FormatTrait::String => matches!(kind, Char | Array),
+        }
+    }
+}
+
+/// A malformed format string, `offset` is the byte offset of the offending placeholder.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at offset {})", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+/// Returns all of the placeholders in `fmt`, in order of appearance.
+///
+/// `{{` and `}}` are escaped braces, not placeholders.
+pub fn placeholders(fmt: &str) -> Result<Vec<Placeholder<'_>>, FormatError> {
+    let mut placeholders = vec![];
+    let mut rest = fmt;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        let offset = fmt.len() - rest.len() + start;
+        let brace = rest.as_bytes()[start];
+        let after = &rest[start + 1..];
+
+        if after.as_bytes().first() == Some(&brace) {
+            rest = &after[1..];
+            continue;
+        }
+
+        if brace == b'}' {
+            return Err(FormatError {
+                offset,
+                message: "Unmatched '}', use '}}' to print a brace".to_string(),
+            });
+        }
+
+        let end = after.find('}').ok_or_else(|| FormatError {
+            offset,
+            message: "Unterminated placeholder, use '{{' to print a brace".to_string(),
+        })?;
+
+        placeholders.push(parse_placeholder(&after[..end], offset)?);
+        rest = &after[end + 1..];
+    }
+
+    Ok(placeholders)
+}
+
+fn parse_placeholder(inner: &str, offset: usize) -> Result<Placeholder<'_>, FormatError> {
+    let error = |message: String| FormatError { offset, message };
+
+    let (argument, spec) = inner.split_once(':').unwrap_or((inner, ""));
+    let argument = argument.trim();
+
+    let argument = if argument.is_empty() {
+        Argument::Next
+    } else if let Ok(index) = argument.parse() {
+        Argument::Index(index)
+    } else if argument.contains(['{', ' ']) {
+        return Err(error(format!("Invalid argument '{argument}'")));
+    } else {
+        Argument::Name(argument)
+    };
+
+    Ok(Placeholder {
+        offset,
+        argument,
+        spec: parse_spec(spec).map_err(error)?,
+    })
+}
+
+fn parse_spec(mut spec: &str) -> Result<Spec<'_>, String> {
+    let original = spec;
+    let mut result = Spec::default();
+
+    let align = |c| match c {
+        '<' => Some(Align::Left),
+        '^' => Some(Align::Center),
+        '>' => Some(Align::Right),
+        _ => None,
+    };
+
+    let mut chars = spec.chars();
+    let first = chars.next();
+    let second = chars.next();
+    if let Some(a) = second.and_then(align) {
+        result.fill = first;
+        result.align = Some(a);
+        spec = &spec[first.map_or(0, char::len_utf8) + 1..];
+    } else if let Some(a) = first.and_then(align) {
+        result.align = Some(a);
+        spec = &spec[1..];
+    }
+
+    if let Some(rest) = spec.strip_prefix('+') {
+        result.sign = Some(Sign::Plus);
+        spec = rest;
+    } else if let Some(rest) = spec.strip_prefix('-') {
+        result.sign = Some(Sign::Minus);
+        spec = rest;
+    }
+
+    if let Some(rest) = spec.strip_prefix('#') {
+        result.alternate = true;
+        spec = rest;
+    }
+
+    if let Some(rest) = spec.strip_prefix('0') {
+        result.zero = true;
+        spec = rest;
+    }
+
+    let (width, rest) = split_number(spec);
+    result.width = width;
+    spec = rest;
+
+    if let Some(rest) = spec.strip_prefix('.') {
+        let (precision, rest) = split_number(rest);
+        if precision.is_none() {
+            return Err(format!("Missing precision in '{original}'"));
+        }
+        result.precision = precision;
+        spec = rest;
+    }
+
+    if !spec
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '?' || c == '_')
+    {
+        return Err(format!("Invalid format spec '{original}'"));
+    }
+    result.ty = spec;
+
+    Ok(result)
+}
+
+/// Splits a leading decimal number from `s`.
+fn split_number(s: &str) -> (Option<usize>, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    (s[..end].parse().ok(), &s[end..])
+}
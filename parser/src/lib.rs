@@ -2,10 +2,17 @@ use gimli::{DwAte, DwTag, SectionId};
 
 mod dwarf;
 
+pub mod catalog;
+pub mod check;
+pub mod diff;
+pub mod dissector;
+pub mod format;
 pub mod metadata;
 pub mod parser;
+pub mod path;
 pub mod r#type;
 
+pub use dwarf::SourceLocation;
 pub use parser::Parser;
 
 #[derive(Debug, thiserror::Error)]
@@ -42,8 +49,38 @@ pub enum Error {
     NoDIE(u64),
     #[error("Unsupported schema version: {0}")]
     SchemaVersion(u32),
-    #[error("{0}")]
-    Custom(&'static str),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Formatting error: {0}")]
+    Fmt(#[from] std::fmt::Error),
+    #[error("The log id [{0}] doesn't match any log")]
+    UnknownLogId(usize),
+    /// A failure parsing the debugging information, in the DIE described by `item` and `name`.
+    #[error("{}: in {item} `{name}`: {source}", location.as_ref().map_or("<unknown>".to_string(), |l| l.to_string()))]
+    Dwarf {
+        item: String,
+        name: String,
+        location: Option<SourceLocation>,
+        source: Box<Error>,
+    },
+    #[error("Unable to find build ID in elf!")]
+    MissingBuildId,
+    #[error("Unsupported architecture, no address size information!")]
+    UnsupportedArchitecture,
+    #[error("Type is nested too deeply!")]
+    TypeTooDeep,
+    #[error("C enums must have integer types!")]
+    NonIntegerEnum,
+    #[error("Unknown level '{0}', expected one of: error, warning, info, debug, verbose")]
+    UnknownLevel(String),
+    #[error("Invalid log level: {0}")]
+    InvalidLevel(u8),
+    #[error("The log at id [{0}] has an empty string, with no null terminator")]
+    EmptyString(usize),
+    #[error(
+        "The dissector's link type must be one of LINKTYPE_USER0 to LINKTYPE_USER15 (147-162), not {0}"
+    )]
+    InvalidLinkType(u16),
 }
 
-pub type Result<T> = std::result::Result<T, anyhow::Error>;
+pub type Result<T> = std::result::Result<T, Error>;
@@ -1,12 +1,18 @@
 //! Representation of log metadata extracted from the target elf's .cdefmt section.
 
 use core::{fmt, str};
+use std::borrow::Cow;
 
 use gimli::{EndianSlice, Reader, RunTimeEndian};
 
 use crate::{Error, Result};
 
-#[derive(Clone, Copy, Debug)]
+/// The metadata schema version supported by this parser.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 #[repr(u8)]
 pub enum Level {
     Error = 0,
@@ -22,12 +28,49 @@ impl fmt::Display for Level {
     }
 }
 
+impl str::FromStr for Level {
+    type Err = Error;
+
+    /// Parses a level name, case insensitive, either in full (`warning`) or in the 3 letter form
+    /// used by the C macros (`wrn`).
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "error" | "err" => Ok(Level::Error),
+            "warning" | "wrn" | "warn" => Ok(Level::Warning),
+            "info" | "inf" => Ok(Level::Info),
+            "debug" | "dbg" => Ok(Level::Debug),
+            "verbose" | "vrb" => Ok(Level::Verbose),
+            _ => Err(Error::UnknownLevel(s.to_string())),
+        }
+    }
+}
+
+impl TryFrom<u8> for Level {
+    type Error = Error;
+
+    fn try_from(level: u8) -> std::result::Result<Self, Error> {
+        match level {
+            0 => Ok(Level::Error),
+            1 => Ok(Level::Warning),
+            2 => Ok(Level::Info),
+            3 => Ok(Level::Debug),
+            4 => Ok(Level::Verbose),
+            _ => Err(Error::InvalidLevel(level)),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Metadata<'elf> {
     pub id: usize,
     pub counter: u32,
     pub line: usize,
-    pub file: &'elf str,
+    /// The source file containing the log, after applying the parser's
+    /// [`PathMap`](crate::path::PathMap).
+    ///
+    /// Borrowed from the elf, unless a rule rewrote it.
+    pub file: Cow<'elf, str>,
     pub fmt: &'elf str,
     pub names: Vec<&'elf str>,
     pub level: Level,
@@ -42,8 +85,8 @@ fn parse_metadata_impl<'elf>(
     let version = endian_slice.read_u32()?;
     offset += 4;
 
-    if version != 1 {
-        return Err(Error::SchemaVersion(version).into());
+    if version != SCHEMA_VERSION {
+        return Err(Error::SchemaVersion(version));
     }
 
     let counter = endian_slice.read_u32()?;
@@ -55,24 +98,22 @@ fn parse_metadata_impl<'elf>(
     let names_len = endian_slice.read_u32()? as usize;
     offset += 4 * 3;
 
-    let level = endian_slice.read_u8()?;
+    let level = Level::try_from(endian_slice.read_u8()?)?;
     offset += 1;
 
-    let file = endian_slice.split(file_len)?;
-    let file = str::from_utf8(&file.slice()[..file_len - 1]).map_err(|e| Error::Utf8(id, e))?;
+    let file = read_str(id, endian_slice, file_len)?;
     offset += file_len;
 
-    let fmt = endian_slice.split(fmt_len)?;
-    let fmt = str::from_utf8(&fmt.slice()[..fmt_len - 1]).map_err(|e| Error::Utf8(id, e))?;
+    let fmt = read_str(id, endian_slice, fmt_len)?;
     offset += fmt_len;
 
     let names = (0..names_len)
         .map(|_| {
             let name_len = endian_slice.read_u32()? as usize;
             offset += 4;
-            let name = endian_slice.split(name_len)?;
+            let name = read_str(id, endian_slice, name_len)?;
             offset += name_len;
-            str::from_utf8(&name.slice()[..name_len - 1]).map_err(|e| Error::Utf8(id, e).into())
+            Ok(name)
         })
         .collect::<Result<Vec<_>>>()?;
 
@@ -81,15 +122,26 @@ fn parse_metadata_impl<'elf>(
             id,
             counter,
             line,
-            file,
+            file: Cow::Borrowed(file),
             fmt,
             names,
-            level: unsafe { std::mem::transmute::<u8, Level>(level) },
+            level,
         },
         offset,
     ))
 }
 
+/// Reads a string of `len` bytes, including its null terminator.
+fn read_str<'elf>(
+    id: usize,
+    endian_slice: &mut EndianSlice<'elf, RunTimeEndian>,
+    len: usize,
+) -> Result<&'elf str> {
+    let bytes = endian_slice.split(len)?.slice();
+    let (_, bytes) = bytes.split_last().ok_or(Error::EmptyString(id))?;
+    str::from_utf8(bytes).map_err(|e| Error::Utf8(id, e))
+}
+
 pub(crate) fn parse_metadata(
     cdefmt_section: &[u8],
     id: usize,
@@ -1,11 +1,14 @@
 //! Contains logic related to finding logs in the elf and parsing them.
 
+use std::borrow::Cow;
+
 use object::{AddressSize, Object, ObjectSection, ObjectSymbol, ReadRef};
 
 use crate::{
     Error, Result,
     dwarf::Dwarf,
     metadata::{Metadata, parse_metadata},
+    path::PathMap,
     r#type::Type,
 };
 
@@ -15,7 +18,8 @@ pub struct Parser<'elf> {
     build_id: &'elf [u8],
     dwarf: Dwarf<'elf>,
     address_size: AddressSize,
-    metadata_addresses: Vec<u64>,
+    pub(crate) metadata_addresses: Vec<u64>,
+    path_map: PathMap,
 }
 
 impl<'elf> Parser<'elf> {
@@ -23,19 +27,20 @@ impl<'elf> Parser<'elf> {
     pub fn new<R: ReadRef<'elf>>(data: R) -> Result<Self> {
         let file = object::File::parse(data)?;
         let dwarf = Dwarf::new(&file)?;
-        let build_id = file
-            .build_id()?
-            .ok_or(Error::Custom("Unable to find build ID in elf!"))?;
+        let build_id = file.build_id()?.ok_or(Error::MissingBuildId)?;
 
-        let address_size = file.architecture().address_size().ok_or(Error::Custom(
-            "Unsupported architecture, no address size information!",
-        ))?;
+        let address_size = file
+            .architecture()
+            .address_size()
+            .ok_or(Error::UnsupportedArchitecture)?;
 
-        let metadata_addresses = file
+        let mut metadata_addresses = file
             .symbols()
             .filter(|s| s.name().is_ok_and(|n| n.contains("cdefmt_log_metadata")))
             .map(|s| s.address())
             .collect::<Vec<_>>();
+        metadata_addresses.sort_unstable();
+        metadata_addresses.dedup();
 
         Ok(Parser {
             logs_section: file
@@ -46,12 +51,38 @@ impl<'elf> Parser<'elf> {
             dwarf,
             address_size,
             metadata_addresses,
+            path_map: Default::default(),
         })
     }
 
+    /// Sets the path prefix remapping applied to the source file of every log.
+    ///
+    /// The remapped paths are also used to find the compilation units containing the logs'
+    /// argument types, allowing elfs built with `-fdebug-prefix-map` to be parsed.
+    pub fn set_path_map(&mut self, path_map: PathMap) {
+        self.path_map = path_map;
+    }
+
+    pub fn path_map(&self) -> &PathMap {
+        &self.path_map
+    }
+
     /// Returns a specific log's metadata.
+    ///
+    /// Fails with [`Error::UnknownLogId`] if the elf's symbols don't have a log at `id`, elfs without
+    /// the metadata symbols are parsed at any id.
     pub fn get_log_metadata(&self, id: usize) -> Result<Metadata<'elf>> {
-        parse_metadata(self.logs_section, id, self.endian())
+        if !self.metadata_addresses.is_empty()
+            && self.metadata_addresses.binary_search(&(id as u64)).is_err()
+        {
+            return Err(Error::UnknownLogId(id));
+        }
+
+        let mut metadata = parse_metadata(self.logs_section, id, self.endian())?;
+        if let Cow::Borrowed(file) = metadata.file {
+            metadata.file = self.path_map.remap(file);
+        }
+        Ok(metadata)
     }
 
     /// Returns an iterator over all of the log's metadata/type information.
@@ -62,6 +93,29 @@ impl<'elf> Parser<'elf> {
         }
     }
 
+    /// Returns the id of the log closest to `id`, `None` if the elf has no metadata symbols.
+    ///
+    /// When two logs are as close, the lower one is returned.
+    pub fn nearest_log_id(&self, id: usize) -> Option<usize> {
+        let id = id as u64;
+        let after = self
+            .metadata_addresses
+            .partition_point(|&address| address < id);
+        let before = after.checked_sub(1).map(|i| self.metadata_addresses[i]);
+        let after = self.metadata_addresses.get(after).copied();
+
+        match (before, after) {
+            (Some(before), Some(after)) if after - id < id - before => Some(after as usize),
+            (Some(before), _) => Some(before as usize),
+            (None, after) => after.map(|after| after as usize),
+        }
+    }
+
+    /// Returns the number of logs in the elf.
+    pub fn log_count(&self) -> usize {
+        self.metadata_addresses.len()
+    }
+
     /// Returns the type of the log's arguments.
     /// Return:
     /// * Ok(Some(_)) => The type of the arguments.
@@ -69,7 +123,8 @@ impl<'elf> Parser<'elf> {
     /// * Err(_)      => Encountered some error while parsing the dwarf.
     pub fn get_log_args_type(&self, metadata: &Metadata) -> Result<Option<Type>> {
         let type_name = format!("cdefmt_log_args_t{}", metadata.counter);
-        self.dwarf.get_type(metadata.file, &type_name)
+        self.dwarf
+            .get_type(&metadata.file, &type_name, &self.path_map)
     }
 
     pub fn build_id(&self) -> &'elf [u8] {
@@ -0,0 +1,99 @@
+//! Source file path handling.
+//!
+//! The paths embedded in the elf are the ones seen by the compiler on the build machine, which
+//! rarely exist on the machine decoding the logs.
+
+use std::borrow::Cow;
+
+/// Rewrites path prefixes, similar to gcc's `-fdebug-prefix-map`.
+///
+/// Prefixes are matched by whole path components, so `/build` matches `/build/main.c` but not
+/// `/buildroot/main.c`.
+/// If multiple rules match a path the last one added wins.
+#[derive(Clone, Debug, Default)]
+pub struct PathMap {
+    rules: Vec<(String, String)>,
+}
+
+impl PathMap {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Replaces the prefix `from` with `to`.
+    pub fn add(&mut self, from: impl Into<String>, to: impl Into<String>) {
+        self.rules.push((from.into(), to.into()));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Applies the first matching rule, searching from the last one added, to `path`.
+    pub fn remap<'a>(&self, path: &'a str) -> Cow<'a, str> {
+        self.rules
+            .iter()
+            .rev()
+            .find_map(|(from, to)| strip_prefix(path, from).map(|rest| (to, rest)))
+            .map_or(Cow::Borrowed(path), |(to, rest)| {
+                Cow::Owned(format!("{to}{rest}"))
+            })
+    }
+}
+
+/// Removes `prefix` from `path`, if it consists of whole components of `path`.
+///
+/// Both `/` and `\` are treated as separators, like [`components`] does.
+fn strip_prefix<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    let rest = path.strip_prefix(prefix)?;
+    let whole = prefix.is_empty()
+        || prefix.ends_with(['/', '\\'])
+        || rest.is_empty()
+        || rest.starts_with(['/', '\\']);
+    whole.then_some(rest)
+}
+
+/// Splits a path into its components, resolving `.` and `..` lexically.
+///
+/// Both `/` and `\` are treated as separators, the elf might have been built on another platform.
+fn components(path: &str) -> Vec<&str> {
+    let mut components = vec![];
+
+    for component in path.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." if components.last().is_some_and(|c| *c != "..") => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+
+    components
+}
+
+/// Number of trailing path components shared by both paths, for example `src/main.c` and
+/// `/build/project/src/main.c` share 2.
+pub(crate) fn common_suffix(a: &str, b: &str) -> usize {
+    components(a)
+        .iter()
+        .rev()
+        .zip(components(b).iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+/// Joins a relative `path` to the directory `dir`, absolute paths are returned as is.
+pub(crate) fn join(dir: &str, path: &str) -> String {
+    let absolute = path.starts_with(['/', '\\']) || path.as_bytes().get(1) == Some(&b':');
+    if absolute {
+        path.to_string()
+    } else {
+        format!("{}/{path}", dir.trim_end_matches(['/', '\\']))
+    }
+}
+
+/// Whether both paths point to the same file, after resolving `.` and `..`.
+pub(crate) fn same(a: &str, b: &str) -> bool {
+    components(a) == components(b) && a.starts_with(['/', '\\']) == b.starts_with(['/', '\\'])
+}
@@ -3,6 +3,8 @@ use std::collections::BTreeMap;
 // TODO: support booleans larger than 1 byte?
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Type {
     Bool,
     U8,
@@ -32,6 +34,7 @@ pub enum Type {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct StructureMember {
     pub offset: u64,
     pub name: String,
@@ -60,8 +63,111 @@ impl Type {
                     return 0;
                 }
 
-                ty.size() * (lengths.iter().product::<u64>() as usize)
+                // Saturating, as the lengths come from the elf and may be malformed.
+                let count = lengths
+                    .iter()
+                    .fold(1u64, |count, l| count.saturating_mul(*l));
+                ty.size()
+                    .saturating_mul(count.try_into().unwrap_or(usize::MAX))
             }
         }
     }
+
+    /// Returns the types of a log's arguments, given the type of the log's arguments structure.
+    ///
+    /// Dynamic arrays are returned as an array with no length, their length is only known once the
+    /// log is received.
+    pub fn log_arguments(&self) -> Option<Vec<Type>> {
+        let Type::Structure { members, .. } = self else {
+            return None;
+        };
+
+        Some(
+            members
+                .iter()
+                // Skip the log id.
+                .skip(1)
+                .filter(|m| m.name != "dynamic_data")
+                .map(|m| match m.dynamic_array() {
+                    Some(ty) => Type::Array {
+                        ty: Box::new(ty.clone()),
+                        lengths: vec![],
+                    },
+                    None => m.ty.clone(),
+                })
+                .collect(),
+        )
+    }
+
+    /// Renders the type as a C type name, for example `uint32_t` or `uint8_t[5]`.
+    ///
+    /// The debugging information only describes the types' layout, so typedef, structure and
+    /// enumeration names are lost, as is the type pointed to by pointers.
+    pub fn c_name(&self) -> String {
+        self.c_declaration("")
+    }
+
+    /// Renders a C declaration of `declarator` with this type, for example `uint8_t some_array[5]`.
+    pub fn c_declaration(&self, declarator: &str) -> String {
+        let base = match self {
+            Type::Bool => "bool".to_string(),
+            Type::U8 => "uint8_t".to_string(),
+            Type::U16 => "uint16_t".to_string(),
+            Type::U32 => "uint32_t".to_string(),
+            Type::U64 => "uint64_t".to_string(),
+            Type::I8 => "int8_t".to_string(),
+            Type::I16 => "int16_t".to_string(),
+            Type::I32 => "int32_t".to_string(),
+            Type::I64 => "int64_t".to_string(),
+            Type::F32 => "float".to_string(),
+            Type::F64 => "double".to_string(),
+            Type::Enumeration { valid_values, .. } => {
+                let values = valid_values
+                    .iter()
+                    .map(|(value, name)| format!("{name} = {value}"))
+                    .collect::<Vec<_>>();
+                format!("enum {{ {} }}", values.join(", "))
+            }
+            Type::Structure { members, .. } => {
+                let members = members
+                    .iter()
+                    .map(|m| format!("{}; ", m.ty.c_declaration(&m.name)))
+                    .collect::<String>();
+                format!("struct {{ {members}}}")
+            }
+            Type::Pointer(_) => "void*".to_string(),
+            Type::Array { ty, lengths } => {
+                let dimensions = if lengths.is_empty() {
+                    "[]".to_string()
+                } else {
+                    lengths.iter().map(|l| format!("[{l}]")).collect()
+                };
+                return ty.c_declaration(&format!("{declarator}{dimensions}"));
+            }
+        };
+
+        if declarator.is_empty() || declarator.starts_with('[') {
+            format!("{base}{declarator}")
+        } else {
+            format!("{base} {declarator}")
+        }
+    }
+}
+
+impl StructureMember {
+    /// Returns the element type of a dynamic array argument, which is a `{ size, type[0] }`
+    /// structure.
+    pub(crate) fn dynamic_array(&self) -> Option<&Type> {
+        if !self.name.contains("dynamic_array") {
+            return None;
+        }
+
+        match &self.ty {
+            Type::Structure { members, .. } => match members.get(1).map(|m| &m.ty) {
+                Some(Type::Array { ty, .. }) => Some(ty),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,98 @@
+//! Collecting the catalog of an elf's logs, and writing it as CSV and Markdown.
+
+use cdefmt_encoder::{
+    Encoding,
+    elf::{self, ArgumentType, LogSpec},
+};
+use cdefmt_parser::{Parser, catalog, metadata::Level, r#type::Type};
+
+/// Logs whose strings need escaping.
+const LOGS: [LogSpec; 3] = [
+    (
+        Level::Info,
+        "src/main.c",
+        10,
+        "temperature: {}, ok: {}",
+        &[
+            ("sensor->temp", ArgumentType::Value(Type::U32)),
+            ("ok", ArgumentType::Value(Type::Bool)),
+        ],
+    ),
+    (
+        Level::Warning,
+        "src/a,b.c",
+        20,
+        "said \"hi\" | {}\n",
+        &[("buf", ArgumentType::DynamicArray(Type::U8))],
+    ),
+    (Level::Debug, "src/main.c", 5, "no `args`", &[]),
+];
+
+#[test]
+fn collect() {
+    let (elf, ids) = elf::build(Encoding::NATIVE, &LOGS).unwrap();
+    let [info, warning, debug] = ids.try_into().unwrap();
+    let parser = Parser::new(elf.as_slice()).unwrap();
+    let entries = catalog::collect(&parser).unwrap();
+
+    // Sorted by id, starting with the init log.
+    let ids = entries.iter().map(|e| e.id as u64).collect::<Vec<_>>();
+    assert_eq!(ids, [0, info, warning, debug]);
+
+    let entry = &entries[1];
+    assert_eq!(entry.level, Level::Info);
+    assert_eq!(entry.file, "src/main.c");
+    assert_eq!(entry.line, 10);
+    assert_eq!(entry.fmt, "temperature: {}, ok: {}");
+    let arguments = entry
+        .arguments
+        .iter()
+        .map(|a| (a.name, a.c_type.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        arguments,
+        [("sensor->temp", Some("uint32_t")), ("ok", Some("bool")),]
+    );
+    assert_eq!(entry.parameters(), "uint32_t sensor->temp, bool ok");
+
+    // Dynamic arrays are described as arrays of unknown length.
+    assert_eq!(entries[2].arguments[0].c_type.as_deref(), Some("uint8_t[]"));
+    assert_eq!(entries[2].parameters(), "uint8_t buf[]");
+
+    assert!(entries[3].arguments.is_empty());
+    assert_eq!(entries[3].parameters(), "");
+}
+
+#[test]
+fn write() {
+    let (elf, ids) = elf::build(Encoding::NATIVE, &LOGS).unwrap();
+    let [info, warning, debug] = ids.try_into().unwrap();
+    let parser = Parser::new(elf.as_slice()).unwrap();
+    let entries = catalog::collect(&parser).unwrap();
+    let entries = &entries[1..];
+
+    let mut csv = vec![];
+    catalog::write_csv(entries, &mut csv).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        format!(
+            "id,level,file,line,fmt,arguments\n\
+             {info:#x},Info,src/main.c,10,\"temperature: {{}}, ok: {{}}\",\"uint32_t sensor->temp, bool ok\"\n\
+             {warning:#x},Warning,\"src/a,b.c\",20,\"said \"\"hi\"\" | {{}}\n\",uint8_t buf[]\n\
+             {debug:#x},Debug,src/main.c,5,no `args`,\n"
+        )
+    );
+
+    let mut markdown = vec![];
+    catalog::write_markdown(entries, &mut markdown).unwrap();
+    assert_eq!(
+        String::from_utf8(markdown).unwrap(),
+        format!(
+            "| ID | Level | Location | Format | Arguments |\n\
+             |----|-------|----------|--------|-----------|\n\
+             | {info:#x} | Info | src/main.c:10 | ` temperature: {{}}, ok: {{}} ` | ` uint32_t sensor->temp, bool ok ` |\n\
+             | {warning:#x} | Warning | src/a,b.c:20 | ` said \"hi\" \\| {{}}\\n ` | ` uint8_t buf[] ` |\n\
+             | {debug:#x} | Debug | src/main.c:5 | `` no `args` `` |  |\n"
+        )
+    );
+}
@@ -0,0 +1,172 @@
+//! Checking format strings against the types of the logs' arguments.
+
+use std::collections::BTreeMap;
+
+use cdefmt_encoder::{
+    Encoding,
+    elf::{self, ArgumentType},
+};
+use cdefmt_parser::{
+    Parser,
+    metadata::Level,
+    r#type::{StructureMember, Type},
+};
+
+/// Checks a single log, returning the messages of the problems found.
+fn check(fmt: &str, args: &[(&str, Type)]) -> Vec<String> {
+    let args = args
+        .iter()
+        .map(|(name, ty)| (*name, ArgumentType::Value(ty.clone())))
+        .collect::<Vec<_>>();
+
+    let (elf, _) =
+        elf::build(Encoding::NATIVE, &[(Level::Info, "check.c", 1, fmt, &args)]).unwrap();
+
+    Parser::new(elf.as_slice())
+        .unwrap()
+        .check()
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect()
+}
+
+fn structure(members: &[(&str, Type)]) -> Type {
+    let mut offset = 0;
+    let members = members
+        .iter()
+        .map(|(name, ty)| {
+            let member = StructureMember {
+                offset,
+                name: name.to_string(),
+                ty: ty.clone(),
+            };
+            offset += ty.size() as u64;
+            member
+        })
+        .collect();
+
+    Type::Structure {
+        members,
+        size: offset as usize,
+    }
+}
+
+fn array(ty: Type, length: u64) -> Type {
+    Type::Array {
+        ty: Box::new(ty),
+        lengths: vec![length],
+    }
+}
+
+#[test]
+fn good_specs() {
+    let enumeration = Type::Enumeration {
+        ty: Box::new(Type::I32),
+        valid_values: BTreeMap::from([(1, "ONE".to_string())]),
+    };
+    let point = structure(&[("x", Type::I32), ("y", Type::I32)]);
+
+    let good = [
+        ("{} {:?}", vec![("a", Type::Bool), ("b", Type::F64)]),
+        ("{:#010x} {:b}", vec![("a", Type::U32), ("b", Type::I8)]),
+        ("{:X} {:o}", vec![("a", enumeration), ("b", Type::U64)]),
+        ("{:.3e} {:E}", vec![("a", Type::F32), ("b", Type::I64)]),
+        ("{:x}", vec![("point", point)]),
+        (
+            "{:s} {:s}",
+            vec![("name", array(Type::I8, 8)), ("c", Type::U8)],
+        ),
+        ("{name:>8} {0}", vec![("name", Type::U16)]),
+    ];
+
+    for (fmt, args) in good {
+        assert_eq!(check(fmt, &args), Vec::<String>::new(), "{fmt}");
+    }
+}
+
+#[test]
+fn bad_specs() {
+    let with_float = structure(&[("count", Type::U32), ("ratio", Type::F32)]);
+
+    let bad = [
+        (
+            "{:x}",
+            vec![("a", Type::F64)],
+            "'x' isn't supported for f64 values",
+        ),
+        (
+            "{:b}",
+            vec![("a", Type::Bool)],
+            "'b' isn't supported for bool values",
+        ),
+        // Structures are formatted member by member.
+        (
+            "{:o}",
+            vec![("a", with_float)],
+            "'o' isn't supported for f32 values",
+        ),
+        (
+            "{:e}",
+            vec![("a", Type::Pointer(Box::new(Type::U64)))],
+            "'e' isn't supported for pointer values",
+        ),
+        (
+            "{:s}",
+            vec![("a", Type::U32)],
+            "'s' requires a char array or a char",
+        ),
+        ("{:y}", vec![("a", Type::U32)], "unknown format type 'y'"),
+    ];
+
+    for (fmt, args, expected) in bad {
+        let messages = check(fmt, &args);
+        assert_eq!(messages.len(), 1, "{fmt}: {messages:?}");
+        assert!(messages[0].ends_with(expected), "{fmt}: {}", messages[0]);
+    }
+}
+
+#[test]
+fn exponent_specs() {
+    let enumeration = Type::Enumeration {
+        ty: Box::new(Type::I32),
+        valid_values: BTreeMap::from([(1, "ONE".to_string())]),
+    };
+    let types = [
+        Type::Bool,
+        Type::U8,
+        Type::I32,
+        Type::F64,
+        enumeration,
+        Type::Pointer(Box::new(Type::U32)),
+        structure(&[("count", Type::U32), ("ratio", Type::F32)]),
+        array(Type::U16, 4),
+    ];
+
+    // `e` and `E` only differ in the case of the exponent.
+    for ty in types {
+        let lower = check("{:e}", &[("a", ty.clone())]);
+        let upper = check("{:E}", &[("a", ty.clone())]);
+        assert_eq!(lower.is_empty(), upper.is_empty(), "{ty:?}");
+    }
+
+    assert_eq!(
+        check("{:E}", &[("a", Type::Pointer(Box::new(Type::U32)))]).len(),
+        1
+    );
+}
+
+#[test]
+fn bad_placeholders() {
+    let args = [("a", Type::U32), ("b", Type::U32)];
+
+    assert_eq!(
+        check("{} {} {}", &args),
+        ["Placeholder {} has no matching argument, the log has 2 arguments"]
+    );
+    assert_eq!(
+        check("{b}", &args),
+        ["Argument 'a' isn't used by the format string"]
+    );
+    assert!(check("{a} }", &args)[0].starts_with("Invalid format string"));
+    assert!(check("{a:.} {b}", &args)[0].contains("Missing precision"));
+}
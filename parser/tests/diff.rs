@@ -0,0 +1,82 @@
+//! Matching the logs of two elfs, as after code was added above them and some logs were edited.
+
+use cdefmt_encoder::{
+    Encoding,
+    elf::{self, ArgumentType, LogSpec},
+};
+use cdefmt_parser::{
+    Parser, catalog,
+    diff::{self, Change, DEFAULT_MAX_DISTANCE, Field},
+    metadata::Level,
+    r#type::Type,
+};
+
+const U8: &[(&str, ArgumentType)] = &[("value", ArgumentType::Value(Type::U8))];
+const U32: &[(&str, ArgumentType)] = &[("value", ArgumentType::Value(Type::U32))];
+const I32: &[(&str, ArgumentType)] = &[("value", ArgumentType::Value(Type::I32))];
+
+const OLD: &[LogSpec] = &[
+    (Level::Info, "main.c", 10, "boot", U8),
+    (Level::Info, "main.c", 20, "temperature: {}", U32),
+    (Level::Info, "main.c", 30, "old format: {}", U32),
+    (Level::Info, "main.c", 40, "removed: {}", U32),
+    (Level::Info, "main.c", 50, "far: {}", U32),
+    (Level::Info, "other.c", 5, "boot", U8),
+];
+
+/// Five lines were added at the top of `main.c`.
+const NEW: &[LogSpec] = &[
+    (Level::Info, "main.c", 15, "boot", U8),
+    (Level::Warning, "main.c", 25, "temperature: {}", I32),
+    (Level::Info, "main.c", 36, "new format: {}", U32),
+    (Level::Info, "main.c", 80, "moved far: {}", U32),
+    (Level::Info, "main.c", 100, "added: {}", U32),
+    (Level::Info, "other.c", 5, "boot", U8),
+];
+
+/// Describes each change by its kind, the lines of the logs it affects and the differing fields.
+fn summarize(changes: &[Change]) -> Vec<(&'static str, usize, usize, Vec<Field>)> {
+    changes
+        .iter()
+        .map(|change| match change {
+            Change::Added { log } => ("added", 0, log.line, vec![]),
+            Change::Removed { log } => ("removed", log.line, 0, vec![]),
+            Change::Changed { old, new, fields } => ("changed", old.line, new.line, fields.clone()),
+        })
+        .collect()
+}
+
+#[test]
+fn changes() {
+    let (old, _) = elf::build(Encoding::NATIVE, OLD).unwrap();
+    let (new, _) = elf::build(Encoding::NATIVE, NEW).unwrap();
+    let old = catalog::collect(&Parser::new(old.as_slice()).unwrap()).unwrap();
+    let new = catalog::collect(&Parser::new(new.as_slice()).unwrap()).unwrap();
+
+    // Unchanged logs that moved aren't reported, logs that moved further than the maximum
+    // distance can't be told apart from a removed log and an added one.
+    assert_eq!(
+        summarize(&diff::diff(&old, &new, DEFAULT_MAX_DISTANCE)),
+        [
+            ("changed", 20, 25, vec![Field::Level, Field::Arguments]),
+            ("changed", 30, 36, vec![Field::Fmt]),
+            ("removed", 40, 0, vec![]),
+            ("removed", 50, 0, vec![]),
+            ("added", 0, 80, vec![]),
+            ("added", 0, 100, vec![]),
+        ]
+    );
+
+    assert_eq!(
+        summarize(&diff::diff(&old, &new, 30)),
+        [
+            ("changed", 20, 25, vec![Field::Level, Field::Arguments]),
+            ("changed", 30, 36, vec![Field::Fmt]),
+            ("removed", 40, 0, vec![]),
+            ("changed", 50, 80, vec![Field::Fmt]),
+            ("added", 0, 100, vec![]),
+        ]
+    );
+
+    assert!(diff::diff(&old, &old, DEFAULT_MAX_DISTANCE).is_empty());
+}
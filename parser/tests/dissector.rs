@@ -0,0 +1,131 @@
+//! Generating the Wireshark dissector, compared to the golden file `tests/golden/dissector.lua`.
+//!
+//! Run with `CDEFMT_BLESS=1` to rewrite the golden file.
+
+use std::{collections::BTreeMap, env, fs};
+
+use cdefmt_encoder::{
+    Encoding,
+    elf::{ArgumentType, ElfBuilder},
+};
+use cdefmt_parser::{
+    Error, Parser,
+    dissector::{LINKTYPE_USER0, write_lua},
+    metadata::Level,
+    r#type::{StructureMember, Type},
+};
+
+const GOLDEN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/dissector.lua");
+
+fn elf() -> Vec<u8> {
+    let mut builder = ElfBuilder::new(Encoding {
+        little_endian: true,
+        address_size: 4,
+    })
+    .with_build_id([0x5a; 20]);
+
+    let point = Type::Structure {
+        members: vec![
+            StructureMember {
+                offset: 0,
+                name: "x".to_string(),
+                ty: Type::I16,
+            },
+            StructureMember {
+                offset: 2,
+                name: "y".to_string(),
+                ty: Type::I16,
+            },
+        ],
+        size: 4,
+    };
+    let state = Type::Enumeration {
+        ty: Box::new(Type::U32),
+        valid_values: BTreeMap::from([(0, "IDLE".to_string()), (1, "BUSY".to_string())]),
+    };
+
+    let samples = Type::Array {
+        ty: Box::new(Type::F32),
+        lengths: vec![2, 3],
+    };
+    let pointer = Type::Pointer(Box::new(Type::U32));
+
+    builder.add_logs(&[
+        (
+            Level::Info,
+            "/build/src/main.c",
+            12,
+            "moved to {} in state {}",
+            &[
+                ("point", ArgumentType::Value(point)),
+                ("state", ArgumentType::Value(state)),
+            ],
+        ),
+        (
+            Level::Warning,
+            "/build/src/\"quoted\".c",
+            40,
+            "name: {:s}\tsamples: {}",
+            &[
+                ("name", ArgumentType::DynamicArray(Type::I8)),
+                ("samples", ArgumentType::Value(samples)),
+            ],
+        ),
+        (
+            Level::Debug,
+            "/build/src/main.c",
+            50,
+            "{} {} {}",
+            &[
+                ("flag", ArgumentType::Value(Type::Bool)),
+                ("big", ArgumentType::Value(Type::I64)),
+                ("ptr", ArgumentType::Value(pointer)),
+            ],
+        ),
+    ]);
+
+    builder.build().unwrap()
+}
+
+#[test]
+fn golden() {
+    let elf = elf();
+    let parser = Parser::new(elf.as_slice()).unwrap();
+    let mut lua = Vec::new();
+    write_lua(&parser, LINKTYPE_USER0 + 2, &mut lua).unwrap();
+    let lua = String::from_utf8(lua).unwrap();
+
+    if env::var_os("CDEFMT_BLESS").is_some() {
+        fs::write(GOLDEN, &lua).unwrap();
+        return;
+    }
+
+    let golden = fs::read_to_string(GOLDEN).unwrap_or_else(|e| {
+        panic!("Could not read {GOLDEN}: {e}, run with CDEFMT_BLESS=1 to create it")
+    });
+    // Not using assert_eq, the whole dissector would be printed twice.
+    if let Some((number, (expected, found))) = golden
+        .lines()
+        .zip(lua.lines())
+        .enumerate()
+        .find(|(_, (expected, found))| expected != found)
+    {
+        panic!(
+            "line {}: expected {expected:?}, found {found:?}",
+            number + 1
+        );
+    }
+    assert_eq!(golden.lines().count(), lua.lines().count());
+}
+
+#[test]
+fn invalid_link_type() {
+    let elf = elf();
+    let parser = Parser::new(elf.as_slice()).unwrap();
+    for link_type in [LINKTYPE_USER0 - 1, LINKTYPE_USER0 + 16] {
+        assert!(matches!(
+            write_lua(&parser, link_type, Vec::new()),
+            Err(Error::InvalidLinkType(l)) if l == link_type
+        ));
+    }
+}
@@ -0,0 +1,241 @@
+-- Wireshark dissector of cdefmt logs, generated from the elf with build ID 5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a.
+-- Log ids change with every build, regenerate it whenever the elf is rebuilt.
+
+local ADDRESS_SIZE = 4
+local LITTLE_ENDIAN = true
+local WTAP_ENCAP = wtap.USER2
+
+local logs = {
+    [0x0] = {
+        level = "Error", file = "cdefmt_init.c", line = 1,
+        fmt = "cdefmt init, build-id: {}",
+        args = {
+            { name = "build_id", type = { kind = "array", count = 20, type = { kind = "u8" } } },
+        },
+    },
+    [0x4e] = {
+        level = "Info", file = "/build/src/main.c", line = 12,
+        fmt = "moved to {} in state {}",
+        args = {
+            { name = "point", type = { kind = "struct", size = 4, members = { { name = "x", offset = 0, type = { kind = "i16" } }, { name = "y", offset = 2, type = { kind = "i16" } }, } } },
+            { name = "state", type = { kind = "enum", type = { kind = "u32" }, values = { ["0"] = "IDLE", ["1"] = "BUSY", } } },
+        },
+    },
+    [0xa5] = {
+        level = "Warning", file = "/build/src/\"quoted\".c", line = 40,
+        fmt = "name: {:s}\tsamples: {}",
+        args = {
+            { name = "name", type = { kind = "dynamic", size = 4, length = { kind = "u32" }, type = { kind = "i8" } } },
+            { name = "samples", type = { kind = "array", count = 6, type = { kind = "f32" } } },
+        },
+    },
+    [0x100] = {
+        level = "Debug", file = "/build/src/main.c", line = 50,
+        fmt = "{} {} {}",
+        args = {
+            { name = "flag", type = { kind = "bool" } },
+            { name = "big", type = { kind = "i64" } },
+            { name = "ptr", type = { kind = "pointer", type = { kind = "u32" } } },
+        },
+    },
+}
+
+local cdefmt = Proto("cdefmt", "cdefmt log")
+
+local fields = {
+    id = ProtoField.uint64("cdefmt.id", "ID", base.HEX),
+    level = ProtoField.string("cdefmt.level", "Level"),
+    file = ProtoField.string("cdefmt.file", "File"),
+    line = ProtoField.uint32("cdefmt.line", "Line"),
+    fmt = ProtoField.string("cdefmt.fmt", "Format"),
+    message = ProtoField.string("cdefmt.message", "Message"),
+}
+cdefmt.fields = { fields.id, fields.level, fields.file, fields.line, fields.fmt, fields.message }
+
+local experts = {
+    unknown = ProtoExpert.new("cdefmt.unknown", "Unknown log id, was the dissector generated from this elf?",
+        expert.group.UNDECODED, expert.severity.WARN),
+    malformed = ProtoExpert.new("cdefmt.malformed", "Malformed log frame",
+        expert.group.MALFORMED, expert.severity.ERROR),
+}
+cdefmt.experts = { experts.unknown, experts.malformed }
+
+local function integer(size, signed)
+    local method = (LITTLE_ENDIAN and "le_" or "") .. (signed and "int" or "uint") .. (size == 8 and "64" or "")
+    return { size = size, read = function(range) return range[method](range) end }
+end
+
+local function float(size)
+    local method = LITTLE_ENDIAN and "le_float" or "float"
+    return { size = size, read = function(range) return range[method](range) end }
+end
+
+local SCALARS = {
+    bool = { size = 1, read = function(range) return range:uint() ~= 0 end },
+    u8 = integer(1, false),
+    u16 = integer(2, false),
+    u32 = integer(4, false),
+    u64 = integer(8, false),
+    i8 = integer(1, true),
+    i16 = integer(2, true),
+    i32 = integer(4, true),
+    i64 = integer(8, true),
+    f32 = float(4),
+    f64 = float(8),
+}
+
+local function size_of(ty)
+    local scalar = SCALARS[ty.kind]
+    if scalar then
+        return scalar.size
+    elseif ty.kind == "enum" or ty.kind == "pointer" then
+        return size_of(ty.type)
+    elseif ty.kind == "array" then
+        return ty.count * size_of(ty.type)
+    end
+    return ty.size
+end
+
+-- Adds the value of type `ty` at `offset` to `tree`, returns the value's text.
+local function dissect(tvb, offset, ty, tree, name)
+    local scalar = SCALARS[ty.kind]
+    if scalar then
+        local range = tvb(offset, scalar.size)
+        local text = tostring(scalar.read(range))
+        tree:add(range, name .. ": " .. text)
+        return text
+    elseif ty.kind == "enum" or ty.kind == "pointer" then
+        local inner = SCALARS[ty.type.kind]
+        local range = tvb(offset, inner.size)
+        local value = inner.read(range)
+        local text
+        if ty.kind == "enum" then
+            text = ty.values[tostring(value)] or tostring(value)
+        elseif type(value) == "number" then
+            text = string.format("0x%x", value)
+        else
+            text = "0x" .. value:tohex()
+        end
+        tree:add(range, name .. ": " .. text)
+        return text
+    end
+
+    local parts = {}
+    local subtree = tree:add(tvb(offset, size_of(ty)), name)
+    if ty.kind == "struct" then
+        for _, member in ipairs(ty.members) do
+            local text = dissect(tvb, offset + member.offset, member.type, subtree, member.name)
+            parts[#parts + 1] = member.name .. ": " .. text
+        end
+        parts = "{ " .. table.concat(parts, ", ") .. " }"
+    else
+        local size = size_of(ty.type)
+        for i = 0, ty.count - 1 do
+            parts[#parts + 1] = dissect(tvb, offset + i * size, ty.type, subtree, "[" .. i .. "]")
+        end
+        parts = "[" .. table.concat(parts, ", ") .. "]"
+    end
+    subtree:set_text(name .. ": " .. parts)
+    return parts
+end
+
+-- Adds the log's arguments to `tree`, returns the arguments' texts.
+local function dissect_arguments(tvb, log, tree)
+    local values = {}
+    local dynamic = {}
+    local offset = ADDRESS_SIZE
+
+    for i, arg in ipairs(log.args) do
+        if arg.type.kind == "dynamic" then
+            local length = SCALARS[arg.type.length.kind]
+            local bytes = length.read(tvb(offset, length.size))
+            dynamic[#dynamic + 1] = { index = i, bytes = tonumber(tostring(bytes)) }
+        else
+            values[i] = dissect(tvb, offset, arg.type, tree, arg.name)
+        end
+        offset = offset + size_of(arg.type)
+    end
+
+    -- The elements of dynamic arrays follow the fixed size arguments, in order.
+    for _, array in ipairs(dynamic) do
+        local arg = log.args[array.index]
+        local size = size_of(arg.type.type)
+        local count = size > 0 and math.floor(array.bytes / size) or 0
+        local ty = { kind = "array", count = count, type = arg.type.type }
+        values[array.index] = dissect(tvb, offset, ty, tree, arg.name)
+        offset = offset + count * size
+    end
+
+    return values
+end
+
+-- Replaces the placeholders of the log's format string with the arguments' texts, their format
+-- specs are ignored.
+local function format(log, values)
+    local next_index = 0
+    local text = log.fmt:gsub("{{", "\1"):gsub("}}", "\2")
+
+    text = text:gsub("{([^:}]*)[^}]*}", function(argument)
+        argument = argument:match("^%s*(.-)%s*$")
+        local index
+        if argument == "" then
+            next_index = next_index + 1
+            index = next_index
+        elseif argument:match("^%d+$") then
+            index = tonumber(argument) + 1
+        else
+            for i, arg in ipairs(log.args or {}) do
+                if arg.name == argument then
+                    index = i
+                end
+            end
+        end
+        -- Unknown arguments are left as they are.
+        return index and values[index]
+    end)
+
+    return (text:gsub("\1", "{"):gsub("\2", "}"))
+end
+
+function cdefmt.dissector(tvb, pinfo, tree)
+    pinfo.cols.protocol = "CDEFMT"
+    local subtree = tree:add(cdefmt, tvb())
+
+    if tvb:len() < ADDRESS_SIZE then
+        subtree:add_proto_expert_info(experts.malformed, "The frame is shorter than a log id")
+        return
+    end
+
+    local id_range = tvb(0, ADDRESS_SIZE)
+    local id = tonumber(tostring(integer(ADDRESS_SIZE, false).read(id_range)))
+    subtree:add(fields.id, id_range, UInt64.new(id))
+
+    local log = logs[id]
+    if not log then
+        pinfo.cols.info = string.format("Unknown log 0x%x", id)
+        subtree:add_proto_expert_info(experts.unknown)
+        return
+    end
+
+    subtree:add(fields.level, log.level)
+    subtree:add(fields.file, log.file)
+    subtree:add(fields.line, log.line)
+    subtree:add(fields.fmt, log.fmt)
+
+    local values = {}
+    if log.args then
+        local ok, err = pcall(function()
+            values = dissect_arguments(tvb, log, subtree)
+        end)
+        if not ok then
+            subtree:add_proto_expert_info(experts.malformed, tostring(err))
+        end
+    end
+
+    local message = format(log, values)
+    subtree:add(fields.message, message)
+    subtree:append_text(", " .. message)
+    pinfo.cols.info = string.format("[%s] %s:%d %s", log.level, log.file, log.line, message)
+end
+
+DissectorTable.get("wtap_encap"):add(WTAP_ENCAP, cdefmt)
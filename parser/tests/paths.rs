@@ -0,0 +1,139 @@
+//! Remapping source paths, and matching logs to the compilation units describing their arguments.
+
+use cdefmt_encoder::{
+    Encoding,
+    elf::{ArgumentType, ElfBuilder},
+};
+use cdefmt_parser::{Parser, metadata::Level, path::PathMap, r#type::Type};
+
+#[test]
+fn remap() {
+    let mut path_map = PathMap::new();
+    path_map.add("/build", "/home/me/project");
+    path_map.add("C:\\ci\\", "/mnt/ci/");
+
+    assert_eq!(
+        path_map.remap("/build/src/main.c"),
+        "/home/me/project/src/main.c"
+    );
+    assert_eq!(path_map.remap("/build"), "/home/me/project");
+    assert_eq!(path_map.remap("C:\\ci\\main.c"), "/mnt/ci/main.c");
+
+    // Only whole components match.
+    assert_eq!(path_map.remap("/buildroot/main.c"), "/buildroot/main.c");
+    assert_eq!(path_map.remap("/other/build/main.c"), "/other/build/main.c");
+
+    // The last rule added wins.
+    path_map.add("/build/src", "/src");
+    assert_eq!(path_map.remap("/build/src/main.c"), "/src/main.c");
+    assert_eq!(
+        path_map.remap("/build/include/log.h"),
+        "/home/me/project/include/log.h"
+    );
+}
+
+/// Adds a log in each `(file, compilation unit name)` pair, returning whether the type of each
+/// log's arguments was found.
+fn find_types(files: &[(&str, &str)], path_map: PathMap) -> Vec<bool> {
+    let mut builder = ElfBuilder::new(Encoding::NATIVE);
+    for (file, unit) in files {
+        builder.add_log(
+            Level::Info,
+            file,
+            1,
+            "{}",
+            &[("a", ArgumentType::Value(Type::U32))],
+        );
+        builder.set_compilation_unit(file, unit);
+    }
+    let elf = builder.build().unwrap();
+
+    let mut parser = Parser::new(elf.as_slice()).unwrap();
+    parser.set_path_map(path_map);
+    parser
+        .iter_logs()
+        .skip(1)
+        .map(|log| log.unwrap().1.is_some())
+        .collect()
+}
+
+#[test]
+fn compilation_unit_by_path() {
+    // Relative to the compilation directory, or equal after resolving `.` and `..`.
+    assert_eq!(
+        find_types(&[("/src/./app/../main.c", "/src/main.c")], PathMap::new()),
+        [true]
+    );
+
+    // The unit was built using `-fdebug-prefix-map=/home/me=/build`.
+    let mut path_map = PathMap::new();
+    path_map.add("/build", "/home/me");
+    assert_eq!(
+        find_types(&[("/home/me/main.c", "/build/main.c")], path_map),
+        [true]
+    );
+}
+
+#[test]
+fn compilation_unit_by_suffix() {
+    // The longest suffix wins, even if other units share the file name.
+    assert_eq!(
+        find_types(
+            &[
+                ("/home/me/app/main.c", "/ci/app/main.c"),
+                ("/home/me/lib/main.c", "/ci/lib/main.c"),
+            ],
+            PathMap::new()
+        ),
+        [true, true]
+    );
+
+    // The file and its directory.
+    assert_eq!(
+        find_types(&[("/home/me/app/main.c", "/ci/app/main.c")], PathMap::new()),
+        [true]
+    );
+
+    // Nothing in common but the file name, even though it's unique.
+    assert_eq!(
+        find_types(&[("/home/me/main.c", "/ci/main.c")], PathMap::new()),
+        [false]
+    );
+
+    // Not even the file name matches.
+    assert_eq!(
+        find_types(&[("/home/me/main.c", "/ci/app.c")], PathMap::new()),
+        [false]
+    );
+}
+
+#[test]
+fn compilation_unit_ambiguous_suffix() {
+    // Both units share just the file name with both logs, either could describe the arguments.
+    assert_eq!(
+        find_types(
+            &[
+                ("/home/me/main.c", "/ci/a/main.c"),
+                ("/other/main.c", "/ci/b/main.c")
+            ],
+            PathMap::new()
+        ),
+        [false, false]
+    );
+}
+
+#[test]
+fn compilation_unit_shared_file_name() {
+    // Both units are named `main.c`, the second log's unit shares only the file name with it, so
+    // its arguments are left without a type instead of guessing.
+    assert_eq!(
+        find_types(
+            &[
+                ("/home/me/app/main.c", "/ci/app/main.c"),
+                ("/home/me/lib/main.c", "/ci/main.c")
+            ],
+            PathMap::new()
+        ),
+        [true, false]
+    );
+}